@@ -0,0 +1,153 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use crate::noise::Noise;
+use crate::shaders::{procedural_bump, procedural_shader, ProceduralFn, ProceduralSurface, SurfaceMaterial};
+use crate::texture::Texture;
+
+// Horneado de los shaders procedurales a texturas: para exportarlos a otras herramientas
+// y para que el renderer lea el color de una textura en vez de evaluar el ruido.
+// Todas las direcciones están en el espacio del objeto, el mismo en que se evalúan los shaders.
+
+// Sufijos de las caras de un cubemap, en el orden de bake_cubemap
+pub const CUBE_FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+// Punto de la esfera unitaria que corresponde a (u, v), con la misma parametrización
+// que primitives::uv_sphere: v = 0 es el polo +Y y u recorre el ángulo alrededor de Y
+pub fn equirectangular_direction(u: f32, v: f32) -> Vec3 {
+    let (sin_phi, cos_phi) = (v * PI).sin_cos();
+    let (sin_theta, cos_theta) = (u * 2.0 * PI).sin_cos();
+    Vec3::new(sin_phi * cos_theta, cos_phi, sin_phi * sin_theta)
+}
+
+// Inversa de equirectangular_direction, para muestrear una textura horneada
+pub fn equirectangular_uv(direction: &Vec3) -> Vec2 {
+    let d = direction.normalize();
+    let u = d.z.atan2(d.x) / (2.0 * PI);
+    let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+    Vec2::new(u.rem_euclid(1.0), v)
+}
+
+// Dirección de (u, v) en la cara `face` de un cubemap, con la convención de OpenGL
+// (cada cara vista desde el centro, v hacia abajo)
+pub fn cubemap_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    let (s, t) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    let d = match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    };
+    d.normalize()
+}
+
+// Material horneado de una superficie: albedo y rugosidad siempre; emisión y metal solo si
+// el shader los usa. La rugosidad y el metal van en escala de grises. `bump` guarda el
+// gradiente del relieve fino (ver shaders::procedural_bump), con componentes negativas.
+pub struct BakedSurface {
+    pub albedo: Texture,
+    pub roughness: Texture,
+    pub metallic: Option<Texture>,
+    pub emission: Option<Texture>,
+    pub bump: Option<Texture>,
+}
+
+impl BakedSurface {
+    pub fn sample(&self, uv: Vec2) -> SurfaceMaterial {
+        let gray = |texture: &Texture| texture.sample(uv).x;
+        SurfaceMaterial::diffuse(self.albedo.sample(uv))
+            .with_roughness(gray(&self.roughness))
+            .with_metallic(self.metallic.as_ref().map_or(0.0, gray))
+            .with_emission(self.emission.as_ref().map_or(Vec3::zeros(), |texture| texture.sample(uv)))
+    }
+}
+
+// Evalúa `shader` (y el relieve fino de la superficie, si tiene) en el centro de cada píxel,
+// con la dirección que da `direction(u, v)`
+fn bake(surface: &ProceduralSurface, shader: ProceduralFn, time: f32, width: usize, height: usize, direction: impl Fn(f32, f32) -> Vec3) -> BakedSurface {
+    let noise = Noise::new(surface.seed);
+    let bump_fn = procedural_bump(surface.shader_type);
+    let mut bump = bump_fn.map(|_| Texture::new(width, height));
+    let mut albedo = Texture::new(width, height);
+    let mut roughness = Texture::new(width, height);
+    let mut metallic = Texture::new(width, height);
+    let mut emission = Texture::new(width, height);
+
+    for y in 0..height {
+        let v = (y as f32 + 0.5) / height as f32;
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let point = direction(u, v);
+            let material = shader(point, time, &noise, &surface.planet);
+            albedo.set(x, y, material.albedo);
+            roughness.set(x, y, Vec3::repeat(material.roughness));
+            metallic.set(x, y, Vec3::repeat(material.metallic));
+            emission.set(x, y, material.emission);
+            if let (Some(bump), Some(bump_fn)) = (&mut bump, bump_fn) {
+                bump.set(x, y, bump_fn(point, &noise));
+            }
+        }
+    }
+
+    let used = |texture: Texture| texture.pixels.iter().any(|pixel| pixel.max() > 0.0).then_some(texture);
+    BakedSurface { albedo, roughness, metallic: used(metallic), emission: used(emission), bump }
+}
+
+// Texturas equirectangulares de width x (width / 2) de la superficie en el instante `time`.
+// Devuelve None si el shader no es procedural.
+pub fn bake_equirectangular(surface: ProceduralSurface, time: f32, width: usize) -> Option<BakedSurface> {
+    let shader = procedural_shader(surface.shader_type)?;
+    let width = width.max(2);
+    Some(bake(&surface, shader, time, width, width / 2, equirectangular_direction))
+}
+
+// Las seis caras de size x size, en el orden de CUBE_FACES
+pub fn bake_cubemap(surface: ProceduralSurface, time: f32, size: usize) -> Option<Vec<BakedSurface>> {
+    let shader = procedural_shader(surface.shader_type)?;
+    let size = size.max(1);
+    Some((0..6).map(|face| bake(&surface, shader, time, size, size, |u, v| cubemap_direction(face, u, v))).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_shaders_with_fine_relief_bake_a_bump_map() {
+        let rocky = ProceduralSurface::new(1, 4);
+        let baked = bake_equirectangular(rocky, 0.0, 64).unwrap();
+        let bump = baked.bump.as_ref().unwrap();
+
+        // Cada píxel guarda el gradiente en la dirección de su centro
+        let (x, y) = (13, 9);
+        let point = equirectangular_direction((x as f32 + 0.5) / 64.0, (y as f32 + 0.5) / 32.0);
+        let expected = procedural_bump(1).unwrap()(point, &Noise::new(4));
+        assert!((bump.get(x, y) - expected).magnitude() < 1e-6);
+
+        assert!(bake_equirectangular(ProceduralSurface::new(2, 4), 0.0, 64).unwrap().bump.is_none());
+    }
+
+    #[test]
+    fn sampling_the_bake_matches_the_shader() {
+        // La estrella (emisión), el planeta rocoso (luces de ciudades) y el gaseoso, leídos como
+        // los lee fragment_shader: con la UV de la dirección, en el centro de algunos píxeles
+        for shader_type in [0, 1, 2] {
+            let surface = ProceduralSurface::new(shader_type, 7);
+            let baked = bake_equirectangular(surface, 2.5, 64).unwrap();
+            let shader = procedural_shader(shader_type).unwrap();
+            let noise = Noise::new(7);
+
+            for (x, y) in [(0, 3), (17, 11), (40, 16), (63, 28)] {
+                let direction = equirectangular_direction((x as f32 + 0.5) / 64.0, (y as f32 + 0.5) / 32.0);
+                let expected = shader(direction, 2.5, &noise, &surface.planet);
+                let sampled = baked.sample(equirectangular_uv(&direction));
+
+                assert!((sampled.albedo - expected.albedo).magnitude() < 1e-3, "shader {} en ({}, {})", shader_type, x, y);
+                assert!((sampled.roughness - expected.roughness).abs() < 1e-3);
+                assert!((sampled.metallic - expected.metallic).abs() < 1e-3);
+                assert!((sampled.emission - expected.emission).magnitude() < 1e-3);
+            }
+        }
+    }
+}
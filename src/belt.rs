@@ -0,0 +1,139 @@
+use nalgebra_glm::{Vec3, Vec4};
+use std::f32::consts::PI;
+use crate::noise::{Noise, Rng};
+use crate::primitives;
+use crate::renderer::Instance;
+use crate::vertex::Vertex;
+use crate::create_model_matrix;
+
+// Cinturón de asteroides: miles de rocas entre dos órbitas que comparten unas pocas mallas y
+// se dibujan con un render instanciado por malla (ver renderer::render_instanced)
+
+// Mallas distintas entre las que se reparten los asteroides
+pub const ASTEROID_SHAPES: u32 = 4;
+// Mayor estiramiento de un asteroide a lo largo de uno de sus ejes; el menor es 2 - MAX_STRETCH
+pub const MAX_STRETCH: f32 = 1.3;
+
+struct Asteroid {
+    shape: u32,
+    orbit_radius: f32,
+    phase: f32,
+    speed: f32,
+    height: f32,
+    size: f32,
+    rotation: Vec3,
+    spin: Vec3,
+    params: Vec4,
+    // Escala por eje que aplica el vertex shader, así cuatro mallas dan formas muy distintas
+    stretch: Vec3,
+}
+
+pub struct AsteroidBelt {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub seed: u32,
+    asteroids: Vec<Asteroid>,
+}
+
+impl AsteroidBelt {
+    // `count` asteroides entre las órbitas de radio `inner_radius` y `outer_radius`, casi
+    // todos pequeños, cada uno con su órbita, su giro y su color
+    pub fn new(inner_radius: f32, outer_radius: f32, count: usize, seed: u32) -> Self {
+        let mut rng = Rng::new(Noise::new(seed));
+        let width = outer_radius - inner_radius;
+        let stretch = |rng: &mut Rng| rng.range(2.0 - MAX_STRETCH, MAX_STRETCH);
+        let asteroids = (0..count)
+            .map(|_| {
+                let orbit_radius = rng.range(inner_radius, outer_radius);
+                // Más espeso en el medio del cinturón
+                let middle = 1.0 - ((orbit_radius - inner_radius) / width * 2.0 - 1.0).abs();
+                Asteroid {
+                    shape: rng.range_int(0, ASTEROID_SHAPES - 1),
+                    orbit_radius,
+                    phase: rng.range(0.0, 2.0 * PI),
+                    // Tercera ley de Kepler, con la misma escala que system.rs
+                    speed: 0.3 * (8.0 / orbit_radius).powf(1.5),
+                    height: rng.range(-1.0, 1.0) * width * 0.15 * (0.3 + 0.7 * middle),
+                    size: 0.03 + 0.1 * rng.next().powi(3),
+                    rotation: Vec3::new(rng.range(0.0, 2.0 * PI), rng.range(0.0, 2.0 * PI), rng.range(0.0, 2.0 * PI)),
+                    spin: Vec3::new(rng.range(-1.5, 1.5), rng.range(-1.5, 1.5), rng.range(-1.5, 1.5)),
+                    // x = de gris a marrón, y = brillo
+                    params: Vec4::new(rng.next(), rng.range(0.7, 1.1), 0.0, 0.0),
+                    stretch: Vec3::new(stretch(&mut rng), stretch(&mut rng), stretch(&mut rng)),
+                }
+            })
+            .collect();
+
+        AsteroidBelt { inner_radius, outer_radius, seed, asteroids }
+    }
+
+    // Una malla por forma, con la semilla del cinturón
+    pub fn meshes(&self) -> Vec<(Vec<Vertex>, Vec<u32>)> {
+        let noise = Noise::new(self.seed);
+        (0..ASTEROID_SHAPES).map(|shape| asteroid_mesh(&noise.derive(shape))).collect()
+    }
+
+    // Copias de cada forma en el instante `time`, en el mismo orden que `meshes`. El id de
+    // cada copia es el índice del asteroide en el cinturón, no en la lista de su forma.
+    pub fn instances(&self, time: f32) -> Vec<Vec<Instance>> {
+        let mut instances = vec![Vec::new(); ASTEROID_SHAPES as usize];
+        for (id, asteroid) in self.asteroids.iter().enumerate() {
+            let angle = asteroid.phase + time * asteroid.speed;
+            let position = Vec3::new(angle.cos() * asteroid.orbit_radius, asteroid.height, angle.sin() * asteroid.orbit_radius);
+            instances[asteroid.shape as usize].push(Instance {
+                model_matrix: create_model_matrix(position, asteroid.size, asteroid.rotation + asteroid.spin * time),
+                id: id as u32,
+                params: asteroid.params,
+                stretch: asteroid.stretch,
+            });
+        }
+        instances
+    }
+}
+
+// Roca irregular: una icoesfera cuyos vértices se alejan o acercan al centro según un fBm de
+// su dirección, con la normal recalculada por diferencias finitas. Los vértices duplicados en
+// la costura de UV quedan en el mismo lugar porque el relieve solo depende de la dirección.
+pub fn asteroid_mesh(noise: &Noise) -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, indices) = primitives::icosphere(1.0, 2);
+    let surface = |direction: Vec3| {
+        let direction = direction.normalize();
+        let lumps = noise.fbm(direction * 1.3, 3, 0.5, 2.0) * 0.35;
+        let bumps = noise.derive(1).fbm(direction * 4.0, 2, 0.5, 2.0) * 0.08;
+        direction * (1.0 + lumps + bumps).max(0.4)
+    };
+
+    for vertex in vertices.iter_mut() {
+        let n = vertex.position.normalize();
+        let axis = if n.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        let t = n.cross(&axis).normalize();
+        let b = n.cross(&t);
+        let epsilon = 0.01;
+
+        let center = surface(n);
+        let normal = (surface(n + t * epsilon) - center).cross(&(surface(n + b * epsilon) - center));
+        let normal = if normal.dot(&n) < 0.0 { -normal } else { normal };
+        vertex.position = center;
+        vertex.normal = normal.normalize();
+    }
+
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_ids_are_unique_across_shapes() {
+        let belt = AsteroidBelt::new(10.0, 14.0, 200, 9);
+        let mut ids: Vec<u32> = belt.instances(0.0).iter().flatten().map(|instance| instance.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (0..200).collect::<Vec<u32>>());
+
+        // El estiramiento es el del asteroide, el mismo en cualquier instante
+        let stretches = |time| belt.instances(time).iter().flatten().map(|instance| instance.stretch).collect::<Vec<_>>();
+        assert_eq!(stretches(0.0), stretches(5.0));
+        assert!(stretches(0.0).iter().all(|s| s.iter().all(|&axis| (2.0 - MAX_STRETCH..=MAX_STRETCH).contains(&axis))));
+    }
+}
@@ -0,0 +1,200 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::vertex::Vertex;
+
+// Volúmenes envolventes en espacio de objeto y prueba contra el frustum de la cámara
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3>) -> Self {
+        let mut min = Vec3::repeat(f32::INFINITY);
+        let mut max = Vec3::repeat(f32::NEG_INFINITY);
+        for point in points {
+            min = min.inf(point);
+            max = max.sup(point);
+        }
+
+        if min.x > max.x {
+            // Sin puntos: caja vacía en el origen
+            return Aabb { min: Vec3::zeros(), max: Vec3::zeros() };
+        }
+        Aabb { min, max }
+    }
+
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        Aabb::from_points(vertices.iter().map(|v| &v.position))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z), Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z), Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z), Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z), Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    // Caja alineada a los ejes de mundo que contiene a esta caja transformada
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        let corners: Vec<Vec3> = self.corners().iter().map(|c| (matrix * c.push(1.0)).xyz()).collect();
+        Aabb::from_points(&corners)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    // Centro de la caja envolvente y distancia máxima a él
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3> + Clone) -> Self {
+        let center = Aabb::from_points(points.clone()).center();
+        let radius = points
+            .into_iter()
+            .map(|p| (p - center).magnitude())
+            .fold(0.0, f32::max);
+        BoundingSphere { center, radius }
+    }
+
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        BoundingSphere::from_points(vertices.iter().map(|v| &v.position))
+    }
+
+    // Esfera en espacio de mundo; con escala no uniforme se usa el eje más grande
+    pub fn transformed(&self, matrix: &Mat4) -> BoundingSphere {
+        let scale = (0..3)
+            .map(|i| matrix.column(i).xyz().magnitude())
+            .fold(0.0, f32::max);
+        BoundingSphere {
+            center: (matrix * self.center.push(1.0)).xyz(),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+// Seis planos (normal hacia adentro, ax + by + cz + d >= 0) extraídos de la matriz
+// vista-proyección, con la convención de OpenGL (z de clip entre -w y w)
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let row = |i: usize| -> Vec4 { view_projection.row(i).transpose() };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2]
+            .map(|p| p / p.xyz().magnitude());
+
+        Frustum { planes }
+    }
+
+    fn distance(plane: &Vec4, point: &Vec3) -> f32 {
+        plane.xyz().dot(point) + plane.w
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Frustum::distance(plane, &sphere.center) >= -sphere.radius)
+    }
+
+    // Prueba del vértice positivo: la esquina más adentro de cada plano
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let positive = Vec3::new(
+                if plane.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            Frustum::distance(plane, &positive) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{ortho, perspective};
+    use std::f32::consts::PI;
+
+    // Cámara en el origen mirando hacia -Z, con los planos laterales en x, y = ±1
+    fn box_frustum() -> Frustum {
+        Frustum::from_matrix(&ortho(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0))
+    }
+
+    fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+        BoundingSphere { center: Vec3::new(x, y, z), radius }
+    }
+
+    fn aabb(center: Vec3, half: f32) -> Aabb {
+        Aabb { min: center - Vec3::repeat(half), max: center + Vec3::repeat(half) }
+    }
+
+    #[test]
+    fn frustum_planes_are_normalized_and_face_inward() {
+        let frustum = Frustum::from_matrix(&perspective(4.0 / 3.0, PI / 3.0, 0.1, 100.0));
+        let inside = Vec3::new(0.0, 0.0, -10.0);
+        for plane in &frustum.planes {
+            assert!((plane.xyz().magnitude() - 1.0).abs() < 1e-5);
+            assert!(Frustum::distance(plane, &inside) > 0.0);
+        }
+
+        // En la proyección ortográfica la distancia al plano x = 1 es exacta
+        let frustum = box_frustum();
+        assert!((Frustum::distance(&frustum.planes[1], &Vec3::new(0.25, 0.0, -5.0)) - 0.75).abs() < 1e-5);
+    }
+
+    #[test]
+    fn spheres_in_front_behind_and_across_a_plane() {
+        let frustum = Frustum::from_matrix(&perspective(4.0 / 3.0, PI / 3.0, 0.1, 100.0));
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.0, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 10.0, 1.0)));
+        // Cruza el plano cercano: la cámara está dentro de la esfera
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, 0.5, 1.0)));
+        // Más allá del plano lejano
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -105.0, 1.0)));
+
+        // Cruzando el plano lateral x = 1 o del todo afuera
+        let frustum = box_frustum();
+        assert!(frustum.intersects_sphere(&sphere(1.5, 0.0, -5.0, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(1.5, 0.0, -5.0, 0.4)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, -1.5, -5.0, 0.4)));
+    }
+
+    #[test]
+    fn boxes_in_front_behind_and_across_a_plane() {
+        let frustum = Frustum::from_matrix(&perspective(4.0 / 3.0, PI / 3.0, 0.1, 100.0));
+        assert!(frustum.intersects_aabb(&aabb(Vec3::new(0.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects_aabb(&aabb(Vec3::new(0.0, 0.0, 10.0), 1.0)));
+        assert!(frustum.intersects_aabb(&aabb(Vec3::new(0.0, 0.0, 0.5), 1.0)));
+
+        let frustum = box_frustum();
+        assert!(frustum.intersects_aabb(&aabb(Vec3::new(1.5, 0.0, -5.0), 0.6)));
+        assert!(!frustum.intersects_aabb(&aabb(Vec3::new(1.5, 0.0, -5.0), 0.4)));
+        assert!(!frustum.intersects_aabb(&aabb(Vec3::new(-1.5, 1.5, -5.0), 0.4)));
+    }
+
+    #[test]
+    fn transformed_volumes_follow_the_matrix() {
+        let matrix = nalgebra_glm::translate(&nalgebra_glm::scaling(&Vec3::new(1.0, 3.0, 1.0)), &Vec3::new(0.0, 1.0, 0.0));
+        let transformed = sphere(0.0, 0.0, 0.0, 1.0).transformed(&matrix);
+        assert!((transformed.center - Vec3::new(0.0, 3.0, 0.0)).magnitude() < 1e-5);
+        assert!((transformed.radius - 3.0).abs() < 1e-5);
+
+        let transformed = aabb(Vec3::zeros(), 1.0).transformed(&matrix);
+        assert!((transformed.min - Vec3::new(-1.0, 0.0, -1.0)).magnitude() < 1e-5);
+        assert!((transformed.max - Vec3::new(1.0, 6.0, 1.0)).magnitude() < 1e-5);
+    }
+}
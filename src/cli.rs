@@ -0,0 +1,265 @@
+use crate::obj::{Material, Mesh, Obj};
+use crate::export::SaveOptions;
+use crate::simplify::SimplifyOptions;
+use crate::normals::NormalMode;
+use crate::primitives;
+use crate::scene::{solar_system, Scene};
+use crate::system::generate_system;
+use crate::bake::{bake_cubemap, bake_equirectangular, BakedSurface, CUBE_FACES};
+use crate::shaders::ProceduralSurface;
+use crate::planet::{PlanetClass, PlanetParams};
+use crate::star::StarClass;
+use crate::noise::{Noise, Rng};
+
+// Subcomandos de línea de comandos. Sin argumentos el programa abre la ventana.
+
+pub fn print_usage() {
+    println!("Uso:");
+    println!("  proyecto_nave                      Abre el sistema solar");
+    println!("  proyecto_nave simplify <entrada> <salida.obj> [--ratio R | --triangles N] [--max-error E] [--normals M]");
+    println!("      Simplifica un modelo (OBJ, STL, PLY o glTF) por colapso de aristas (por defecto --ratio 0.5)");
+    println!("      --normals: file (por defecto), flat, smooth o un ángulo de quiebre en grados");
+    println!("  proyecto_nave export <entrada | sphere | cube | torus | cylinder | scene> <salida.obj> [--shader S] [--seed N] [--random] [--system N] [--bake ANCHO] [--time T] [--segments N]");
+    println!("      Escribe un OBJ con su MTL: un modelo convertido, una primitiva o el sistema solar en el instante T");
+    println!("      --shader: star, rocky, gas, ship, ice, desert, volcanic o su número (primitivas; por defecto rocky)");
+    println!("      --system: exporta el sistema estelar generado con esa semilla en vez del sistema solar");
+    println!("      --bake: hornea los shaders procedurales a texturas equirectangulares TGA de ese ancho");
+    println!("  proyecto_nave bake <shader> <salida.tga> [--seed N] [--random] [--size N] [--time T] [--cubemap]");
+    println!("      Hornea un shader procedural a una textura equirectangular de N x N/2 (por defecto 1024)");
+    println!("      Escribe también la rugosidad en <salida>_roughness.tga y, si el shader los usa,");
+    println!("      el metal en <salida>_metallic.tga y la luz emitida en <salida>_emission.tga");
+    println!("      --cubemap: escribe en cambio seis caras de N x N, <salida>_px.tga ... <salida>_nz.tga");
+    println!("      --seed: semilla del ruido; cada semilla da otra superficie del mismo tipo (por defecto 0)");
+    println!("      --random: genera también la paleta y los parámetros del planeta a partir de la semilla");
+    println!("  proyecto_nave system <semilla> [--list]");
+    println!("      Genera un sistema estelar al azar (estrella, planetas, lunas y anillos) y lo muestra");
+    println!("      --list: solo describe el sistema, sin abrir la ventana");
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args[0].as_str() {
+        "simplify" => simplify(&args[1..]),
+        "export" => export(&args[1..]),
+        "bake" => bake(&args[1..]),
+        "system" => system(&args[1..]),
+        "help" | "--help" | "-h" => {
+            print_usage();
+            Ok(())
+        }
+        other => {
+            print_usage();
+            Err(format!("subcomando desconocido: {}", other))
+        }
+    }
+}
+
+// Valor de una opción `--nombre valor`
+fn option<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            let value = args.get(i + 1).ok_or(format!("falta el valor de {}", name))?;
+            value.parse().map(Some).map_err(|_| format!("valor inválido para {}: {}", name, value))
+        }
+        None => Ok(None),
+    }
+}
+
+fn simplify(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input, output, ..] if !input.starts_with("--") && !output.starts_with("--") => (input, output),
+        _ => return Err("simplify necesita <entrada> <salida.obj>".to_string()),
+    };
+
+    let normals = option::<NormalMode>(args, "--normals")?.unwrap_or_default();
+    let obj = Obj::load_with_normals(input, normals).map_err(|e| e.to_string())?;
+    for warning in obj.warnings() {
+        eprintln!("Aviso: {}", warning);
+    }
+    let before = obj.triangle_count();
+
+    let target = match (option::<usize>(args, "--triangles")?, option::<f32>(args, "--ratio")?) {
+        (Some(triangles), _) => triangles,
+        (None, Some(ratio)) => (before as f32 * ratio.clamp(0.0, 1.0)) as usize,
+        (None, None) => before / 2,
+    };
+    let mut options = SimplifyOptions::new(target);
+    if let Some(max_error) = option::<f32>(args, "--max-error")? {
+        options = options.with_max_error(max_error);
+    }
+
+    let simplified = obj.simplify(&options);
+    simplified.save(output).map_err(|e| e.to_string())?;
+
+    println!("{}: {} -> {} triángulos", output, before, simplified.triangle_count());
+    Ok(())
+}
+
+// Nombre o número de un shader procedural de fragment_shader
+fn shader_type(name: &str) -> Result<u32, String> {
+    let names = ["star", "rocky", "gas", "ship", "ice", "desert", "volcanic"];
+    match names.iter().position(|&n| n == name) {
+        Some(index) => Ok(index as u32),
+        None => name.parse().ok()
+            .filter(|&n: &u32| (n as usize) < names.len())
+            .ok_or(format!("shader desconocido: {}", name)),
+    }
+}
+
+// Superficie de las opciones --seed y --random
+fn surface(args: &[String], shader: u32) -> Result<ProceduralSurface, String> {
+    let seed = option::<u32>(args, "--seed")?.unwrap_or(0);
+    let surface = ProceduralSurface::new(shader, seed);
+    if !args.iter().any(|a| a == "--random") {
+        return Ok(surface);
+    }
+    // Una estrella solo elige al azar su clase espectral
+    if shader == 0 {
+        return Ok(surface.with_planet(StarClass::random(&mut Rng::new(Noise::new(seed))).surface()));
+    }
+    let class = PlanetClass::from_shader_type(shader).ok_or("--random no sirve para la nave")?;
+    Ok(surface.with_planet(PlanetParams::generate(class, seed)))
+}
+
+fn export(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input, output, ..] if !input.starts_with("--") && !output.starts_with("--") => (input, output),
+        _ => return Err("export necesita <entrada | sphere | cube | torus | cylinder | scene> <salida.obj>".to_string()),
+    };
+    let time = option::<f32>(args, "--time")?.unwrap_or(0.0);
+    let segments = option::<u32>(args, "--segments")?.unwrap_or(64).max(3);
+
+    let obj = match input.as_str() {
+        // Primitivas de radio 1; `segments` es la resolución alrededor del eje
+        "sphere" | "cube" | "torus" | "cylinder" => {
+            let shader = shader_type(&option::<String>(args, "--shader")?.unwrap_or("rocky".to_string()))?;
+            let surface = surface(args, shader)?;
+            let (name, (vertices, indices)) = match input.as_str() {
+                "sphere" => ("esfera", primitives::uv_sphere(1.0, segments, segments / 2)),
+                "cube" => ("cubo", primitives::cube(2.0)),
+                "torus" => ("toro", primitives::torus(1.0, 0.3, segments, segments / 2)),
+                _ => ("cilindro", primitives::cylinder(1.0, 2.0, segments)),
+            };
+            let mut mesh = Mesh::from_vertex_arrays(name, &vertices, &indices);
+            mesh.material = Some(0);
+            Obj::from_meshes(vec![mesh]).with_materials(vec![Material::new(name).with_procedural(surface)])
+        }
+        // Cada cuerpo en su posición en el instante `time`, con su shader como material
+        "scene" => {
+            let (vertices, indices) = primitives::uv_sphere(1.0, segments, segments / 2);
+            let scene = match option::<u32>(args, "--system")? {
+                Some(seed) => generate_system(seed),
+                None => solar_system(),
+            };
+            let bodies = scene.bodies;
+            let meshes = bodies.iter().enumerate().map(|(i, body)| {
+                let mut mesh = Mesh::from_vertex_arrays(&body.name, &vertices, &indices);
+                mesh.transform(&body.model_matrix(time));
+                mesh.material = Some(i);
+                mesh
+            }).collect();
+            let materials = bodies.iter().map(|b| Material::new(&b.name).with_procedural(b.surface())).collect();
+            Obj::from_meshes(meshes).with_materials(materials)
+        }
+        _ => {
+            let normals = option::<NormalMode>(args, "--normals")?.unwrap_or_default();
+            let obj = Obj::load_with_normals(input, normals).map_err(|e| e.to_string())?;
+            for warning in obj.warnings() {
+                eprintln!("Aviso: {}", warning);
+            }
+            obj
+        }
+    };
+
+    let mut options = SaveOptions::default();
+    if let Some(width) = option::<usize>(args, "--bake")? {
+        options = options.with_bake(width, time);
+    }
+    obj.save_with_options(output, &options).map_err(|e| e.to_string())?;
+
+    println!("{}: {} objetos, {} triángulos", output, obj.meshes().len(), obj.triangle_count());
+    Ok(())
+}
+
+fn bake(args: &[String]) -> Result<(), String> {
+    let (shader, output) = match args {
+        [shader, output, ..] if !shader.starts_with("--") && !output.starts_with("--") => (shader_type(shader)?, output),
+        _ => return Err("bake necesita <shader> <salida.tga>".to_string()),
+    };
+    let surface = surface(args, shader)?;
+    let size = option::<usize>(args, "--size")?.unwrap_or(1024);
+    let time = option::<f32>(args, "--time")?.unwrap_or(0.0);
+    let save = |texture: &crate::texture::Texture, path: &str| {
+        texture.save_tga(path).map_err(|e| format!("{}: {}", path, e))?;
+        println!("{}: {}x{}", path, texture.width, texture.height);
+        Ok::<(), String>(())
+    };
+
+    // El resto del material va aparte: <salida>_roughness y, si el shader los usa,
+    // <salida>_metallic y <salida>_emission
+    let stem = output.strip_suffix(".tga").unwrap_or(output);
+    let save_all = |baked: &BakedSurface, face: &str| {
+        save(&baked.albedo, &format!("{}{}.tga", stem, face))?;
+        save(&baked.roughness, &format!("{}_roughness{}.tga", stem, face))?;
+        if let Some(metallic) = &baked.metallic {
+            save(metallic, &format!("{}_metallic{}.tga", stem, face))?;
+        }
+        if let Some(emission) = &baked.emission {
+            save(emission, &format!("{}_emission{}.tga", stem, face))?;
+        }
+        Ok::<(), String>(())
+    };
+    if args.iter().any(|a| a == "--cubemap") {
+        let faces = bake_cubemap(surface, time, size).ok_or("el shader no es procedural")?;
+        for (face, suffix) in faces.iter().zip(CUBE_FACES) {
+            save_all(face, &format!("_{}", suffix))?;
+        }
+    } else {
+        let baked = bake_equirectangular(surface, time, size).ok_or("el shader no es procedural")?;
+        save_all(&baked, "")?;
+    }
+    Ok(())
+}
+
+fn system(args: &[String]) -> Result<(), String> {
+    let seed = match args.first() {
+        Some(seed) if !seed.starts_with("--") => seed.parse::<u32>().map_err(|_| format!("semilla inválida: {}", seed))?,
+        _ => return Err("system necesita <semilla>".to_string()),
+    };
+
+    let scene = generate_system(seed);
+    describe(&scene);
+    if !args.iter().any(|a| a == "--list") {
+        crate::view(scene);
+    }
+    Ok(())
+}
+
+// Un cuerpo por línea; las lunas van sangradas bajo su planeta
+fn describe(scene: &Scene) {
+    println!("{}", scene.name);
+    for body in &scene.bodies {
+        let indent = if body.is_moon() { "    " } else { "  " };
+        let kind = match (body.star, body.planet.class) {
+            (Some(star), _) => format!("estrella {}", star.letter()),
+            (None, Some(class)) => format!("{:?}", class),
+            (None, None) => "sin clase".to_string(),
+        };
+        let mut extras = Vec::new();
+        if body.atmosphere.is_some() {
+            extras.push("atmósfera");
+        }
+        if body.rings.is_some() {
+            extras.push("anillos");
+        }
+        println!(
+            "{}{}: {}, radio {:.2}, órbita {:.1}{}{}",
+            indent,
+            body.name,
+            kind,
+            body.scale,
+            body.orbit.radius,
+            if extras.is_empty() { "" } else { ", " },
+            extras.join(", "),
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+use std::io;
+
+// Errores al cargar o guardar modelos. Cada variante lleva el archivo y, cuando se
+// conoce, la línea donde se detectó el problema.
+#[derive(Debug)]
+pub enum ModelError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    UnsupportedFormat {
+        path: String,
+    },
+    Parse {
+        path: String,
+        line: Option<usize>,
+        message: String,
+    },
+    Invalid {
+        path: String,
+        mesh: String,
+        problem: MeshProblem,
+    },
+    MissingMaterial {
+        path: String,
+        line: usize,
+        library: String,
+    },
+    MissingTexture {
+        path: String,
+        material: String,
+        texture: String,
+    },
+}
+
+// Problemas que encuentra la validación de un mesh
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshProblem {
+    // Un índice apunta fuera de la lista de vértices
+    IndexOutOfRange { face: usize, index: u32, vertex_count: usize },
+    // Posición con NaN o infinito
+    NonFinitePosition { vertex: usize },
+    // Caras sin área (se descartan al cargar)
+    DegenerateFaces { count: usize },
+    // La lista de índices no es múltiplo de 3
+    IncompleteFace { indices: usize },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::Io { path, source } => write!(f, "{}: {}", path, source),
+            ModelError::UnsupportedFormat { path } => {
+                write!(f, "{}: formato no soportado (se aceptan .obj, .stl, .ply, .gltf y .glb)", path)
+            }
+            ModelError::Parse { path, line: Some(line), message } => write!(f, "{}:{}: {}", path, line, message),
+            ModelError::Parse { path, line: None, message } => write!(f, "{}: {}", path, message),
+            ModelError::Invalid { path, mesh, problem } => write!(f, "{}: objeto '{}': {}", path, mesh, problem),
+            ModelError::MissingMaterial { path, line, library } => {
+                write!(f, "{}:{}: no se pudo leer la biblioteca de materiales '{}'", path, line, library)
+            }
+            ModelError::MissingTexture { path, material, texture } => {
+                write!(f, "{}: el material '{}' usa la textura '{}', que no existe", path, material, texture)
+            }
+        }
+    }
+}
+
+impl fmt::Display for MeshProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshProblem::IndexOutOfRange { face, index, vertex_count } => {
+                write!(f, "la cara {} usa el vértice {} pero solo hay {}", face, index, vertex_count)
+            }
+            MeshProblem::NonFinitePosition { vertex } => write!(f, "el vértice {} tiene una posición no finita", vertex),
+            MeshProblem::DegenerateFaces { count } => write!(f, "caras sin área descartadas: {}", count),
+            MeshProblem::IncompleteFace { indices } => write!(f, "{} índices no forman triángulos completos", indices),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
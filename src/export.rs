@@ -0,0 +1,236 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::bake::bake_equirectangular;
+use crate::error::ModelError;
+use crate::obj::{Material, Obj};
+use crate::texture::Texture;
+
+// Exportación a OBJ con su MTL, con los shaders procedurales opcionalmente horneados a texturas
+
+// Opciones de Obj::save_with_options
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SaveOptions {
+    // Ancho de las texturas horneadas (el alto es la mitad); None para no hornear
+    pub bake_width: Option<usize>,
+    // Instante en que se evalúan los shaders animados
+    pub time: f32,
+}
+
+impl SaveOptions {
+    pub fn with_bake(mut self, width: usize, time: f32) -> Self {
+        self.bake_width = Some(width);
+        self.time = time;
+        self
+    }
+}
+
+impl Obj {
+    // Escribe el modelo como OBJ con su MTL al lado, deshaciendo el cambio de ejes y de V que hace `load`
+    pub fn save(&self, filename: &str) -> Result<(), ModelError> {
+        self.save_with_options(filename, &SaveOptions::default())
+    }
+
+    // Con `bake_width`, los materiales procedurales se hornean a <nombre>_<material>.tga
+    // y el MTL los referencia con map_Kd. La rugosidad va a <nombre>_<material>_roughness.tga
+    // (map_Pr) y, si el shader los usa, el metal y la emisión a _metallic (map_Pm) y
+    // _emission (map_Ke)
+    pub fn save_with_options(&self, filename: &str, options: &SaveOptions) -> Result<(), ModelError> {
+        let path = Path::new(filename);
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let io_error = |file: &Path| {
+            let path = file.display().to_string();
+            move |source| ModelError::Io { path, source }
+        };
+
+        // Los meshes sin material usan uno blanco agregado al final
+        let mut materials = self.materials().to_vec();
+        if self.meshes().iter().any(|m| m.material.is_none_or(|i| i >= self.materials().len())) {
+            materials.push(Material::new("default"));
+        }
+
+        if let Some(width) = options.bake_width {
+            for material in &mut materials {
+                let Some(baked) = material.procedural.and_then(|s| bake_equirectangular(s, options.time, width)) else {
+                    continue;
+                };
+                let save = |texture: &Texture, suffix: &str| {
+                    let name = format!("{}_{}{}.tga", stem, file_safe(&material.name), suffix);
+                    let texture_path = directory.join(&name);
+                    texture.save_tga(&texture_path.to_string_lossy()).map_err(io_error(&texture_path))?;
+                    Ok::<String, ModelError>(name)
+                };
+                let albedo = save(&baked.albedo, "")?;
+                let roughness = save(&baked.roughness, "_roughness")?;
+                let metallic = baked.metallic.as_ref().map(|texture| save(texture, "_metallic")).transpose()?;
+                let emission = baked.emission.as_ref().map(|texture| save(texture, "_emission")).transpose()?;
+                material.base_color_texture = Some(albedo);
+                material.roughness_texture = Some(roughness);
+                // Los mapas multiplican al valor del material
+                (material.roughness, material.metallic) = (1.0, if metallic.is_some() { 1.0 } else { 0.0 });
+                material.metallic_texture = metallic;
+                material.emission_texture = emission;
+            }
+        }
+
+        let mtl_name = format!("{}.mtl", stem);
+        let mtl_path = directory.join(&mtl_name);
+        write_mtl(&mtl_path, &materials).map_err(io_error(&mtl_path))?;
+        self.write(filename, &mtl_name, &materials).map_err(io_error(path))
+    }
+
+    fn write(&self, filename: &str, mtl_name: &str, materials: &[Material]) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(filename)?);
+        writeln!(out, "# proyecto_nave OBJ export")?;
+        writeln!(out, "mtllib {}", mtl_name)?;
+
+        // Los índices de OBJ empiezan en 1 y son globales para v, vt y vn por separado
+        let (mut v_offset, mut vt_offset, mut vn_offset) = (1, 1, 1);
+        for mesh in self.meshes() {
+            writeln!(out, "o {}", mesh.name)?;
+            let material = mesh.material.and_then(|i| self.materials().get(i)).or(materials.last());
+            if let Some(material) = material {
+                writeln!(out, "usemtl {}", material.name)?;
+            }
+            for v in &mesh.vertices {
+                writeln!(out, "v {:.6} {:.6} {:.6}", v.x, -v.y, -v.z)?;
+            }
+
+            let has_uv = mesh.texcoords.len() == mesh.vertices.len();
+            let has_normals = mesh.normals.len() == mesh.vertices.len();
+            if has_uv {
+                for t in &mesh.texcoords {
+                    writeln!(out, "vt {:.6} {:.6}", t.x, 1.0 - t.y)?;
+                }
+            }
+            if has_normals {
+                for n in &mesh.normals {
+                    writeln!(out, "vn {:.6} {:.6} {:.6}", n.x, -n.y, -n.z)?;
+                }
+            }
+
+            for face in mesh.indices.chunks_exact(3) {
+                write!(out, "f")?;
+                for &index in face {
+                    let i = index as usize;
+                    match (has_uv, has_normals) {
+                        (true, true) => write!(out, " {}/{}/{}", i + v_offset, i + vt_offset, i + vn_offset)?,
+                        (true, false) => write!(out, " {}/{}", i + v_offset, i + vt_offset)?,
+                        (false, true) => write!(out, " {}//{}", i + v_offset, i + vn_offset)?,
+                        (false, false) => write!(out, " {}", i + v_offset)?,
+                    }
+                }
+                writeln!(out)?;
+            }
+
+            v_offset += mesh.vertices.len();
+            if has_uv {
+                vt_offset += mesh.vertices.len();
+            }
+            if has_normals {
+                vn_offset += mesh.vertices.len();
+            }
+        }
+
+        out.flush()
+    }
+}
+
+// Kd y d salen del color base y Ke de la textura de emisión; Ns aproxima la rugosidad igual que al importar, y Pr/Pm
+// son la extensión PBR del formato que leen Blender y otros
+fn write_mtl(path: &Path, materials: &[Material]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "# proyecto_nave MTL export")?;
+
+    for material in materials {
+        let color = material.base_color;
+        let roughness = material.roughness.max(0.01);
+        writeln!(out, "\nnewmtl {}", material.name)?;
+        writeln!(out, "Kd {:.6} {:.6} {:.6}", color.x, color.y, color.z)?;
+        writeln!(out, "d {:.6}", color.w)?;
+        writeln!(out, "Ns {:.6}", (2.0 / (roughness * roughness) - 2.0).min(1000.0))?;
+        writeln!(out, "Pr {:.6}", material.roughness)?;
+        writeln!(out, "Pm {:.6}", material.metallic)?;
+        if let Some(texture) = &material.base_color_texture {
+            writeln!(out, "map_Kd {}", texture)?;
+        }
+        if let Some(texture) = &material.roughness_texture {
+            writeln!(out, "map_Pr {}", texture)?;
+        }
+        if let Some(texture) = &material.metallic_texture {
+            writeln!(out, "map_Pm {}", texture)?;
+        }
+        if let Some(texture) = &material.emission_texture {
+            writeln!(out, "Ke 1.000000 1.000000 1.000000")?;
+            writeln!(out, "map_Ke {}", texture)?;
+        }
+    }
+
+    out.flush()
+}
+
+// Nombre de material apto para usarse en un nombre de archivo
+fn file_safe(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::obj::Mesh;
+    use crate::primitives;
+    use crate::shaders::ProceduralSurface;
+
+    #[test]
+    fn saved_sphere_loads_back_with_its_materials() {
+        let directory = std::env::temp_dir().join(format!("proyecto_nave_export_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("esferas.obj");
+        let path = path.to_str().unwrap();
+
+        // Una esfera con un material procedural horneado y otra sin material
+        let (vertices, indices) = primitives::uv_sphere(1.0, 12, 6);
+        let mut rocky = Mesh::from_vertex_arrays("rocosa", &vertices, &indices);
+        rocky.material = Some(0);
+        let plain = Mesh::from_vertex_arrays("lisa", &vertices, &indices);
+        let material = Material::new("roca").with_procedural(ProceduralSurface::new(1, 3));
+        let obj = Obj::from_meshes(vec![rocky, plain]).with_materials(vec![material]);
+        obj.save_with_options(path, &SaveOptions::default().with_bake(16, 0.0)).unwrap();
+
+        let obj_text = std::fs::read_to_string(path).unwrap();
+        let mtl_text = std::fs::read_to_string(directory.join("esferas.mtl")).unwrap();
+        let loaded = Obj::load(path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert!(obj_text.lines().any(|line| line == "usemtl roca"));
+        assert!(obj_text.lines().any(|line| line == "usemtl default"));
+        assert!(mtl_text.lines().any(|line| line == "map_Kd esferas_roca.tga"));
+        assert!(mtl_text.lines().any(|line| line == "map_Pr esferas_roca_roughness.tga"));
+
+        // El OBJ repite v, vt y vn por vértice, así que vuelven los mismos vértices que usan
+        // las caras (los polos de uv_sphere tienen copias que ninguna cara usa)
+        let used = indices.iter().collect::<HashSet<_>>().len();
+        assert_eq!(loaded.meshes().len(), 2);
+        for mesh in loaded.meshes() {
+            assert_eq!(mesh.indices.len(), indices.len());
+            assert_eq!(mesh.vertices.len(), used);
+            assert_eq!(mesh.texcoords.len(), used);
+            assert_eq!(mesh.normals.len(), used);
+            // Los ejes y la V se deshacen al guardar y se vuelven a aplicar al cargar
+            for ((position, normal), uv) in mesh.vertices.iter().zip(&mesh.normals).zip(&mesh.texcoords) {
+                assert!((position - normal).magnitude() < 1e-4);
+                assert!(vertices.iter().any(|v| (v.position - position).magnitude() < 1e-4 && (v.tex_coords - uv).magnitude() < 1e-4));
+            }
+        }
+
+        let names: Vec<_> = loaded.materials().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["roca", "default"]);
+        let roca = &loaded.materials()[0];
+        assert_eq!(roca.base_color_texture.as_deref(), Some("esferas_roca.tga"));
+        assert!(roca.maps.as_ref().is_some_and(|maps| maps.base_color.is_some()));
+        assert!(loaded.warnings().is_empty(), "{:?}", loaded.warnings());
+    }
+}
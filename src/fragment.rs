@@ -1,29 +1,64 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 pub struct Fragment {
     pub position: Vec2,
+    #[allow(dead_code)] // el color sale del fragment shader; este es el de line.rs
     pub color: Color,
     pub depth: f32,
     pub vertex_position: Vec3,
+    pub world_position: Vec3,
+    pub normal: Vec3,
+    pub tex_coords: Vec2,
+    // Tangente en mundo (w = signo de la bitangente) para los mapas de normales
+    pub tangent: Vec4,
 }
 
 impl Fragment {
+    #[allow(dead_code)] // lo usa line.rs
     pub fn new(x: f32, y: f32, color: Color, depth: f32) -> Self {
         Fragment {
             position: Vec2::new(x, y),
             color,
             depth,
             vertex_position: Vec3::new(0.0, 0.0, 0.0),
+            world_position: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+            tangent: Vec4::zeros(),
         }
     }
 
+    #[allow(dead_code)] // el rasterizador ahora interpola todos los atributos
     pub fn new_with_vertex_position(x: f32, y: f32, color: Color, depth: f32, vertex_position: Vec3) -> Self {
         Fragment {
             position: Vec2::new(x, y),
             color,
             depth,
             vertex_position,
+            world_position: vertex_position,
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+            tangent: Vec4::zeros(),
         }
     }
+
+    // Fragmento con los atributos interpolados que necesita la iluminación
+    pub fn new_with_attributes(x: f32, y: f32, depth: f32, vertex_position: Vec3, world_position: Vec3, normal: Vec3, tex_coords: Vec2) -> Self {
+        Fragment {
+            position: Vec2::new(x, y),
+            color: Color::new(255, 255, 255),
+            depth,
+            vertex_position,
+            world_position,
+            normal,
+            tex_coords,
+            tangent: Vec4::zeros(),
+        }
+    }
+
+    pub fn with_tangent(mut self, tangent: Vec4) -> Self {
+        self.tangent = tangent;
+        self
+    }
 }
\ No newline at end of file
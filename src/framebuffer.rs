@@ -1,12 +1,33 @@
 // framebuffer.rs
 
+use nalgebra_glm::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Opaque,
+    Alpha,
+    Additive,
+    // Tiñe lo que hay detrás con el color actual (vidrios de color, sombras suaves)
+    #[allow(dead_code)] // todavía no lo usa ninguna escena
+    Multiply,
+    // El color ya viene multiplicado por su alpha
+    #[allow(dead_code)] // todavía no lo usa ninguna escena
+    Premultiplied,
+}
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
+    // Luz emitida por lo que se ve en cada píxel, sin recortar a [0, 1]; la lee el bloom
+    pub emission: Vec<Vec3>,
     background_color: u32,
     current_color: u32,
+    current_emission: Vec3,
+    current_alpha: f32,
+    blend_mode: BlendMode,
+    depth_write: bool,
 }
 
 impl Framebuffer {
@@ -16,8 +37,13 @@ impl Framebuffer {
             height,
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            emission: vec![Vec3::zeros(); width * height],
             background_color: 0x000000,
             current_color: 0xFFFFFF,
+            current_emission: Vec3::zeros(),
+            current_alpha: 1.0,
+            blend_mode: BlendMode::Opaque,
+            depth_write: true,
         }
     }
 
@@ -28,18 +54,73 @@ impl Framebuffer {
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        for emission in self.emission.iter_mut() {
+            *emission = Vec3::zeros();
+        }
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
         if x < self.width && y < self.height {
             let index = y * self.width + x;
             if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
-                self.zbuffer[index] = depth;
+                self.buffer[index] = self.blend(self.buffer[index]);
+                self.emission[index] = self.blend_emission(self.emission[index]);
+                if self.depth_write {
+                    self.zbuffer[index] = depth;
+                }
             }
         }
     }
 
+    // Escribe directamente en el buffer de color, sin profundidad ni mezcla (para overlays)
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
+        if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = color;
+        }
+    }
+
+    // Combina el color actual con el que ya está en el buffer según el modo de mezcla
+    fn blend(&self, dst: u32) -> u32 {
+        if self.blend_mode == BlendMode::Opaque {
+            return self.current_color;
+        }
+
+        let a = self.current_alpha.clamp(0.0, 1.0);
+        let mix = |shift: u32| -> u32 {
+            let s = ((self.current_color >> shift) & 0xFF) as f32 / 255.0;
+            let d = ((dst >> shift) & 0xFF) as f32 / 255.0;
+            let out = match self.blend_mode {
+                BlendMode::Opaque => s,
+                BlendMode::Alpha => s * a + d * (1.0 - a),
+                BlendMode::Additive => d + s * a,
+                BlendMode::Multiply => d * (1.0 - a + s * a),
+                BlendMode::Premultiplied => s + d * (1.0 - a),
+            };
+            ((out.clamp(0.0, 1.0) * 255.0) as u32) << shift
+        };
+
+        mix(16) | mix(8) | mix(0)
+    }
+
+    // La emisión se mezcla como el color, para que lo que se dibuja encima (nubes, anillos)
+    // tape la luz que había debajo
+    fn blend_emission(&self, dst: Vec3) -> Vec3 {
+        let a = self.current_alpha.clamp(0.0, 1.0);
+        let src = self.current_emission;
+        match self.blend_mode {
+            BlendMode::Opaque => src,
+            BlendMode::Alpha => src * a + dst * (1.0 - a),
+            BlendMode::Additive => dst + src * a,
+            BlendMode::Multiply => {
+                let channel = |shift: u32| ((self.current_color >> shift) & 0xFF) as f32 / 255.0;
+                let tint = Vec3::new(channel(16), channel(8), channel(0));
+                dst.component_mul(&tint.map(|s| 1.0 - a + s * a))
+            }
+            BlendMode::Premultiplied => src + dst * (1.0 - a),
+        }
+    }
+
+    #[allow(dead_code)] // el fondo por defecto es negro
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
@@ -47,4 +128,96 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
-}
\ No newline at end of file
+
+    pub fn set_current_emission(&mut self, emission: Vec3) {
+        self.current_emission = emission;
+    }
+
+    pub fn set_current_alpha(&mut self, alpha: f32) {
+        self.current_alpha = alpha;
+    }
+
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    pub fn set_depth_write(&mut self, enabled: bool) {
+        self.depth_write = enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dibuja un píxel con el color 0x80FF40, alpha 0.5, sobre un destino 0x4080C0 y
+    // devuelve los canales resultantes
+    fn blend_over_known_pixel(mode: BlendMode) -> [u32; 3] {
+        let mut framebuffer = Framebuffer::new(1, 1);
+        framebuffer.buffer[0] = 0x4080C0;
+        framebuffer.set_current_color(0x80FF40);
+        framebuffer.set_current_alpha(0.5);
+        framebuffer.set_blend_mode(mode);
+        framebuffer.point(0, 0, 1.0);
+
+        let pixel = framebuffer.buffer[0];
+        [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF]
+    }
+
+    // Lo que da la fórmula en cada canal, con src y dst en [0, 1]
+    fn expected(formula: impl Fn(f32, f32) -> f32) -> [u32; 3] {
+        let src = [0x80, 0xFF, 0x40];
+        let dst = [0x40, 0x80, 0xC0];
+        [0, 1, 2].map(|i| {
+            let out = formula(src[i] as f32 / 255.0, dst[i] as f32 / 255.0);
+            (out.clamp(0.0, 1.0) * 255.0) as u32
+        })
+    }
+
+    #[test]
+    fn blend_modes_follow_their_formulas() {
+        let a = 0.5;
+        assert_eq!(blend_over_known_pixel(BlendMode::Opaque), [0x80, 0xFF, 0x40]);
+        assert_eq!(blend_over_known_pixel(BlendMode::Alpha), expected(|s, d| s * a + d * (1.0 - a)));
+        assert_eq!(blend_over_known_pixel(BlendMode::Additive), expected(|s, d| d + s * a));
+        assert_eq!(blend_over_known_pixel(BlendMode::Multiply), expected(|s, d| d * (1.0 - a + s * a)));
+        assert_eq!(blend_over_known_pixel(BlendMode::Premultiplied), expected(|s, d| s + d * (1.0 - a)));
+
+        // Valores concretos: la mezcla alpha queda a mitad de camino y la aditiva satura
+        assert_eq!(blend_over_known_pixel(BlendMode::Alpha), [0x60, 0xBF, 0x80]);
+        assert_eq!(blend_over_known_pixel(BlendMode::Additive)[1], 0xFF);
+    }
+
+    #[test]
+    fn emission_blends_like_the_color() {
+        let dst = Vec3::new(0.2, 0.4, 0.8);
+        let src = Vec3::new(1.0, 0.5, 0.0);
+        let tint = Vec3::new(1.0, 128.0 / 255.0, 0.0);
+        let mut framebuffer = Framebuffer::new(1, 1);
+        framebuffer.emission[0] = dst;
+        framebuffer.set_current_color(0xFF8000);
+        framebuffer.set_current_emission(src);
+        framebuffer.set_current_alpha(0.25);
+
+        let cases = [
+            (BlendMode::Alpha, src * 0.25 + dst * 0.75),
+            (BlendMode::Additive, dst + src * 0.25),
+            // La multiplicación tiñe con el color actual, no con la emisión
+            (BlendMode::Multiply, dst.component_mul(&tint.map(|s| 0.75 + s * 0.25))),
+            (BlendMode::Premultiplied, src + dst * 0.75),
+        ];
+        for (mode, expected) in cases {
+            framebuffer.set_blend_mode(mode);
+            assert!((framebuffer.blend_emission(dst) - expected).magnitude() < 1e-6, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn disabled_depth_write_keeps_the_zbuffer() {
+        let mut framebuffer = Framebuffer::new(1, 1);
+        framebuffer.set_depth_write(false);
+        framebuffer.point(0, 0, 0.5);
+        assert_eq!(framebuffer.zbuffer[0], f32::INFINITY);
+        assert_eq!(framebuffer.buffer[0], 0xFFFFFF);
+    }
+}
@@ -0,0 +1,324 @@
+use nalgebra_glm::{self as glm, Mat4, Vec2, Vec3, Vec4};
+use std::fs;
+use std::path::Path;
+use crate::error::ModelError;
+use crate::obj::{Material, Mesh};
+use super::{check_texture, from_file_axes, ImportedModel, MeshImporter};
+
+// glTF 2.0, tanto .gltf (JSON con buffers externos o embebidos en base64) como .glb.
+// Cada primitiva de cada nodo de la escena pasa a ser un Mesh, con la transformación
+// del nodo ya aplicada.
+pub struct GltfImporter;
+
+impl MeshImporter for GltfImporter {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["gltf", "glb"]
+    }
+
+    fn import(&self, filename: &str) -> Result<ImportedModel, ModelError> {
+        let path = filename.to_string();
+        let parse_error = |message: String| ModelError::Parse { path: path.clone(), line: None, message };
+
+        let data = fs::read(filename).map_err(|source| ModelError::Io { path: path.clone(), source })?;
+        let gltf = ::gltf::Gltf::from_slice(&data).map_err(|e| parse_error(format!("glTF inválido: {}", e)))?;
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+
+        let mut buffers = Vec::new();
+        for buffer in gltf.document.buffers() {
+            let bytes = match buffer.source() {
+                ::gltf::buffer::Source::Bin => gltf.blob.clone()
+                    .ok_or_else(|| parse_error("falta el bloque binario del .glb".to_string()))?,
+                ::gltf::buffer::Source::Uri(uri) => match uri.strip_prefix("data:") {
+                    Some(embedded) => embedded
+                        .split_once(";base64,")
+                        .and_then(|(_, encoded)| decode_base64(encoded))
+                        .ok_or_else(|| parse_error(format!("buffer {} embebido inválido", buffer.index())))?,
+                    None => fs::read(directory.join(uri))
+                        .map_err(|source| ModelError::Io { path: directory.join(uri).display().to_string(), source })?,
+                },
+            };
+            if bytes.len() < buffer.length() {
+                return Err(parse_error(format!("el buffer {} es más corto de lo declarado", buffer.index())));
+            }
+            buffers.push(bytes);
+        }
+
+        let mut warnings = Vec::new();
+        let materials: Vec<Material> = gltf.document.materials().map(convert_material).collect();
+        for material in &materials {
+            check_texture(filename, material, &mut warnings);
+        }
+
+        // Escena por defecto, o la primera si el archivo no la indica
+        let mut meshes = Vec::new();
+        let scene = gltf.document.default_scene().or_else(|| gltf.document.scenes().next());
+        for node in scene.iter().flat_map(|scene| scene.nodes()) {
+            read_node(&node, &Mat4::identity(), &buffers, &mut meshes, &parse_error)?;
+        }
+
+        Ok(ImportedModel { meshes, materials, warnings })
+    }
+}
+
+fn read_node(
+    node: &::gltf::Node,
+    parent: &Mat4,
+    buffers: &[Vec<u8>],
+    meshes: &mut Vec<Mesh>,
+    parse_error: &dyn Fn(String) -> ModelError,
+) -> Result<(), ModelError> {
+    let local: Vec<f32> = node.transform().matrix().iter().flatten().copied().collect();
+    let transform = parent * Mat4::from_column_slice(&local);
+
+    if let Some(mesh) = node.mesh() {
+        // Las normales usan la inversa transpuesta; un determinante negativo invierte el giro de las caras.
+        // Un nodo con escala 0 (oculto) no tiene inversa: sus normales se descartan y las genera
+        // NormalMode como si el archivo no las trajera, en vez de quedar en NaN
+        let normal_matrix = glm::mat4_to_mat3(&transform).try_inverse().map(|inverse| inverse.transpose());
+        let mirrored = transform.determinant() < 0.0;
+        let base_name = node.name().or(mesh.name()).unwrap_or("mesh").to_string();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|b| b.as_slice()));
+            let Some(positions) = reader.read_positions() else {
+                return Err(parse_error(format!("la primitiva {} de '{}' no tiene posiciones", primitive.index(), base_name)));
+            };
+
+            let vertices: Vec<Vec3> = positions
+                .map(|p| {
+                    let world = transform * Vec4::new(p[0], p[1], p[2], 1.0);
+                    from_file_axes([world.x, world.y, world.z])
+                })
+                .collect();
+            let normals = reader.read_normals()
+                .zip(normal_matrix)
+                .map(|(normals, normal_matrix)| normals
+                    .map(|n| {
+                        let world = (normal_matrix * Vec3::from(n)).normalize();
+                        from_file_axes([world.x, world.y, world.z])
+                    })
+                    .collect())
+                .unwrap_or_default();
+            let texcoords = reader.read_tex_coords(0)
+                .map(|uv| uv.into_f32().map(|t| Vec2::new(t[0], t[1])).collect())
+                .unwrap_or_default();
+            let mut indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+            if mirrored {
+                for face in indices.chunks_exact_mut(3) {
+                    face.swap(1, 2);
+                }
+            }
+
+            let name = if mesh.primitives().len() > 1 {
+                format!("{}.{}", base_name, primitive.index())
+            } else {
+                base_name.clone()
+            };
+            meshes.push(Mesh { name, vertices, normals, texcoords, indices, material: primitive.material().index() });
+        }
+    }
+
+    for child in node.children() {
+        read_node(&child, &transform, buffers, meshes, parse_error)?;
+    }
+    Ok(())
+}
+
+fn convert_material(material: ::gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    // Solo se conservan las texturas que son archivos; las embebidas en buffers no tienen ruta
+    let file = |texture: ::gltf::Texture| match texture.source().source() {
+        ::gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => Some(uri.to_string()),
+        _ => None,
+    };
+    let texture = pbr.base_color_texture().and_then(|info| file(info.texture()));
+    let emission = material.emissive_texture().and_then(|info| file(info.texture()));
+    let name = match material.index() {
+        Some(index) => material.name().map(str::to_string).unwrap_or_else(|| format!("material{}", index)),
+        None => "default".to_string(),
+    };
+
+    Material {
+        base_color: Vec4::from(pbr.base_color_factor()),
+        base_color_texture: texture,
+        emission_texture: emission,
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        ..Material::new(&name)
+    }
+}
+
+// Buffers "data:" de los .gltf: base64 estándar con relleno. Un carácter fuera del alfabeto
+// o un relleno mal puesto invalidan el buffer.
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.decode(encoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    // Triángulo en el plano XY con normales +Z: posiciones, normales e índices u16, con
+    // relleno hasta múltiplo de 4 como pide .glb
+    fn triangle_buffer() -> Vec<u8> {
+        let floats = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let mut bytes: Vec<u8> = floats.iter().flat_map(|f| f.to_le_bytes()).collect();
+        bytes.extend([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()));
+        bytes.resize(80, 0);
+        bytes
+    }
+
+    // Escena con el triángulo en cuatro nodos: trasladado, un hijo suyo, espejado en X y
+    // oculto con escala 0. `buffer` es el objeto del buffer (con o sin uri).
+    fn scene_json(buffer: &str) -> String {
+        format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0, 2, 3] }}],
+            "nodes": [
+                {{ "name": "movido", "mesh": 0, "translation": [2, 0, 0], "children": [1] }},
+                {{ "name": "hijo", "mesh": 0, "translation": [0, 0, 3] }},
+                {{ "name": "espejo", "mesh": 0, "scale": [-1, 1, 1] }},
+                {{ "name": "oculto", "mesh": 0, "scale": [0, 1, 1] }}
+            ],
+            "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0, "NORMAL": 1 }}, "indices": 2, "material": 0 }}] }}],
+            "materials": [{{
+                "name": "rojo",
+                "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0, 0, 0.5], "metallicFactor": 0.25, "roughnessFactor": 0.75 }}
+            }}],
+            "buffers": [{}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 72 }},
+                {{ "buffer": 0, "byteOffset": 72, "byteLength": 6 }}
+            ],
+            "accessors": [
+                {{ "bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }},
+                {{ "bufferView": 0, "byteOffset": 36, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ]
+        }}"#, buffer)
+    }
+
+    fn embedded_gltf() -> Vec<u8> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(triangle_buffer());
+        let buffer = format!(r#"{{ "byteLength": 80, "uri": "data:application/octet-stream;base64,{}" }}"#, encoded);
+        scene_json(&buffer).into_bytes()
+    }
+
+    // Contenedor .glb: cabecera, bloque JSON (relleno con espacios) y bloque binario
+    fn binary_glb() -> Vec<u8> {
+        let mut json = scene_json(r#"{ "byteLength": 80 }"#).into_bytes();
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+        let bin = triangle_buffer();
+
+        let mut glb = Vec::new();
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(bin);
+        glb
+    }
+
+    // Importa `contents` desde un archivo temporal con nombre propio de cada prueba
+    fn import(name: &str, contents: &[u8]) -> Result<ImportedModel, ModelError> {
+        let path = std::env::temp_dir().join(format!("proyecto_nave_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        let result = GltfImporter.import(&path.to_string_lossy());
+        fs::remove_file(&path).ok();
+        result
+    }
+
+    fn assert_scene(model: &ImportedModel) {
+        let mesh = |name: &str| model.meshes.iter().find(|m| m.name == name).unwrap();
+        assert_eq!(model.meshes.len(), 4);
+
+        // Las transformaciones de los nodos se acumulan y después va el cambio de ejes
+        let moved = mesh("movido");
+        assert_eq!(moved.vertices, [Vec3::new(2.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0), Vec3::new(2.0, -1.0, 0.0)]);
+        assert_eq!(moved.indices, [0, 1, 2]);
+        assert_eq!(mesh("hijo").vertices[0], Vec3::new(2.0, 0.0, -3.0));
+
+        // El espejo invierte el giro para que la cara siga mirando hacia su normal
+        let mirrored = mesh("espejo");
+        assert_eq!(mirrored.indices, [0, 2, 1]);
+        for mesh in [moved, mirrored] {
+            let [a, b, c] = [0, 1, 2].map(|k| mesh.vertices[mesh.indices[k] as usize]);
+            let face = (b - a).cross(&(c - a));
+            assert!(mesh.normals.iter().all(|n| (n - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-6));
+            assert!(face.dot(&mesh.normals[0]) > 0.0, "{}", mesh.name);
+        }
+
+        // Con escala 0 no hay inversa: las normales se descartan en vez de quedar en NaN
+        assert!(mesh("oculto").normals.is_empty());
+
+        let material = &model.materials[0];
+        assert_eq!(material.name, "rojo");
+        assert_eq!(material.base_color, Vec4::new(1.0, 0.0, 0.0, 0.5));
+        assert_eq!((material.metallic, material.roughness), (0.25, 0.75));
+        assert!(model.meshes.iter().all(|m| m.material == Some(0)));
+    }
+
+    #[test]
+    fn imports_an_embedded_gltf() {
+        assert_scene(&import("escena.gltf", &embedded_gltf()).unwrap());
+    }
+
+    #[test]
+    fn imports_a_binary_glb() {
+        assert_scene(&import("escena.glb", &binary_glb()).unwrap());
+    }
+
+    #[test]
+    fn glb_without_its_binary_chunk_is_an_error() {
+        let json = scene_json(r#"{ "byteLength": 80 }"#);
+        let mut glb = binary_glb();
+        let json_length = json.len().div_ceil(4) * 4;
+        let length = 12 + 8 + json_length;
+        glb.truncate(length);
+        glb[8..12].copy_from_slice(&(length as u32).to_le_bytes());
+        match import("sin_bin.glb", &glb) {
+            Err(ModelError::Parse { message, .. }) => assert!(message.contains("binario"), "{}", message),
+            Err(other) => panic!("se esperaba un error de parseo: {}", other),
+            Ok(_) => panic!("un .glb sin bloque binario no se puede leer"),
+        }
+    }
+
+    #[test]
+    fn base64_round_trips_every_padding_length() {
+        let data: Vec<u8> = (0..=255).collect();
+        for length in [0, 1, 2, 3, 4, 5, 256] {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(&data[..length]);
+            assert_eq!(decode_base64(&encoded).as_deref(), Some(&data[..length]), "{}", encoded);
+        }
+        assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+    }
+
+    #[test]
+    fn base64_rejects_bad_padding() {
+        for encoded in ["TQ", "TQ=", "TWE", "TQ===", "T=Q=", "=TQ=", "TWFuT"] {
+            assert_eq!(decode_base64(encoded), None, "{}", encoded);
+        }
+    }
+
+    #[test]
+    fn base64_rejects_invalid_characters() {
+        for encoded in ["TW-u", "TW_u", "TW u", "TWF\n", "TWFu!A==", "TWé="] {
+            assert_eq!(decode_base64(encoded), None, "{}", encoded);
+        }
+    }
+}
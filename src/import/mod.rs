@@ -0,0 +1,100 @@
+use nalgebra_glm::Vec3;
+use std::path::Path;
+use crate::error::{MeshProblem, ModelError};
+use crate::normals::{apply_normals, NormalMode};
+use crate::obj::{Material, Mesh, Obj};
+
+mod obj;
+mod stl;
+mod ply;
+mod gltf;
+
+// Capa de importación: cada formato convierte su archivo a los mismos Mesh y Material,
+// y load_model se encarga de validar y generar normales igual para todos.
+
+// Resultado de un importador, antes de validar
+pub struct ImportedModel {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    // Problemas no fatales encontrados al leer (materiales o texturas que faltan, etc.)
+    pub warnings: Vec<ModelError>,
+}
+
+pub trait MeshImporter {
+    // Extensiones que reconoce, en minúsculas y sin punto
+    fn extensions(&self) -> &'static [&'static str];
+    fn import(&self, path: &str) -> Result<ImportedModel, ModelError>;
+}
+
+pub fn importers() -> Vec<Box<dyn MeshImporter>> {
+    vec![
+        Box::new(obj::ObjImporter),
+        Box::new(stl::StlImporter),
+        Box::new(ply::PlyImporter),
+        Box::new(gltf::GltfImporter),
+    ]
+}
+
+// Importador según la extensión del archivo
+pub fn importer_for(path: &str) -> Result<Box<dyn MeshImporter>, ModelError> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    importers()
+        .into_iter()
+        .find(|importer| importer.extensions().contains(&extension.as_str()))
+        .ok_or(ModelError::UnsupportedFormat { path: path.to_string() })
+}
+
+// Índices fuera de rango y posiciones no finitas son errores; las caras sin área se
+// descartan y quedan como avisos, junto con los del importador
+pub fn load_model(path: &str, mode: NormalMode) -> Result<Obj, ModelError> {
+    let imported = importer_for(path)?.import(path)?;
+    let mut warnings = imported.warnings;
+
+    let mut meshes = Vec::with_capacity(imported.meshes.len());
+    for mut mesh in imported.meshes {
+        let name = mesh.name.clone();
+        let invalid = |problem| ModelError::Invalid { path: path.to_string(), mesh: name.clone(), problem };
+        mesh.validate().map_err(invalid)?;
+        let removed = mesh.remove_degenerate_faces();
+        if removed > 0 {
+            warnings.push(invalid(MeshProblem::DegenerateFaces { count: removed }));
+        }
+
+        meshes.push(apply_normals(mesh, mode));
+    }
+
+    // Las texturas se leen una vez acá; las rutas son relativas al archivo del modelo
+    let mut materials = imported.materials;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    for material in &mut materials {
+        material.load_maps(directory, &mut warnings);
+    }
+
+    Ok(Obj::from_meshes(meshes)
+        .with_materials(materials)
+        .with_warnings(warnings))
+}
+
+// Todos los formatos pasan por el mismo cambio de ejes que siempre se le aplicó a los OBJ
+fn from_file_axes(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], -v[1], -v[2])
+}
+
+// Una textura que no existe junto al archivo del modelo se reporta como aviso
+fn check_texture(path: &str, material: &Material, warnings: &mut Vec<ModelError>) {
+    if let Some(texture) = &material.base_color_texture {
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+        if !directory.join(texture).exists() {
+            warnings.push(ModelError::MissingTexture {
+                path: path.to_string(),
+                material: material.name.clone(),
+                texture: texture.clone(),
+            });
+        }
+    }
+}
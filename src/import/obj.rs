@@ -0,0 +1,234 @@
+use nalgebra_glm::{Vec2, Vec4};
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use crate::error::ModelError;
+use crate::obj::{Material, Mesh};
+use super::{from_file_axes, ImportedModel, MeshImporter};
+
+// Wavefront OBJ con su MTL, a través de tobj
+pub struct ObjImporter;
+
+impl MeshImporter for ObjImporter {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["obj"]
+    }
+
+    fn import(&self, filename: &str) -> Result<ImportedModel, ModelError> {
+        let path = filename.to_string();
+        let file = File::open(filename).map_err(|source| ModelError::Io { path: path.clone(), source })?;
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+
+        let line = Cell::new(0);
+        let warnings = RefCell::new(Vec::new());
+        let mut reader = LineCounter::new(BufReader::new(file), &line);
+
+        let options = tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ..Default::default()
+        };
+        let (models, materials) = tobj::load_obj_buf(&mut reader, &options, |library| {
+            let result = tobj::load_mtl(directory.join(library));
+            if result.is_err() {
+                warnings.borrow_mut().push(ModelError::MissingMaterial {
+                    path: path.clone(),
+                    line: line.get(),
+                    library: library.display().to_string(),
+                });
+            }
+            result
+        })
+        .map_err(|error| ModelError::Parse {
+            path: path.clone(),
+            line: Some(line.get()).filter(|&l| l > 0),
+            message: describe_load_error(error),
+        })?;
+        let mut warnings = warnings.into_inner();
+        let materials = materials.unwrap_or_default();
+
+        // Las texturas se buscan junto al archivo; las opciones como "-bm 1" van antes del nombre
+        for material in &materials {
+            let textures = [
+                &material.ambient_texture,
+                &material.diffuse_texture,
+                &material.specular_texture,
+                &material.normal_texture,
+                &material.shininess_texture,
+                &material.dissolve_texture,
+            ];
+            for texture in textures.into_iter().flatten() {
+                let name = texture_name(texture);
+                if !directory.join(name).exists() {
+                    warnings.push(ModelError::MissingTexture {
+                        path: path.clone(),
+                        material: material.name.clone(),
+                        texture: name.to_string(),
+                    });
+                }
+            }
+        }
+
+        let meshes = models.into_iter().map(|model| {
+            let data = model.mesh;
+            Mesh {
+                name: model.name,
+                vertices: data.positions.chunks(3)
+                    .map(|v| from_file_axes([v[0], v[1], v[2]]))
+                    .collect(),
+                normals: data.normals.chunks(3)
+                    .map(|n| from_file_axes([n[0], n[1], n[2]]))
+                    .collect(),
+                texcoords: data.texcoords.chunks(2)
+                    .map(|t| Vec2::new(t[0], 1.0 - t[1]))
+                    .collect(),
+                indices: data.indices,
+                material: data.material_id,
+            }
+        }).collect();
+
+        Ok(ImportedModel {
+            meshes,
+            materials: materials.iter().map(convert_material).collect(),
+            warnings,
+        })
+    }
+}
+
+fn texture_name(texture: &str) -> &str {
+    texture.split_whitespace().last().unwrap_or(texture)
+}
+
+// Kd y d pasan a color base; si no vienen Pr y Pm (extensión PBR del MTL), el brillo
+// de Phong (Ns) se aproxima con rugosidad
+fn convert_material(material: &tobj::Material) -> Material {
+    let diffuse = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
+    let shininess = material.shininess.unwrap_or(0.0).max(0.0);
+    let pbr = |key: &str| material.unknown_param.get(key).and_then(|v| v.trim().parse::<f32>().ok());
+    let map = |key: &str| material.unknown_param.get(key).map(|t| texture_name(t).to_string());
+
+    Material {
+        base_color: Vec4::new(diffuse[0], diffuse[1], diffuse[2], material.dissolve.unwrap_or(1.0)),
+        base_color_texture: material.diffuse_texture.as_deref().map(|t| texture_name(t).to_string()),
+        emission_texture: map("map_Ke"),
+        metallic_texture: map("map_Pm"),
+        roughness_texture: map("map_Pr"),
+        metallic: pbr("Pm").unwrap_or(0.0),
+        roughness: pbr("Pr").unwrap_or((2.0 / (shininess + 2.0)).sqrt()),
+        ..Material::new(&material.name)
+    }
+}
+
+// Lector que lleva la cuenta de la línea que está leyendo tobj, para ubicar sus errores
+struct LineCounter<'a, R> {
+    inner: R,
+    line: &'a Cell<usize>,
+    at_line_start: bool,
+}
+
+impl<'a, R> LineCounter<'a, R> {
+    fn new(inner: R, line: &'a Cell<usize>) -> Self {
+        LineCounter { inner, line, at_line_start: true }
+    }
+
+    fn count(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if self.at_line_start {
+                self.line.set(self.line.get() + 1);
+            }
+            self.at_line_start = byte == b'\n';
+        }
+    }
+}
+
+impl<R: Read> Read for LineCounter<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let consumed = match self.inner.fill_buf() {
+            Ok(buf) => buf[..amt.min(buf.len())].to_vec(),
+            Err(_) => Vec::new(),
+        };
+        self.count(&consumed);
+        self.inner.consume(amt);
+    }
+}
+
+fn describe_load_error(error: tobj::LoadError) -> String {
+    use tobj::LoadError::*;
+    match error {
+        ReadError => "no se pudo leer el archivo".to_string(),
+        PositionParseError => "posición (v) mal formada".to_string(),
+        ScalingByZeroError => "posición con w = 0".to_string(),
+        NormalParseError => "normal (vn) mal formada".to_string(),
+        TexcoordParseError => "coordenada de textura (vt) mal formada".to_string(),
+        FaceParseError => "cara (f) mal formada".to_string(),
+        MaterialParseError => "usemtl sin nombre de material".to_string(),
+        FaceVertexOutOfBounds => "una cara usa un vértice (v) que no existe".to_string(),
+        FaceTexCoordOutOfBounds => "una cara usa una coordenada de textura (vt) que no existe".to_string(),
+        FaceNormalOutOfBounds => "una cara usa una normal (vn) que no existe".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Importa `contents` desde un archivo temporal con nombre propio de cada prueba
+    fn import(name: &str, contents: &str) -> Result<ImportedModel, ModelError> {
+        let path = std::env::temp_dir().join(format!("proyecto_nave_{}_{}.obj", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = ObjImporter.import(&path.to_string_lossy());
+        fs::remove_file(&path).ok();
+        result
+    }
+
+    fn parse_error_line(result: Result<ImportedModel, ModelError>) -> (Option<usize>, String) {
+        match result {
+            Err(ModelError::Parse { line, message, .. }) => (line, message),
+            Err(other) => panic!("se esperaba un error de parseo: {}", other),
+            Ok(_) => panic!("se esperaba un error de parseo"),
+        }
+    }
+
+    #[test]
+    fn reads_a_triangle() {
+        let model = import("triangulo", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert_eq!(model.meshes.len(), 1);
+        assert_eq!(model.meshes[0].indices.len(), 3);
+        assert!(model.warnings.is_empty());
+    }
+
+    #[test]
+    fn malformed_position_reports_its_line() {
+        let (line, message) = parse_error_line(import("posicion", "# cubo\nv 0 0 0\nv 1 zero 0\nv 0 1 0\nf 1 2 3\n"));
+        assert_eq!(line, Some(3));
+        assert!(message.contains("posición"), "{}", message);
+    }
+
+    #[test]
+    fn face_out_of_bounds_reports_its_line() {
+        let (line, message) = parse_error_line(import("fuera", "v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 7\n"));
+        assert_eq!(line, Some(5));
+        assert!(message.contains("no existe"), "{}", message);
+    }
+
+    #[test]
+    fn missing_material_library_is_a_warning() {
+        let model = import("sin_mtl", "mtllib no_existe.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert!(matches!(model.warnings.as_slice(), [ModelError::MissingMaterial { line: 1, .. }]));
+    }
+}
@@ -0,0 +1,402 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::fs;
+use std::path::Path;
+use crate::error::ModelError;
+use crate::obj::Mesh;
+use super::{from_file_axes, ImportedModel, MeshImporter};
+
+// PLY (Stanford) ASCII o binario. Se leen los elementos "vertex" (posición, normal y UV)
+// y "face" (polígonos, triangulados en abanico); el resto de los elementos se salta.
+pub struct PlyImporter;
+
+impl MeshImporter for PlyImporter {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ply"]
+    }
+
+    fn import(&self, filename: &str) -> Result<ImportedModel, ModelError> {
+        let path = filename.to_string();
+        let data = fs::read(filename).map_err(|source| ModelError::Io { path: path.clone(), source })?;
+        let name = Path::new(filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mesh = parse(&data, name).map_err(|(line, message)| ModelError::Parse { path, line, message })?;
+        Ok(ImportedModel { meshes: vec![mesh], materials: Vec::new(), warnings: Vec::new() })
+    }
+}
+
+// Error con la línea donde ocurrió, si el cuerpo es ASCII
+type PlyError = (Option<usize>, String);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+enum PropertyKind {
+    Scalar(Scalar),
+    // Tipo del contador y tipo de cada elemento
+    List(Scalar, Scalar),
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+fn parse(data: &[u8], name: String) -> Result<Mesh, PlyError> {
+    let (format, elements, body_start, header_lines) = parse_header(data)?;
+    let mut body = Body { data, pos: body_start, line: header_lines + 1, format };
+
+    let mut mesh = Mesh {
+        name,
+        vertices: Vec::new(),
+        normals: Vec::new(),
+        texcoords: Vec::new(),
+        indices: Vec::new(),
+        material: None,
+    };
+
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut body, element, &mut mesh)?,
+            "face" => read_faces(&mut body, element, &mut mesh)?,
+            _ => {
+                for _ in 0..element.count {
+                    read_record(&mut body, element, |_, _| Ok(()))?;
+                }
+            }
+        }
+    }
+
+    Ok(mesh)
+}
+
+fn parse_header(data: &[u8]) -> Result<(Format, Vec<Element>, usize, usize), PlyError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos = 0;
+    let mut line = 0;
+
+    loop {
+        if pos >= data.len() {
+            return Err((None, "la cabecera no termina con end_header".to_string()));
+        }
+        let end = data[pos..].iter().position(|&b| b == b'\n').map_or(data.len(), |i| pos + i);
+        let text = String::from_utf8_lossy(&data[pos..end]);
+        pos = (end + 1).min(data.len());
+        line += 1;
+
+        let error = |message: String| (Some(line), message);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if line == 1 {
+            if words != ["ply"] {
+                return Err(error("falta la firma 'ply' al inicio".to_string()));
+            }
+            continue;
+        }
+
+        match words.as_slice() {
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    other => return Err(error(format!("formato '{}' desconocido", other))),
+                });
+            }
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| error(format!("cantidad '{}' inválida", count)))?;
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            ["property", "list", count_type, item_type, name] => {
+                let scalar = |t: &str| Scalar::parse(t).ok_or_else(|| error(format!("tipo '{}' desconocido", t)));
+                let kind = PropertyKind::List(scalar(count_type)?, scalar(item_type)?);
+                let element = elements.last_mut().ok_or_else(|| error("propiedad antes de cualquier elemento".to_string()))?;
+                element.properties.push(Property { name: name.to_string(), kind });
+            }
+            ["property", ty, name] => {
+                let kind = PropertyKind::Scalar(Scalar::parse(ty).ok_or_else(|| error(format!("tipo '{}' desconocido", ty)))?);
+                let element = elements.last_mut().ok_or_else(|| error("propiedad antes de cualquier elemento".to_string()))?;
+                element.properties.push(Property { name: name.to_string(), kind });
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(error(format!("línea de cabecera no reconocida: '{}'", text.trim()))),
+        }
+    }
+
+    let format = format.ok_or((None, "la cabecera no declara el formato".to_string()))?;
+    Ok((format, elements, pos, line))
+}
+
+// Lector del cuerpo; en ASCII sigue la línea y en binario la posición en bytes
+struct Body<'a> {
+    data: &'a [u8],
+    pos: usize,
+    line: usize,
+    format: Format,
+}
+
+impl Body<'_> {
+    fn error(&self, message: &str) -> PlyError {
+        match self.format {
+            Format::Ascii => (Some(self.line), message.to_string()),
+            _ => (None, format!("{} (byte {})", message, self.pos)),
+        }
+    }
+
+    fn read(&mut self, ty: Scalar) -> Result<f64, PlyError> {
+        match self.format {
+            Format::Ascii => self.read_ascii(),
+            _ => self.read_binary(ty),
+        }
+    }
+
+    fn read_ascii(&mut self) -> Result<f64, PlyError> {
+        while let Some(&byte) = self.data.get(self.pos) {
+            if !byte.is_ascii_whitespace() {
+                break;
+            }
+            if byte == b'\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("el archivo termina antes de lo que declara la cabecera"));
+        }
+
+        let token = String::from_utf8_lossy(&self.data[start..self.pos]);
+        token.parse().map_err(|_| self.error(&format!("valor '{}' inválido", token)))
+    }
+
+    fn read_binary(&mut self, ty: Scalar) -> Result<f64, PlyError> {
+        let bytes = self.data
+            .get(self.pos..self.pos + ty.size())
+            .ok_or_else(|| self.error("el archivo termina antes de lo que declara la cabecera"))?;
+        let big = self.format == Format::BinaryBigEndian;
+        macro_rules! decode {
+            ($t:ty) => {{
+                let raw = bytes.try_into().unwrap();
+                (if big { <$t>::from_be_bytes(raw) } else { <$t>::from_le_bytes(raw) }) as f64
+            }};
+        }
+        let value = match ty {
+            Scalar::I8 => decode!(i8),
+            Scalar::U8 => decode!(u8),
+            Scalar::I16 => decode!(i16),
+            Scalar::U16 => decode!(u16),
+            Scalar::I32 => decode!(i32),
+            Scalar::U32 => decode!(u32),
+            Scalar::F32 => decode!(f32),
+            Scalar::F64 => decode!(f64),
+        };
+        self.pos += ty.size();
+        Ok(value)
+    }
+}
+
+// Lee una instancia del elemento y entrega cada propiedad con sus valores
+fn read_record(
+    body: &mut Body,
+    element: &Element,
+    mut visit: impl FnMut(usize, &[f64]) -> Result<(), PlyError>,
+) -> Result<(), PlyError> {
+    let mut values = Vec::new();
+    for (index, property) in element.properties.iter().enumerate() {
+        values.clear();
+        match property.kind {
+            PropertyKind::Scalar(ty) => values.push(body.read(ty)?),
+            PropertyKind::List(count_type, item_type) => {
+                let count = body.read(count_type)?;
+                if count < 0.0 {
+                    return Err(body.error("lista con tamaño negativo"));
+                }
+                for _ in 0..count as usize {
+                    values.push(body.read(item_type)?);
+                }
+            }
+        }
+        visit(index, &values)?;
+    }
+    Ok(())
+}
+
+fn property_index(element: &Element, names: &[&str]) -> Option<usize> {
+    element.properties.iter().position(|p| names.contains(&p.name.as_str()))
+}
+
+fn read_vertices(body: &mut Body, element: &Element, mesh: &mut Mesh) -> Result<(), PlyError> {
+    let find = |names: &[&str]| property_index(element, names);
+    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let uv = [find(&["u", "s", "texture_u"]), find(&["v", "t", "texture_v"])];
+    if position.iter().any(Option::is_none) {
+        return Err((None, "el elemento vertex no tiene x, y, z".to_string()));
+    }
+    let has_normals = normal.iter().all(Option::is_some);
+    let has_uv = uv.iter().all(Option::is_some);
+
+    let mut values = vec![0.0f32; element.properties.len()];
+    for _ in 0..element.count {
+        read_record(body, element, |index, read| {
+            values[index] = read.first().copied().unwrap_or(0.0) as f32;
+            Ok(())
+        })?;
+
+        let get = |i: Option<usize>| i.map_or(0.0, |i| values[i]);
+        mesh.vertices.push(from_file_axes(position.map(get)));
+        if has_normals {
+            mesh.normals.push(from_file_axes(normal.map(get)));
+        }
+        if has_uv {
+            // Como en OBJ, V crece hacia arriba
+            mesh.texcoords.push(Vec2::new(get(uv[0]), 1.0 - get(uv[1])));
+        }
+    }
+
+    // Normales de longitud cero equivalen a no tenerlas
+    if mesh.normals.iter().any(|n| *n == Vec3::zeros()) {
+        mesh.normals.clear();
+    }
+    Ok(())
+}
+
+fn read_faces(body: &mut Body, element: &Element, mesh: &mut Mesh) -> Result<(), PlyError> {
+    let Some(list) = property_index(element, &["vertex_indices", "vertex_index"]) else {
+        return Err((None, "el elemento face no tiene vertex_indices".to_string()));
+    };
+
+    let mut values = Vec::new();
+    for _ in 0..element.count {
+        values.clear();
+        read_record(body, element, |index, read| {
+            if index == list {
+                values.extend_from_slice(read);
+            }
+            Ok(())
+        })?;
+
+        // Los índices tienen que ser enteros y apuntar a un vértice ya leído (NaN tampoco pasa)
+        let vertex_count = mesh.vertices.len() as f64;
+        let polygon = values
+            .iter()
+            .map(|&v| {
+                if v >= 0.0 && v < vertex_count && v.fract() == 0.0 {
+                    Ok(v as u32)
+                } else {
+                    Err(body.error(&format!("índice de vértice {} inválido (hay {} vértices)", v, vertex_count)))
+                }
+            })
+            .collect::<Result<Vec<u32>, PlyError>>()?;
+
+        // Abanico desde el primer vértice; los polígonos de menos de 3 vértices se ignoran
+        for i in 1..polygon.len().saturating_sub(1) {
+            mesh.indices.extend_from_slice(&[polygon[0], polygon[i], polygon[i + 1]]);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n";
+
+    fn parse_ascii(body: &str) -> Result<Mesh, PlyError> {
+        parse(format!("{}{}", HEADER, body).as_bytes(), "prueba".to_string())
+    }
+
+    #[test]
+    fn reads_an_ascii_triangle() {
+        let mesh = parse_ascii("0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n").unwrap();
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn bad_value_reports_its_line() {
+        // La cabecera ocupa 9 líneas: la segunda línea del cuerpo es la 11
+        let (line, message) = parse_ascii("0 0 0\n1 x 0\n0 1 0\n3 0 1 2\n").unwrap_err();
+        assert_eq!(line, Some(11));
+        assert!(message.contains("'x'"), "{}", message);
+    }
+
+    #[test]
+    fn truncated_body_reports_the_end_of_the_file() {
+        // Falta la z del tercer vértice: el error queda en la línea vacía tras el último salto
+        let (line, message) = parse_ascii("0 0 0\n1 0 0\n0 1\n").unwrap_err();
+        assert_eq!(line, Some(13));
+        assert!(message.contains("termina"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_invalid_face_indices() {
+        for face in ["3 0 1 3", "3 0 -1 2", "3 0 1.5 2", "3 0 nan 2"] {
+            let (line, message) = parse_ascii(&format!("0 0 0\n1 0 0\n0 1 0\n{}\n", face)).unwrap_err();
+            assert_eq!(line, Some(13), "{}", face);
+            assert!(message.contains("índice de vértice"), "{}: {}", face, message);
+        }
+    }
+
+    #[test]
+    fn header_errors_report_their_line() {
+        let data = b"ply\nformat ascii 1.0\nelement vertex 3\nproperty quad x\nend_header\n";
+        let (line, message) = parse(data, "prueba".to_string()).unwrap_err();
+        assert_eq!(line, Some(4));
+        assert!(message.contains("quad"));
+    }
+}
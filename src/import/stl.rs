@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use crate::error::ModelError;
+use crate::obj::Mesh;
+use super::{from_file_axes, ImportedModel, MeshImporter};
+
+// STL ASCII o binario, a través de stl_io. No trae UVs ni materiales, y las normales
+// por cara del archivo no sirven para sombrear suave: se dejan a NormalMode.
+pub struct StlImporter;
+
+impl MeshImporter for StlImporter {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["stl"]
+    }
+
+    fn import(&self, filename: &str) -> Result<ImportedModel, ModelError> {
+        let path = filename.to_string();
+        let file = File::open(filename).map_err(|source| ModelError::Io { path: path.clone(), source })?;
+
+        // stl_io ya une los vértices repetidos entre triángulos
+        let stl = stl_io::read_stl(&mut BufReader::new(file)).map_err(|error| ModelError::Parse {
+            path: path.clone(),
+            line: None,
+            message: format!("STL inválido: {}", error),
+        })?;
+
+        let name = Path::new(filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mesh = Mesh {
+            name,
+            vertices: stl.vertices.iter().map(|v| from_file_axes(v.0)).collect(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            indices: stl.faces.iter()
+                .flat_map(|face| face.vertices)
+                .map(|i| i as u32)
+                .collect(),
+            material: None,
+        };
+
+        Ok(ImportedModel { meshes: vec![mesh], materials: Vec::new(), warnings: Vec::new() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec3;
+    use std::fs;
+    use crate::import::load_model;
+    use crate::normals::NormalMode;
+
+    // Quad en el plano XY partido en dos triángulos que comparten una arista
+    const QUAD: [[[f32; 3]; 3]; 2] = [
+        [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    fn ascii_stl() -> Vec<u8> {
+        let mut text = String::from("solid quad\n");
+        for triangle in QUAD {
+            text += "  facet normal 0 0 1\n    outer loop\n";
+            for [x, y, z] in triangle {
+                text += &format!("      vertex {} {} {}\n", x, y, z);
+            }
+            text += "    endloop\n  endfacet\n";
+        }
+        text += "endsolid quad\n";
+        text.into_bytes()
+    }
+
+    // Cabecera de 80 bytes, cantidad de triángulos y por cada uno normal, vértices y atributo
+    fn binary_stl() -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend((QUAD.len() as u32).to_le_bytes());
+        for triangle in QUAD {
+            let floats = [0.0f32, 0.0, 1.0].into_iter().chain(triangle.into_iter().flatten());
+            bytes.extend(floats.flat_map(f32::to_le_bytes));
+            bytes.extend(0u16.to_le_bytes());
+        }
+        bytes
+    }
+
+    // Escribe `contents` en un archivo temporal con nombre propio de cada prueba
+    fn with_file<T>(name: &str, contents: &[u8], read: impl FnOnce(&str) -> T) -> T {
+        let path = std::env::temp_dir().join(format!("proyecto_nave_{}_{}.stl", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = read(&path.to_string_lossy());
+        fs::remove_file(&path).ok();
+        result
+    }
+
+    fn assert_quad(model: &ImportedModel, name: &str) {
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.name, name);
+        // Los vértices de la arista compartida se unen y pasan por el cambio de ejes
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+        assert!(mesh.vertices.contains(&Vec3::new(1.0, -1.0, 0.0)));
+        let corners: Vec<Vec3> = mesh.indices[..3].iter().map(|&i| mesh.vertices[i as usize]).collect();
+        assert_eq!(corners, [Vec3::zeros(), Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, -1.0, 0.0)]);
+        assert!(mesh.normals.is_empty() && mesh.texcoords.is_empty() && mesh.material.is_none());
+    }
+
+    #[test]
+    fn imports_ascii_and_binary_stl() {
+        let ascii = with_file("ascii", &ascii_stl(), |path| StlImporter.import(path)).unwrap();
+        let binary = with_file("binario", &binary_stl(), |path| StlImporter.import(path)).unwrap();
+        assert_quad(&ascii, &format!("proyecto_nave_ascii_{}", std::process::id()));
+        assert_quad(&binary, &format!("proyecto_nave_binario_{}", std::process::id()));
+    }
+
+    #[test]
+    fn loaded_stl_gets_generated_normals() {
+        let obj = with_file("normales", &binary_stl(), |path| load_model(path, NormalMode::default())).unwrap();
+        let mesh = &obj.meshes()[0];
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        // El cambio de ejes gira el quad para que mire hacia -Z
+        assert!(mesh.normals.iter().all(|n| (n - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-6));
+    }
+
+    #[test]
+    fn truncated_binary_stl_is_a_parse_error() {
+        let mut bytes = binary_stl();
+        bytes.truncate(100);
+        let result = with_file("truncado", &bytes, |path| StlImporter.import(path));
+        assert!(matches!(result, Err(ModelError::Parse { .. })));
+    }
+}
@@ -0,0 +1,161 @@
+use nalgebra_glm::{Vec3, Mat4};
+use crate::vertex::Vertex;
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::obj::MaterialGroup;
+
+// Margen relativo alrededor de cada umbral para que un objeto justo en el límite
+// no cambie de nivel en cada cuadro
+pub const LOD_HYSTERESIS: f32 = 0.15;
+
+pub struct LodLevel {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    // Radio proyectado mínimo, en píxeles, para usar este nivel
+    pub min_screen_radius: f32,
+    // Partes de `indices` con su material; vacío si la malla usa un shader procedural
+    pub groups: Vec<MaterialGroup>,
+}
+
+impl LodLevel {
+    pub fn new((vertices, indices): (Vec<Vertex>, Vec<u32>), min_screen_radius: f32) -> Self {
+        LodLevel { vertices, indices, min_screen_radius, groups: Vec::new() }
+    }
+
+    pub fn with_groups(mut self, groups: Vec<MaterialGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    #[allow(dead_code)] // para depurar los niveles generados
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+// Un modelo con varias mallas, de la más detallada (nivel 0) a la más simple
+pub struct LodModel {
+    levels: Vec<LodLevel>,
+    sphere: BoundingSphere,
+    aabb: Aabb,
+}
+
+impl LodModel {
+    pub fn new(mut levels: Vec<LodLevel>) -> Self {
+        assert!(!levels.is_empty(), "un LodModel necesita al menos un nivel");
+        levels.sort_by(|a, b| b.min_screen_radius.total_cmp(&a.min_screen_radius));
+        if let Some(last) = levels.last_mut() {
+            last.min_screen_radius = 0.0;
+        }
+
+        let sphere = BoundingSphere::from_vertices(&levels[0].vertices);
+        let aabb = Aabb::from_vertices(&levels[0].vertices);
+        LodModel { levels, sphere, aabb }
+    }
+
+    #[allow(dead_code)] // modelos sin niveles simplificados
+    pub fn single(mesh: (Vec<Vertex>, Vec<u32>)) -> Self {
+        LodModel::new(vec![LodLevel::new(mesh, 0.0)])
+    }
+
+    pub fn level(&self, index: usize) -> &LodLevel {
+        &self.levels[index.min(self.levels.len() - 1)]
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Reemplaza los volúmenes calculados del nivel 0, p. ej. por los que ya trae el modelo
+    pub fn with_bounds(mut self, sphere: BoundingSphere, aabb: Aabb) -> Self {
+        self.sphere = sphere;
+        self.aabb = aabb;
+        self
+    }
+
+    // Volúmenes envolventes en espacio de objeto (del nivel más detallado)
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        self.sphere
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    // Elige el nivel para un radio proyectado en píxeles, partiendo del nivel actual
+    pub fn select(&self, screen_radius: f32, current: usize) -> usize {
+        let mut level = current.min(self.levels.len() - 1);
+
+        while level > 0 && screen_radius >= self.levels[level - 1].min_screen_radius * (1.0 + LOD_HYSTERESIS) {
+            level -= 1;
+        }
+        while level + 1 < self.levels.len() && screen_radius < self.levels[level].min_screen_radius * (1.0 - LOD_HYSTERESIS) {
+            level += 1;
+        }
+
+        level
+    }
+}
+
+// Radio en píxeles de una esfera vista desde la cámara
+pub fn projected_radius(sphere: &BoundingSphere, camera_position: Vec3, projection_matrix: &Mat4, viewport_height: f32) -> f32 {
+    let distance = (sphere.center - camera_position).magnitude();
+    if distance <= sphere.radius {
+        return f32::INFINITY;
+    }
+
+    // projection[(1, 1)] = 1 / tan(fov / 2)
+    sphere.radius / distance * projection_matrix[(1, 1)] * viewport_height * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    // Niveles para más de 100 px, más de 40 px y el resto
+    fn model() -> LodModel {
+        LodModel::new(vec![
+            LodLevel::new(primitives::uv_sphere(1.0, 8, 4), 0.0),
+            LodLevel::new(primitives::uv_sphere(1.0, 32, 16), 100.0),
+            LodLevel::new(primitives::uv_sphere(1.0, 16, 8), 40.0),
+        ])
+    }
+
+    #[test]
+    fn levels_are_sorted_from_the_most_detailed() {
+        let model = model();
+        let radii: Vec<f32> = (0..model.level_count()).map(|i| model.level(i).min_screen_radius).collect();
+        assert_eq!(radii, [100.0, 40.0, 0.0]);
+        assert_eq!(model.level(0).triangle_count(), primitives::uv_sphere(1.0, 32, 16).1.len() / 3);
+    }
+
+    #[test]
+    fn select_does_not_flicker_around_a_threshold() {
+        let model = model();
+        // Yendo y viniendo alrededor de 100 px dentro del margen, el nivel no cambia
+        for start in [0, 1] {
+            let mut level = start;
+            for radius in [95.0, 105.0, 90.0, 110.0, 100.0, 88.0, 112.0] {
+                level = model.select(radius, level);
+                assert_eq!(level, start, "{} px", radius);
+            }
+        }
+
+        // Pasando el margen sí cambia, y no vuelve hasta pasar el del otro lado
+        let threshold = 100.0;
+        let closer = threshold * (1.0 + LOD_HYSTERESIS);
+        let farther = threshold * (1.0 - LOD_HYSTERESIS);
+        assert_eq!(model.select(closer, 1), 0);
+        assert_eq!(model.select(farther - 0.1, 0), 1);
+        assert_eq!(model.select(farther, 0), 0);
+        assert_eq!(model.select(closer - 0.1, 1), 1);
+    }
+
+    #[test]
+    fn select_can_skip_levels() {
+        let model = model();
+        assert_eq!(model.select(10.0, 0), 2);
+        assert_eq!(model.select(1000.0, 2), 0);
+        assert_eq!(model.select(f32::INFINITY, 7), 0);
+    }
+}
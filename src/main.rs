@@ -1,32 +1,119 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions, MouseMode};
+
+use nalgebra_glm::{Vec3, Vec4, Mat4, look_at, perspective};
+use minifb::{Key, KeyRepeat, Window, WindowOptions, MouseMode};
 use std::f32::consts::PI;
+use std::path::Path;
+use std::rc::Rc;
 
 mod framebuffer;
 mod triangle;
 mod vertex;
 mod obj;
+mod export;
 mod color;
 mod fragment;
 mod shaders;
+mod renderer;
+mod primitives;
+mod lod;
+mod bounds;
+mod overlay;
+mod scene;
+mod simplify;
+mod mesh_cache;
+mod normals;
+mod tangents;
+mod cli;
+mod error;
+mod import;
+mod texture;
+mod bake;
+mod noise;
+mod planet;
+mod system;
+mod star;
+mod post;
+mod shadow;
+mod particles;
+mod belt;
 
 use framebuffer::Framebuffer;
-use vertex::Vertex;
-use obj::Obj;
-use triangle::triangle;
-use shaders::{vertex_shader, shade_star, shade_rocky, shade_gas_giant, shade_spaceship, 
-              shade_ice_planet, shade_desert_planet, shade_volcanic_planet};
+use obj::{MaterialMaps, Obj};
+use error::ModelError;
+use renderer::{RenderObject, RenderStats, render_scene};
+use framebuffer::BlendMode;
+use shaders::{Atmosphere, Clouds, Corona, Prominence, Rings, SurfaceMaterial, MAX_DISPLACEMENT, displace_mesh};
+use lod::{LodModel, LodLevel, projected_radius};
+use bounds::{Aabb, BoundingSphere};
+use scene::{solar_system, Scene};
+use simplify::SimplifyOptions;
+use bake::BakedSurface;
+use noise::Noise;
+use planet::PlanetParams;
+use shadow::{Occluder, ShadowMap, ShadowMode, Shadows, NO_CASTER};
+use particles::{Emitter, ParticleSystem};
+use belt::MAX_STRETCH;
+use noise::Rng;
 
 const WIDTH: usize = 800;
 const HEIGHT: usize = 600;
-
+// Modelo de la nave y carpeta donde se guardan sus niveles de detalle simplificados
+const NAVE_PATH: &str = "assets/CazaTie.obj";
+const LOD_CACHE_DIR: &str = "assets/cache";
+// Tiempo de la escena que avanza en cada cuadro
+const FRAME_TIME: f32 = 0.01;
+// Cada cuánto tiempo un micrometeorito golpea la nave, y escombros que suelta
+const IMPACT_INTERVAL: f32 = 1.5;
+const IMPACT_DEBRIS: usize = 120;
+// Aceleración con la que el viento solar empuja la cola de los cometas
+const COMET_WIND: f32 = 4.0;
+// Ancho de las texturas horneadas del camino rápido (tecla B)
+const BAKE_WIDTH: usize = 1024;
+// Intensidad del halo de lo que emite luz (tecla G)
+const BLOOM_STRENGTH: f32 = 0.8;
+// Intensidad de los rayos de luz y del lens flare de la estrella (tecla F)
+const SHAFTS_STRENGTH: f32 = 0.6;
+const FLARE_STRENGTH: f32 = 0.5;
+// Lado de cada cara del shadow map y malla de los planetas en la pasada de sombras
+const SHADOW_MAP_SIZE: usize = 512;
+const SHADOW_LOD: usize = 1;
+
+#[derive(Clone)]
 pub struct Uniforms {
     model_matrix: Mat4,
     view_matrix: Mat4,
     projection_matrix: Mat4,
+    #[allow(dead_code)] // triangle.rs convierte a pantalla con el tamaño fijo de la ventana
     viewport_matrix: Mat4,
     time: f32,
     shader_type: u32,
+    camera_position: Vec3,
+    light_position: Vec3,
+    // Luminosidad de la estrella (1 = el sol, ver star.rs), que escala su luz directa
+    light_intensity: f32,
+    atmosphere: Option<Atmosphere>,
+    clouds: Option<Clouds>,
+    rings: Option<Rings>,
+    corona: Option<Corona>,
+    // Protuberancia que se dibuja (ver Corona::prominences)
+    prominence: Option<Prominence>,
+    // Material horneado del cuerpo, si se usa el camino rápido
+    baked: Option<Rc<BakedSurface>>,
+    // Material de la parte de un modelo cargado que se dibuja (ver obj::MaterialGroup)
+    material: Option<SurfaceMaterial>,
+    // Texturas de ese material, que se muestrean con las UV del modelo
+    maps: Option<Rc<MaterialMaps>>,
+    // Ruido con la semilla del cuerpo que se dibuja
+    noise: Noise,
+    // Paleta y parámetros de la superficie del cuerpo
+    planet: PlanetParams,
+    // Sombras del cuadro y objeto que se dibuja, que no se sombrea a sí mismo
+    shadows: Option<Rc<Shadows>>,
+    shadow_id: u32,
+    // Copia que se dibuja en un render instanciado y sus parámetros (ver renderer::Instance)
+    instance: u32,
+    instance_params: Vec4,
+    instance_stretch: Vec3,
 }
 
 struct Camera {
@@ -154,56 +241,56 @@ fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
     )
 }
 
-fn render_model(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertices: &[Vertex], indices: &[u32]) {
-    let mut transformed_vertices = Vec::with_capacity(vertices.len());
-    for vertex in vertices {
-        transformed_vertices.push(vertex_shader(vertex, uniforms));
-    }
-
-    // Process triangles with early culling
-    for i in (0..indices.len()).step_by(3) {
-        let v1 = &transformed_vertices[indices[i] as usize];
-        let v2 = &transformed_vertices[indices[i+1] as usize];
-        let v3 = &transformed_vertices[indices[i+2] as usize];
+// Etiqueta "L<n>" y el recuadro de la esfera proyectada de cada objeto, con un color por nivel
+fn draw_lod_overlay(framebuffer: &mut Framebuffer, labels: &[(Vec3, f32, usize)], view_projection: &Mat4) {
+    const LOD_COLORS: [u32; 4] = [0x00FF00, 0xFFFF00, 0xFF8000, 0xFF0000];
 
-        // Early clip space culling - skip triangles completely outside view
-        let clip_coords = [v1.transformed_position, v2.transformed_position, v3.transformed_position];
-        if clip_coords.iter().all(|v| v.x.abs() > v.w.abs() * 1.5 || v.y.abs() > v.w.abs() * 1.5 || v.z < -v.w || v.z > v.w) {
+    for &(center, screen_radius, lod) in labels {
+        let clip = view_projection * center.push(1.0);
+        if clip.w <= 0.0 {
             continue;
         }
 
-        let fragments = triangle(v1, v2, v3, uniforms);
-        for fragment in fragments {
-            let x = fragment.position.x as usize;
-            let y = fragment.position.y as usize;
-
-            if x < WIDTH && y < HEIGHT {
-                let color_vec = match uniforms.shader_type {
-                    0 => shade_star(fragment.vertex_position, uniforms.time),
-                    1 => shade_rocky(fragment.vertex_position, uniforms.time),
-                    2 => shade_gas_giant(fragment.vertex_position, uniforms.time),
-                    3 => shade_spaceship(fragment.vertex_position, uniforms.time),
-                    4 => shade_ice_planet(fragment.vertex_position, uniforms.time),
-                    5 => shade_desert_planet(fragment.vertex_position, uniforms.time),
-                    6 => shade_volcanic_planet(fragment.vertex_position, uniforms.time),
-                    _ => Vec3::new(0.5, 0.5, 0.5), // Gris por defecto
-                };
+        let x = ((clip.x / clip.w) * 0.5 + 0.5) * framebuffer.width as f32;
+        let y = (1.0 - ((clip.y / clip.w) * 0.5 + 0.5)) * framebuffer.height as f32;
+        let r = screen_radius.min(framebuffer.width as f32);
+        let color = LOD_COLORS[lod.min(LOD_COLORS.len() - 1)];
 
-                let r = (color_vec.x * 255.0).clamp(0.0, 255.0) as u32;
-                let g = (color_vec.y * 255.0).clamp(0.0, 255.0) as u32;
-                let b = (color_vec.z * 255.0).clamp(0.0, 255.0) as u32;
-                let color = (r << 16) | (g << 8) | b;
-                
-                framebuffer.set_current_color(color);
-                framebuffer.point(x, y, fragment.depth);
-            }
-        }
+        overlay::draw_rect(framebuffer, (x - r) as i32, (y - r) as i32, (x + r) as i32, (y + r) as i32, color);
+        overlay::draw_text(framebuffer, (x - r) as i32 + 2, (y - r) as i32 + 2, &format!("L{}", lod), color, 2);
+    }
+}
+
+// Contadores del render en la esquina superior izquierda
+fn draw_stats_overlay(framebuffer: &mut Framebuffer, stats: &RenderStats) {
+    let lines = [
+        format!("OBJETOS: {}", stats.drawn),
+        format!("DESCARTADOS: {}", stats.culled),
+        format!("TRIANGULOS: {}", stats.triangles),
+    ];
+
+    for (i, line) in lines.iter().enumerate() {
+        overlay::draw_text(framebuffer, 8, 8 + i as i32 * 14, line, 0xFFFFFF, 2);
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(message) = cli::run(&args) {
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    view(solar_system());
+}
+
+// Abre la ventana y anima la escena hasta que se cierre
+pub fn view(scene: Scene) {
     let mut window = Window::new(
-        "Lab 5 - Sistema Solar (WASD: mover, Space/Shift: arriba/abajo, Mouse: rotar cámara)",
+        &format!("Lab 5 - {} (WASD: mover, Space/Shift: arriba/abajo, Mouse: rotar cámara)", scene.name),
         WIDTH,
         HEIGHT,
         WindowOptions::default(),
@@ -212,30 +299,164 @@ fn main() {
 
     let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
     
-    // Load planet model for celestial bodies
-    let planet_obj = Obj::load("assets/planeta.obj").expect("No se pudo cargar planeta.obj");
-    let (planet_vertices, planet_indices) = planet_obj.get_vertex_and_index_arrays();
+    // Generated spheres for celestial bodies, one per level of detail
+    let planet_lods = LodModel::new(vec![
+        LodLevel::new(primitives::uv_sphere(1.0, 64, 32), 150.0),
+        LodLevel::new(primitives::uv_sphere(1.0, 48, 24), 60.0),
+        LodLevel::new(primitives::uv_sphere(1.0, 24, 12), 20.0),
+        LodLevel::new(primitives::uv_sphere(1.0, 12, 6), 0.0),
+    ]);
+
+    // Load spaceship model; if it can't be loaded the scene runs without it
+    let nave_lods = match Obj::load(NAVE_PATH) {
+        Ok(nave_obj) => {
+            println!("{}", nave_obj.get_model_info());
+            let nave_triangles = nave_obj.triangle_count();
+            // Los niveles simplificados se leen de la caché si el modelo no cambió
+            let simplified = |target: usize| {
+                let cache = Path::new(LOD_CACHE_DIR).join(format!("CazaTie_{}.lod", target));
+                nave_obj.simplify_cached(&SimplifyOptions::new(target), NAVE_PATH, &cache)
+            };
+            let (medium, low) = (simplified(nave_triangles / 3), simplified(nave_triangles / 10));
+            // Una textura que falta no cambia nada en la ventana: esa parte se dibuja con el
+            // shader de la nave (ver Material::surface). `export` sí la reporta.
+            let warnings = [&nave_obj, &medium, &low].into_iter().flat_map(|obj| obj.warnings());
+            for warning in warnings.filter(|w| !matches!(w, ModelError::MissingTexture { .. })) {
+                eprintln!("Aviso: {}", warning);
+            }
 
-    // Load spaceship model
-    let nave_obj = Obj::load("assets/CazaTie.obj").expect("No se pudo cargar CazaTie.obj");
-    let (nave_vertices, nave_indices) = nave_obj.get_vertex_and_index_arrays();
+            // Cada nivel conserva los materiales del MTL, parte por parte
+            let level = |obj: &Obj, min_screen_radius: f32| {
+                LodLevel::new(obj.get_vertex_and_index_arrays(), min_screen_radius).with_groups(obj.material_groups())
+            };
+            let levels = vec![level(&nave_obj, 80.0), level(&medium, 30.0), level(&low, 0.0)];
+            Some(LodModel::new(levels).with_bounds(nave_obj.bounding_sphere(), nave_obj.bounding_box()))
+        }
+        Err(error) => {
+            eprintln!("No se pudo cargar la nave: {}", error);
+            None
+        }
+    };
+    let mut nave_lod = 0;
+    let nave_pos = Vec3::new(6.0, 4.0, 9.0); // Posición fija más alejada y arriba
+    let nave_rotation = Vec3::new(0.0, PI * 0.75, 0.0); // Ángulo fijo
+    let nave_model = create_model_matrix(nave_pos, 0.3, nave_rotation);
+
+    // Escape del motor (en la parte trasera de la cabina, -X en el modelo), superficie de la
+    // nave para los impactos y sus escombros
+    let mut exhaust = particles::engine_exhaust(1);
+    exhaust.transform = nave_model * create_model_matrix(Vec3::new(-1.0, 0.0, 0.0), 1.0, Vec3::zeros());
+    let nave_surface = nave_lods.as_ref().map(|lods| Emitter::mesh(&lods.level(0).vertices, &lods.level(0).indices));
+    let mut debris = particles::impact_debris(2);
+    let mut impact_rng = Rng::new(Noise::new(3));
+    let mut next_impact = IMPACT_INTERVAL;
+
+    let mut bodies = scene.bodies;
+    // La nave proyecta sombra con el id siguiente al de los cuerpos
+    let nave_id = bodies.len() as u32;
+    // Radio de la estrella, que las sombras analíticas tratan como un disco
+    let light_radius = bodies.iter().find(|body| body.shader_type == 0).map_or(0.0, |body| body.scale);
+    let light_intensity = bodies.iter().find_map(|body| body.star).map_or(1.0, |star| star.luminosity());
+
+    // Rings mesh of each ringed planet (radii are in planet radii), with its bounding volumes
+    let ring_meshes: Vec<_> = bodies
+        .iter()
+        .map(|body| body.rings.map(|rings| {
+            let (vertices, indices) = primitives::ring(rings.inner_radius, rings.outer_radius, 96, 4);
+            let sphere = BoundingSphere::from_vertices(&vertices);
+            let aabb = Aabb::from_vertices(&vertices);
+            (vertices, indices, sphere, aabb)
+        }))
+        .collect();
+
+    // Relieve de los cuerpos rocosos y volcánicos, horneado una vez en cada nivel de la esfera
+    // (ver shaders::displace_mesh); los demás cuerpos usan la esfera tal cual
+    let terrain_lods: Vec<Option<LodModel>> = bodies
+        .iter()
+        .map(|body| {
+            let noise = Noise::new(body.seed);
+            let levels: Option<Vec<_>> = (0..planet_lods.level_count())
+                .map(|index| {
+                    let sphere = planet_lods.level(index);
+                    displace_mesh(&sphere.vertices, &sphere.indices, body.shader_type, &noise, &body.planet)
+                        .map(|mesh| LodLevel::new(mesh, sphere.min_screen_radius))
+                })
+                .collect();
+            levels.map(LodModel::new)
+        })
+        .collect();
+
+    // Mallas del cinturón de asteroides, con volúmenes que cubren el estiramiento por
+    // instancia (ver belt::MAX_STRETCH)
+    let belt = scene.belt;
+    let belt_meshes: Vec<_> = belt
+        .as_ref()
+        .map(|belt| belt.meshes())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(vertices, indices)| {
+            let sphere = BoundingSphere::from_vertices(&vertices);
+            let aabb = Aabb::from_vertices(&vertices);
+            let sphere = BoundingSphere { radius: sphere.radius * MAX_STRETCH, ..sphere };
+            let aabb = Aabb { min: aabb.min * MAX_STRETCH, max: aabb.max * MAX_STRETCH };
+            (vertices, indices, sphere, aabb)
+        })
+        .collect();
+
+    // Cola de cada cometa, que sale de la superficie de su malla
+    let mut comet_tails: Vec<Option<ParticleSystem>> = bodies
+        .iter()
+        .map(|body| body.comet.then(|| {
+            let nucleus = planet_lods.level(2);
+            particles::comet_tail(&nucleus.vertices, &nucleus.indices, body.seed)
+        }))
+        .collect();
+
+    // Billboard de la corona y arco de las protuberancias de las estrellas
+    let (corona_vertices, corona_indices) = primitives::plane(2.0, 16);
+    let corona_bounds = (BoundingSphere::from_vertices(&corona_vertices), Aabb::from_vertices(&corona_vertices));
+    let (arc_vertices, arc_indices) = primitives::arc(1.0, 0.2, PI, 32, 8);
+    let arc_bounds = (BoundingSphere::from_vertices(&arc_vertices), Aabb::from_vertices(&arc_vertices));
+
+    // Bounding volumes for per-object frustum culling
+    let planet_sphere = planet_lods.bounding_sphere();
+    let planet_box = planet_lods.bounding_box();
+    // Las montañas desplazadas sobresalen de la esfera (ver shaders::displace_mesh)
+    let relief = 1.0 + MAX_DISPLACEMENT;
+    let relief_sphere = BoundingSphere { radius: planet_sphere.radius * relief, ..planet_sphere };
+    let relief_box = Aabb { min: planet_box.min * relief, max: planet_box.max * relief };
 
     let projection_matrix = perspective(WIDTH as f32 / HEIGHT as f32, 45.0 * PI / 180.0, 0.1, 100.0);
     let viewport_matrix = create_viewport_matrix(WIDTH as f32, HEIGHT as f32);
 
-    let mut camera = Camera::new(Vec3::new(0.0, 4.0, 15.0));
+    let mut camera = Camera::new(scene.camera_position);
     let mut time = 0.0;
     let mut last_mouse_pos: Option<(f32, f32)> = None;
+    let mut show_debug = false;
+    let mut use_baked = false;
+    // Siguiente cuerpo por hornear: con B activo se hornea uno por cuadro, así la ventana no
+    // se congela; los que faltan siguen con el shader procedural
+    let mut next_bake = 0;
+    let mut use_bloom = true;
+    let mut use_flare = true;
+    let mut shadow_mode = ShadowMode::Analytic;
+    // El shadow map se reserva una vez y se vacía en cada cuadro
+    let mut shadow_map = Rc::new(Shadows::Map(ShadowMap::new(SHADOW_MAP_SIZE, Vec3::zeros())));
 
     println!("Controles:");
     println!("  WASD: Mover cámara");
     println!("  Space/Shift: Subir/Bajar");
     println!("  Mouse: Rotar cámara");
+    println!("  L: Mostrar/ocultar depuración (LOD y estadísticas)");
+    println!("  B: Usar texturas horneadas (más rápido, congela la animación de las superficies; se hornea un cuerpo por cuadro)");
+    println!("  G: Activar/desactivar el bloom de lo que emite luz");
+    println!("  F: Activar/desactivar el lens flare y los rayos de luz de la estrella");
+    println!("  H: Cambiar el modo de sombras (analíticas, shadow map, desactivadas)");
     println!("  ESC: Salir");
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
         framebuffer.clear();
-        time += 0.01;
+        time += FRAME_TIME;
 
         // Camera movement controls
         if window.is_key_down(Key::W) { camera.move_forward(); }
@@ -244,6 +465,25 @@ fn main() {
         if window.is_key_down(Key::D) { camera.move_right(); }
         if window.is_key_down(Key::Space) { camera.move_up(); }
         if window.is_key_down(Key::LeftShift) { camera.move_down(); }
+        if window.is_key_pressed(Key::L, KeyRepeat::No) { show_debug = !show_debug; }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) { use_bloom = !use_bloom; }
+        if window.is_key_pressed(Key::F, KeyRepeat::No) { use_flare = !use_flare; }
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            shadow_mode = shadow_mode.next();
+            println!("Sombras: {}", shadow_mode.name());
+        }
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            use_baked = !use_baked;
+            if use_baked && next_bake < bodies.len() {
+                println!("Horneando texturas de {}x{}, un cuerpo por cuadro...", BAKE_WIDTH, BAKE_WIDTH / 2);
+            }
+        }
+        // Cada cuerpo se hornea una sola vez, con el aspecto del instante en que le toca
+        if use_baked && next_bake < bodies.len() {
+            let body = &mut bodies[next_bake];
+            body.baked = bake::bake_equirectangular(body.surface(), time, BAKE_WIDTH).map(Rc::new);
+            next_bake += 1;
+        }
 
         // Mouse camera control - solo cuando se presiona botón derecho
         if window.get_mouse_down(minifb::MouseButton::Right) {
@@ -261,133 +501,243 @@ fn main() {
         }
 
         let view_matrix = camera.get_view_matrix();
-
-        // Render Sun (center, no rotation, much bigger size)
-        let sun_rotation = Vec3::new(0.0, 0.0, 0.0); // No rotation
-        let sun_model = create_model_matrix(Vec3::new(0.0, 0.0, 0.0), 5.0, sun_rotation);
-        let sun_uniforms = Uniforms {
-            model_matrix: sun_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 0, // Star shader
-        };
-        render_model(&mut framebuffer, &sun_uniforms, &planet_vertices, &planet_indices);
-
-        // Render Rocky Planet (orbiting)
-        let rocky_angle = time * 0.3;
-        let rocky_orbit_radius = 8.0;
-        let rocky_pos = Vec3::new(
-            rocky_angle.cos() * rocky_orbit_radius,
-            0.0,
-            rocky_angle.sin() * rocky_orbit_radius,
-        );
-        let rocky_rotation = Vec3::new(0.0, time * 0.5, 0.0);
-        let rocky_model = create_model_matrix(rocky_pos, 0.8, rocky_rotation);
-        let rocky_uniforms = Uniforms {
-            model_matrix: rocky_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 1, // Rocky shader
-        };
-        render_model(&mut framebuffer, &rocky_uniforms, &planet_vertices, &planet_indices);
-
-        // Render Gas Giant (orbiting in opposite direction)
-        let gas_angle = time * 0.15;
-        let gas_orbit_radius = 12.0;
-        let gas_pos = Vec3::new(
-            -gas_angle.cos() * gas_orbit_radius,
-            0.5,
-            gas_angle.sin() * gas_orbit_radius,
-        );
-        let gas_rotation = Vec3::new(0.0, time * 0.3, 0.0);
-        let gas_model = create_model_matrix(gas_pos, 1.2, gas_rotation);
-        let gas_uniforms = Uniforms {
-            model_matrix: gas_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 2, // Gas giant shader
-        };
-        render_model(&mut framebuffer, &gas_uniforms, &planet_vertices, &planet_indices);
-
-        // Render Ice Planet (orbiting)
-        let ice_angle = time * 0.25;
-        let ice_orbit_radius = 10.0;
-        let ice_pos = Vec3::new(
-            (ice_angle + PI * 0.5).cos() * ice_orbit_radius,
-            -0.3,
-            (ice_angle + PI * 0.5).sin() * ice_orbit_radius,
-        );
-        let ice_rotation = Vec3::new(0.0, time * 0.4, 0.0);
-        let ice_model = create_model_matrix(ice_pos, 0.7, ice_rotation);
-        let ice_uniforms = Uniforms {
-            model_matrix: ice_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 4, // Ice planet shader
-        };
-        render_model(&mut framebuffer, &ice_uniforms, &planet_vertices, &planet_indices);
-
-        // Render Desert Planet (orbiting)
-        let desert_angle = time * 0.35;
-        let desert_orbit_radius = 6.5;
-        let desert_pos = Vec3::new(
-            (desert_angle + PI).cos() * desert_orbit_radius,
-            0.2,
-            (desert_angle + PI).sin() * desert_orbit_radius,
-        );
-        let desert_rotation = Vec3::new(0.0, time * 0.6, 0.0);
-        let desert_model = create_model_matrix(desert_pos, 0.6, desert_rotation);
-        let desert_uniforms = Uniforms {
-            model_matrix: desert_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 5, // Desert planet shader
+        let sun_position = Vec3::new(0.0, 0.0, 0.0);
+        let mut objects = Vec::new();
+        let mut lod_labels = Vec::new();
+
+        // Sombras entre cuerpos; la estrella no proyecta sombra
+        let shadows = match shadow_mode {
+            ShadowMode::Off => None,
+            ShadowMode::Analytic => {
+                let occluders = bodies
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, body)| body.shader_type != 0)
+                    .map(|(id, body)| Occluder { center: body.position(time), radius: body.scale, id: id as u32 })
+                    .collect();
+                Some(Rc::new(Shadows::Analytic { light_radius, occluders }))
+            }
+            ShadowMode::Map => {
+                // Los uniforms del cuadro anterior ya se soltaron, así que nadie más lo comparte
+                if let Some(Shadows::Map(map)) = Rc::get_mut(&mut shadow_map) {
+                    map.clear(sun_position);
+                    for (id, body) in bodies.iter().enumerate().filter(|(_, body)| body.shader_type != 0) {
+                        // Las montañas también proyectan sombra
+                        let caster = terrain_lods[id].as_ref().unwrap_or(&planet_lods).level(SHADOW_LOD);
+                        map.render(&body.model_matrix(time), &caster.vertices, &caster.indices, id as u32);
+                    }
+                    if let Some(nave_lods) = &nave_lods {
+                        let nave_mesh = nave_lods.level(nave_lod);
+                        map.render(&nave_model, &nave_mesh.vertices, &nave_mesh.indices, nave_id);
+                    }
+                }
+                Some(Rc::clone(&shadow_map))
+            }
         };
-        render_model(&mut framebuffer, &desert_uniforms, &planet_vertices, &planet_indices);
-
-        // Render Volcanic Planet (orbiting)
-        let volcanic_angle = time * 0.4;
-        let volcanic_orbit_radius = 14.0;
-        let volcanic_pos = Vec3::new(
-            (volcanic_angle + PI * 1.5).cos() * volcanic_orbit_radius,
-            -0.5,
-            (volcanic_angle + PI * 1.5).sin() * volcanic_orbit_radius,
-        );
-        let volcanic_rotation = Vec3::new(0.0, time * 0.7, 0.0);
-        let volcanic_model = create_model_matrix(volcanic_pos, 0.9, volcanic_rotation);
-        let volcanic_uniforms = Uniforms {
-            model_matrix: volcanic_model,
+
+        let base_uniforms = Uniforms {
+            model_matrix: Mat4::identity(),
             view_matrix,
             projection_matrix,
             viewport_matrix,
             time,
-            shader_type: 6, // Volcanic planet shader
+            shader_type: 0,
+            camera_position: camera.position,
+            light_position: sun_position,
+            light_intensity,
+            atmosphere: None,
+            clouds: None,
+            rings: None,
+            corona: None,
+            prominence: None,
+            baked: None,
+            material: None,
+            maps: None,
+            noise: Noise::default(),
+            planet: shaders::default_planet(0, 0),
+            shadows,
+            shadow_id: NO_CASTER,
+            instance: 0,
+            instance_params: Vec4::zeros(),
+            instance_stretch: Vec3::repeat(1.0),
         };
-        render_model(&mut framebuffer, &volcanic_uniforms, &planet_vertices, &planet_indices);
+
+        for (id, (body, ring_mesh)) in bodies.iter_mut().zip(&ring_meshes).enumerate() {
+            let model_matrix = body.model_matrix(time);
+
+            // LOD según el tamaño del planeta en pantalla
+            let world_sphere = planet_sphere.transformed(&model_matrix);
+            let screen_radius = projected_radius(&world_sphere, camera.position, &projection_matrix, HEIGHT as f32);
+            body.lod = planet_lods.select(screen_radius, body.lod);
+            let mesh = planet_lods.level(body.lod);
+            lod_labels.push((world_sphere.center, screen_radius, body.lod));
+
+            let uniforms = Uniforms {
+                model_matrix,
+                shader_type: body.shader_type,
+                atmosphere: body.atmosphere,
+                clouds: body.clouds,
+                rings: body.rings,
+                corona: body.corona,
+                baked: body.baked.clone().filter(|_| use_baked),
+                noise: Noise::new(body.seed),
+                planet: body.planet,
+                shadow_id: id as u32,
+                ..base_uniforms.clone()
+            };
+
+            if let Some(shell_matrix) = body.atmosphere_matrix(time) {
+                let halo = Uniforms {
+                    model_matrix: shell_matrix,
+                    shader_type: 7, // Atmosphere shader
+                    ..uniforms.clone()
+                };
+                objects.push(
+                    RenderObject::transparent(halo, &mesh.vertices, &mesh.indices, BlendMode::Additive)
+                        .with_bounds(planet_sphere, planet_box),
+                );
+            }
+
+            if let Some(clouds_matrix) = body.clouds_matrix(time) {
+                let cloud_uniforms = Uniforms {
+                    model_matrix: clouds_matrix,
+                    shader_type: 9, // Clouds shader
+                    ..uniforms.clone()
+                };
+                objects.push(
+                    RenderObject::transparent(cloud_uniforms, &mesh.vertices, &mesh.indices, BlendMode::Alpha)
+                        .with_bounds(planet_sphere, planet_box),
+                );
+            }
+
+            if let (Some(rings_matrix), Some((ring_vertices, ring_indices, ring_sphere, ring_box))) = (body.rings_matrix(time), ring_mesh) {
+                let ring_uniforms = Uniforms {
+                    model_matrix: rings_matrix,
+                    shader_type: 8, // Rings shader
+                    ..uniforms.clone()
+                };
+                objects.push(
+                    RenderObject::transparent(ring_uniforms, ring_vertices, ring_indices, BlendMode::Alpha)
+                        .with_bounds(*ring_sphere, *ring_box),
+                );
+            }
+
+            if let Some(corona_matrix) = body.corona_matrix(time, camera.position) {
+                let corona_uniforms = Uniforms {
+                    model_matrix: corona_matrix,
+                    shader_type: 10, // Corona shader
+                    ..uniforms.clone()
+                };
+                objects.push(
+                    RenderObject::transparent(corona_uniforms, &corona_vertices, &corona_indices, BlendMode::Additive)
+                        .with_bounds(corona_bounds.0, corona_bounds.1),
+                );
+            }
+
+            for (prominence, prominence_matrix) in body.prominences(time) {
+                let prominence_uniforms = Uniforms {
+                    model_matrix: prominence_matrix,
+                    shader_type: 11, // Prominence shader
+                    prominence: Some(prominence),
+                    ..uniforms.clone()
+                };
+                objects.push(
+                    RenderObject::transparent(prominence_uniforms, &arc_vertices, &arc_indices, BlendMode::Additive)
+                        .with_bounds(arc_bounds.0, arc_bounds.1),
+                );
+            }
+
+            let surface = terrain_lods[id].as_ref().map_or(mesh, |lods| lods.level(body.lod));
+            objects.push(RenderObject::opaque(uniforms, &surface.vertices, &surface.indices).with_bounds(relief_sphere, relief_box));
+        }
+
+        // Cinturón de asteroides: un dibujo instanciado por forma
+        let belt_instances = belt.as_ref().map(|belt| belt.instances(time)).unwrap_or_default();
+        if let Some(belt) = &belt {
+            let belt_uniforms = Uniforms {
+                shader_type: 12, // Asteroid shader
+                noise: Noise::new(belt.seed),
+                ..base_uniforms.clone()
+            };
+            for ((vertices, indices, sphere, aabb), instances) in belt_meshes.iter().zip(&belt_instances) {
+                objects.push(RenderObject::instanced(belt_uniforms.clone(), vertices, indices, instances).with_bounds(*sphere, *aabb));
+            }
+        }
 
         // Render Spaceship (TIE Fighter) - Static position
-        let nave_pos = Vec3::new(6.0, 4.0, 9.0); // Posición fija más alejada y arriba
-        let nave_rotation = Vec3::new(0.0, PI * 0.75, 0.0); // Ángulo fijo
-        let nave_model = create_model_matrix(nave_pos, 0.3, nave_rotation);
-        let nave_uniforms = Uniforms {
-            model_matrix: nave_model,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            shader_type: 3, // No shader (default color)
-        };
-        render_model(&mut framebuffer, &nave_uniforms, &nave_vertices, &nave_indices);
+        if let Some(nave_lods) = &nave_lods {
+            let nave_sphere = nave_lods.bounding_sphere().transformed(&nave_model);
+            let screen_radius = projected_radius(&nave_sphere, camera.position, &projection_matrix, HEIGHT as f32);
+            nave_lod = nave_lods.select(screen_radius, nave_lod);
+            lod_labels.push((nave_sphere.center, screen_radius, nave_lod));
+            let nave_mesh = nave_lods.level(nave_lod);
+            let nave_uniforms = Uniforms {
+                model_matrix: nave_model,
+                shader_type: 3, // Materiales del modelo con paneles en relieve
+                shadow_id: nave_id,
+                ..base_uniforms.clone()
+            };
+            for group in &nave_mesh.groups {
+                let group_uniforms = Uniforms { material: group.material, maps: group.maps.clone(), ..nave_uniforms.clone() };
+                objects.push(
+                    RenderObject::opaque(group_uniforms, &nave_mesh.vertices, &nave_mesh.indices[group.indices.clone()])
+                        .with_bounds(nave_lods.bounding_sphere(), nave_lods.bounding_box()),
+                );
+            }
+        }
+
+        // Partículas: el viento solar empuja la cola de los cometas lejos de la estrella. Se
+        // dibujan en la pasada transparente, ordenadas con las atmósferas y las nubes.
+        for (body, tail) in bodies.iter().zip(comet_tails.iter_mut()) {
+            if let Some(tail) = tail {
+                tail.transform = body.model_matrix(time);
+                tail.acceleration = (body.position(time) - sun_position).normalize() * COMET_WIND;
+                tail.update(FRAME_TIME);
+            }
+        }
+        if let Some(nave_surface) = &nave_surface {
+            if time >= next_impact {
+                next_impact += IMPACT_INTERVAL;
+                let (point, normal) = nave_surface.sample(&mut impact_rng);
+                let world_point = (nave_model * point.push(1.0)).xyz();
+                let world_normal = (nave_model * normal.push(0.0)).xyz();
+                debris.transform = particles::aligned(world_point, world_normal);
+                debris.burst(IMPACT_DEBRIS);
+            }
+            exhaust.update(FRAME_TIME);
+            debris.update(FRAME_TIME);
+        }
+        let nave_particles = nave_surface.as_ref().map(|_| [&exhaust, &debris]);
+        for system in comet_tails.iter().flatten().chain(nave_particles.into_iter().flatten()) {
+            objects.push(RenderObject::particles(base_uniforms.clone(), system));
+        }
+
+        let stats = render_scene(&mut framebuffer, &objects);
+
+        // La estrella en pantalla, para los efectos que dependen de si se la ve
+        let sun = bodies
+            .iter()
+            .find(|body| body.shader_type == 0)
+            .filter(|_| use_flare)
+            .and_then(|star| {
+                post::SunScreen::project(star.position(time), star.scale, star.planet.palette[0], camera.position, &view_matrix, &projection_matrix, &framebuffer)
+            })
+            .map(|sun| (sun, post::sun_visibility(&framebuffer, &sun)));
+
+        if let Some((sun, visibility)) = &sun {
+            post::light_shafts(&mut framebuffer, sun, *visibility, SHAFTS_STRENGTH);
+        }
+        if use_bloom {
+            post::bloom(&mut framebuffer, BLOOM_STRENGTH);
+        }
+        if let Some((sun, visibility)) = &sun {
+            post::lens_flare(&mut framebuffer, sun, *visibility, FLARE_STRENGTH);
+        }
+
+        if show_debug {
+            draw_lod_overlay(&mut framebuffer, &lod_labels, &(projection_matrix * view_matrix));
+            draw_stats_overlay(&mut framebuffer, &stats);
+        }
 
         window
             .update_with_buffer(&framebuffer.buffer, WIDTH, HEIGHT)
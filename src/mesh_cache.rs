@@ -0,0 +1,157 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use crate::obj::Mesh;
+
+// Caché en disco de mallas ya calculadas (los niveles de detalle simplificados de la nave),
+// para no repetir la simplificación en cada arranque. El archivo guarda el tamaño y la fecha
+// de modificación del modelo del que salieron las mallas; si el modelo cambia, la caché deja
+// de valer. Formato binario little-endian, sin pérdida de precisión.
+
+// Cambia si cambia el formato o el resultado de la simplificación
+const MAGIC: &[u8; 8] = b"NAVELOD\x01";
+const NO_MATERIAL: u32 = u32::MAX;
+
+// Tamaño y fecha de modificación (en nanosegundos) del archivo de origen
+fn source_stamp(source: &str) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(source)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|error| io::Error::other(error.to_string()))?;
+    Ok((metadata.len(), modified.as_nanos() as u64))
+}
+
+// Las mallas guardadas en `path` si se calcularon a partir de la versión actual de `source`
+pub fn read(path: &Path, source: &str) -> Option<Vec<Mesh>> {
+    let stamp = source_stamp(source).ok()?;
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic).ok()?;
+    if &magic != MAGIC || (read_u64(&mut reader).ok()?, read_u64(&mut reader).ok()?) != stamp {
+        return None;
+    }
+    read_meshes(&mut reader).ok()
+}
+
+pub fn write(path: &Path, source: &str, meshes: &[Mesh]) -> io::Result<()> {
+    let (length, modified) = source_stamp(source)?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&length.to_le_bytes())?;
+    out.write_all(&modified.to_le_bytes())?;
+    out.write_all(&(meshes.len() as u32).to_le_bytes())?;
+    for mesh in meshes {
+        write_u32(&mut out, mesh.name.len() as u32)?;
+        out.write_all(mesh.name.as_bytes())?;
+        write_u32(&mut out, mesh.material.map_or(NO_MATERIAL, |index| index as u32))?;
+        write_floats(&mut out, mesh.vertices.iter().flat_map(|v| [v.x, v.y, v.z]), mesh.vertices.len())?;
+        write_floats(&mut out, mesh.normals.iter().flat_map(|n| [n.x, n.y, n.z]), mesh.normals.len())?;
+        write_floats(&mut out, mesh.texcoords.iter().flat_map(|t| [t.x, t.y]), mesh.texcoords.len())?;
+        write_u32(&mut out, mesh.indices.len() as u32)?;
+        for &index in &mesh.indices {
+            write_u32(&mut out, index)?;
+        }
+    }
+    out.flush()
+}
+
+fn write_u32(out: &mut impl Write, value: u32) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+// Cantidad de elementos seguida de sus componentes
+fn write_floats(out: &mut impl Write, values: impl Iterator<Item = f32>, count: usize) -> io::Result<()> {
+    write_u32(out, count as u32)?;
+    for value in values {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// `components` flotantes por elemento, agrupados con `build`
+fn read_floats<T>(reader: &mut impl Read, components: usize, build: impl Fn(&[f32]) -> T) -> io::Result<Vec<T>> {
+    let count = read_u32(reader)? as usize;
+    let mut bytes = vec![0; count * components * 4];
+    reader.read_exact(&mut bytes)?;
+    let floats: Vec<f32> = bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    Ok(floats.chunks_exact(components).map(build).collect())
+}
+
+fn read_meshes(reader: &mut impl Read) -> io::Result<Vec<Mesh>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let count = read_u32(reader)?;
+    let mut meshes = Vec::new();
+    for _ in 0..count {
+        let mut name = vec![0; read_u32(reader)? as usize];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| invalid("nombre inválido"))?;
+        let material = Some(read_u32(reader)?).filter(|&index| index != NO_MATERIAL).map(|index| index as usize);
+        let vertices = read_floats(reader, 3, |v| Vec3::new(v[0], v[1], v[2]))?;
+        let normals = read_floats(reader, 3, |n| Vec3::new(n[0], n[1], n[2]))?;
+        let texcoords = read_floats(reader, 2, |t| Vec2::new(t[0], t[1]))?;
+        let indices = (0..read_u32(reader)?).map(|_| read_u32(reader)).collect::<io::Result<Vec<u32>>>()?;
+
+        let mesh = Mesh { name, vertices, normals, texcoords, indices, material };
+        mesh.validate().map_err(|problem| invalid(&problem.to_string()))?;
+        meshes.push(mesh);
+    }
+    Ok(meshes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    #[test]
+    fn round_trips_meshes_while_the_source_is_unchanged() {
+        let directory = std::env::temp_dir().join(format!("proyecto_nave_cache_{}", std::process::id()));
+        let source = directory.join("modelo.obj");
+        let cache = directory.join("modelo_100.lod");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(&source, "v 0 0 0\n").unwrap();
+        let source = source.to_string_lossy();
+
+        let (vertices, indices) = primitives::uv_sphere(1.0, 8, 4);
+        let mut with_material = Mesh::from_vertex_arrays("esfera", &vertices, &indices);
+        with_material.material = Some(2);
+        let mut without_normals = Mesh::from_vertex_arrays("sin normales", &vertices, &indices);
+        without_normals.normals.clear();
+        write(&cache, &source, &[with_material, without_normals]).unwrap();
+
+        let meshes = read(&cache, &source).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].material, Some(2));
+        assert_eq!(meshes[1].material, None);
+        assert_eq!(meshes[0].vertices, vertices.iter().map(|v| v.position).collect::<Vec<_>>());
+        assert_eq!(meshes[0].texcoords.len(), vertices.len());
+        assert!(meshes[1].normals.is_empty());
+        assert_eq!(meshes[1].indices, indices);
+
+        // Otro contenido en el modelo invalida la caché
+        fs::write(&*source, "v 0 0 0\nv 1 0 0\n").unwrap();
+        assert!(read(&cache, &source).is_none());
+
+        fs::remove_dir_all(&directory).ok();
+    }
+}
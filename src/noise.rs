@@ -0,0 +1,387 @@
+use nalgebra_glm::{Vec3, Vec4};
+
+// Ruido procedural con semilla. Todo sale de un hash entero de las coordenadas de la
+// rejilla, así que no hay tablas que construir, no se pierde precisión lejos del origen
+// (como con el hash de sin(dot) * 43758.5453) y cada semilla da un patrón distinto.
+// Los ruidos básicos devuelven valores en [-1, 1] con media 0.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Noise {
+    seed: u32,
+}
+
+// Distancias a los dos puntos característicos más cercanos de Worley y un valor
+// aleatorio en [0, 1) propio de la celda más cercana
+#[derive(Debug, Clone, Copy)]
+pub struct Worley {
+    pub f1: f32,
+    pub f2: f32,
+    pub cell: f32,
+}
+
+// Gradientes de Perlin: las 12 aristas del cubo, con 4 repetidas para indexar con 4 bits
+const GRADIENTS_3D: [[f32; 3]; 16] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [0.0, -1.0, 1.0], [0.0, -1.0, -1.0],
+];
+
+// Factores de sesgo y corrección de la rejilla simplex
+const SKEW_3D: f32 = 1.0 / 3.0;
+const UNSKEW_3D: f32 = 1.0 / 6.0;
+const SKEW_4D: f32 = 0.309_017; // (sqrt(5) - 1) / 4
+const UNSKEW_4D: f32 = 0.138_196_6; // (5 - sqrt(5)) / 20
+
+// Desplazamientos entre las componentes de domain warping, para que no estén correlacionadas
+const WARP_OFFSETS: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [5.2, 1.3, 2.8], [1.7, 9.2, 4.1]];
+
+impl Noise {
+    pub fn new(seed: u32) -> Self {
+        Noise { seed }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    // Otra instancia independiente derivada de esta, para capas que no deben coincidir
+    pub fn derive(&self, salt: u32) -> Noise {
+        Noise::new(self.hash(salt as i32, 0x5EED, 0, 0))
+    }
+
+    // Hash de un punto entero de la rejilla (hasta 4 dimensiones)
+    pub fn hash(&self, x: i32, y: i32, z: i32, w: i32) -> u32 {
+        let mut h = self.seed.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+        for v in [x, y, z, w] {
+            h = (h ^ v as u32).wrapping_mul(0x27D4_EB2D);
+            h ^= h >> 15;
+        }
+        // Mezcla final de MurmurHash3
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85EB_CA6B);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xC2B2_AE35);
+        h ^ (h >> 16)
+    }
+
+    // Valor aleatorio en [0, 1) para una celda entera
+    pub fn random(&self, x: i32, y: i32, z: i32, w: i32) -> f32 {
+        (self.hash(x, y, z, w) >> 8) as f32 / (1 << 24) as f32
+    }
+
+    // Ruido de gradiente de Perlin con interpolación quíntica
+    pub fn perlin(&self, p: Vec3) -> f32 {
+        let cell = p.map(|x| x.floor());
+        let f = p - cell;
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let fade = f.map(|t| t * t * t * (t * (t * 6.0 - 15.0) + 10.0));
+
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let g = GRADIENTS_3D[(self.hash(x + dx, y + dy, z + dz, 0) & 15) as usize];
+            let d = f - Vec3::new(dx as f32, dy as f32, dz as f32);
+            g[0] * d.x + g[1] * d.y + g[2] * d.z
+        };
+        let mix = |a: f32, b: f32, t: f32| a + t * (b - a);
+
+        mix(
+            mix(
+                mix(corner(0, 0, 0), corner(1, 0, 0), fade.x),
+                mix(corner(0, 1, 0), corner(1, 1, 0), fade.x),
+                fade.y,
+            ),
+            mix(
+                mix(corner(0, 0, 1), corner(1, 0, 1), fade.x),
+                mix(corner(0, 1, 1), corner(1, 1, 1), fade.x),
+                fade.y,
+            ),
+            fade.z,
+        )
+    }
+
+    // Simplex 3D (Perlin 2001, en la formulación de Gustavson): 4 esquinas por punto en vez de 8
+    pub fn simplex3(&self, p: Vec3) -> f32 {
+        let s = (p.x + p.y + p.z) * SKEW_3D;
+        let cell = (p + Vec3::repeat(s)).map(|x| x.floor());
+        let t = (cell.x + cell.y + cell.z) * UNSKEW_3D;
+        let d0 = p - (cell - Vec3::repeat(t));
+
+        // Orden de los ejes de mayor a menor: define el recorrido por el tetraedro
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| d0[b].total_cmp(&d0[a]));
+        let mut offsets = [[0i32; 3]; 4];
+        for step in 1..4 {
+            offsets[step] = offsets[step - 1];
+            offsets[step][order[step - 1]] = 1;
+        }
+
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let mut total = 0.0;
+        for (i, o) in offsets.iter().enumerate() {
+            let d = d0 - Vec3::new(o[0] as f32, o[1] as f32, o[2] as f32) + Vec3::repeat(i as f32 * UNSKEW_3D);
+            let falloff = 0.6 - d.magnitude_squared();
+            if falloff > 0.0 {
+                let g = GRADIENTS_3D[(self.hash(x + o[0], y + o[1], z + o[2], 0) & 15) as usize];
+                total += falloff.powi(4) * (g[0] * d.x + g[1] * d.y + g[2] * d.z);
+            }
+        }
+
+        32.0 * total
+    }
+
+    // Simplex 4D; la cuarta coordenada suele ser el tiempo, para que la superficie
+    // evolucione en vez de desplazarse
+    pub fn simplex4(&self, p: Vec4) -> f32 {
+        let s = (p.x + p.y + p.z + p.w) * SKEW_4D;
+        let cell = (p + Vec4::repeat(s)).map(|x| x.floor());
+        let t = (cell.x + cell.y + cell.z + cell.w) * UNSKEW_4D;
+        let d0 = p - (cell - Vec4::repeat(t));
+
+        let mut order = [0, 1, 2, 3];
+        order.sort_by(|&a, &b| d0[b].total_cmp(&d0[a]));
+        let mut offsets = [[0i32; 4]; 5];
+        for step in 1..5 {
+            offsets[step] = offsets[step - 1];
+            offsets[step][order[step - 1]] = 1;
+        }
+
+        let c = [cell.x as i32, cell.y as i32, cell.z as i32, cell.w as i32];
+        let mut total = 0.0;
+        for (i, o) in offsets.iter().enumerate() {
+            let d = d0 - Vec4::new(o[0] as f32, o[1] as f32, o[2] as f32, o[3] as f32) + Vec4::repeat(i as f32 * UNSKEW_4D);
+            let falloff = 0.6 - d.magnitude_squared();
+            if falloff > 0.0 {
+                // 32 gradientes: los puntos medios de las aristas del hipercubo
+                let h = self.hash(c[0] + o[0], c[1] + o[1], c[2] + o[2], c[3] + o[3]);
+                let zero = (h & 3) as usize;
+                let signs = h >> 2;
+                let mut dot = 0.0;
+                for axis in 0..4 {
+                    if axis != zero {
+                        let sign = if signs & (1 << axis) != 0 { -1.0 } else { 1.0 };
+                        dot += sign * d[axis];
+                    }
+                }
+                total += falloff.powi(4) * dot;
+            }
+        }
+
+        27.0 * total
+    }
+
+    // Ruido celular: un punto característico por celda, buscando en las 27 vecinas
+    pub fn worley(&self, p: Vec3) -> Worley {
+        let cell = p.map(|x| x.floor());
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let mut result = Worley { f1: f32::MAX, f2: f32::MAX, cell: 0.0 };
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let (cx, cy, cz) = (x + dx, y + dy, z + dz);
+                    let feature = Vec3::new(cx as f32, cy as f32, cz as f32)
+                        + Vec3::new(self.random(cx, cy, cz, 1), self.random(cx, cy, cz, 2), self.random(cx, cy, cz, 3));
+                    let d = (p - feature).magnitude();
+                    if d < result.f1 {
+                        result.f2 = result.f1;
+                        result.f1 = d;
+                        result.cell = self.random(cx, cy, cz, 4);
+                    } else if d < result.f2 {
+                        result.f2 = d;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    // Suma de octavas de Perlin normalizada a [-1, 1]; persistence es la ganancia de
+    // amplitud y lacunarity la de frecuencia entre octavas
+    pub fn fbm(&self, p: Vec3, octaves: u32, persistence: f32, lacunarity: f32) -> f32 {
+        self.octaves(octaves, persistence, lacunarity, |noise, frequency| noise.perlin(p * frequency))
+    }
+
+    // fBm de simplex 4D, para superficies animadas con w = tiempo
+    pub fn fbm4(&self, p: Vec4, octaves: u32, persistence: f32, lacunarity: f32) -> f32 {
+        self.octaves(octaves, persistence, lacunarity, |noise, frequency| noise.simplex4(p * frequency))
+    }
+
+    // Crestas afiladas (montañas, grietas): (1 - |n|)², en [0, 1]
+    pub fn ridged(&self, p: Vec3, octaves: u32, persistence: f32, lacunarity: f32) -> f32 {
+        self.octaves(octaves, persistence, lacunarity, |noise, frequency| {
+            (1.0 - noise.perlin(p * frequency).abs()).powi(2)
+        })
+    }
+
+    // Formas redondeadas (nubes, dunas): |n|, en [0, 1]
+    pub fn billow(&self, p: Vec3, octaves: u32, persistence: f32, lacunarity: f32) -> f32 {
+        self.octaves(octaves, persistence, lacunarity, |noise, frequency| noise.perlin(p * frequency).abs())
+    }
+
+    // Domain warping: desplaza p con un fBm vectorial antes de muestrear otro ruido
+    pub fn warp(&self, p: Vec3, strength: f32, octaves: u32) -> Vec3 {
+        let offset = |i: usize| {
+            let o = WARP_OFFSETS[i];
+            self.derive(100 + i as u32).fbm(p + Vec3::new(o[0], o[1], o[2]), octaves, 0.5, 2.0)
+        };
+        p + Vec3::new(offset(0), offset(1), offset(2)) * strength
+    }
+
+    // Cada octava usa una semilla derivada para que los patrones no se alineen en el origen
+    fn octaves(&self, octaves: u32, persistence: f32, lacunarity: f32, sample: impl Fn(&Noise, f32) -> f32) -> f32 {
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut max_value = 0.0;
+
+        for octave in 0..octaves {
+            total += sample(&self.derive(octave), frequency) * amplitude;
+            max_value += amplitude;
+            amplitude *= persistence;
+            frequency *= lacunarity;
+        }
+
+        if max_value > 0.0 { total / max_value } else { 0.0 }
+    }
+}
+
+// Secuencia de números al azar reproducible, sacada del hash del ruido
+pub struct Rng {
+    noise: Noise,
+    index: i32,
+}
+
+impl Rng {
+    pub fn new(noise: Noise) -> Self {
+        Rng { noise, index: 0 }
+    }
+
+    // Siguiente valor en [0, 1)
+    pub fn next(&mut self) -> f32 {
+        self.index += 1;
+        self.noise.random(self.index, 0, 0, 0)
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+
+    // Entero en [min, max]; el tamaño del rango se calcula en u64 para que max = u32::MAX no
+    // desborde. Un rango vacío da min.
+    pub fn range_int(&mut self, min: u32, max: u32) -> u32 {
+        debug_assert!(min <= max, "range_int: min {} > max {}", min, max);
+        if max <= min {
+            return min;
+        }
+        let span = (max - min) as u64 + 1;
+        (min as u64 + (self.next() * span as f32) as u64).min(max as u64) as u32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next() < probability
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.index += 1;
+        self.noise.hash(self.index, 0, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> impl Iterator<Item = Vec3> {
+        (0..200).map(|i| {
+            let t = i as f32;
+            Vec3::new(t * 0.37 - 30.0, (t * 1.13).sin() * 50.0, t * -0.71 + 12.5)
+        })
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_noise() {
+        let (a, b) = (Noise::new(42), Noise::new(42));
+        for p in samples() {
+            assert_eq!(a.perlin(p), b.perlin(p));
+            assert_eq!(a.simplex3(p), b.simplex3(p));
+            assert_eq!(a.fbm(p, 4, 0.5, 2.0), b.fbm(p, 4, 0.5, 2.0));
+            assert_eq!(a.worley(p).f1, b.worley(p).f1);
+        }
+        assert_eq!(a.derive(7), b.derive(7));
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let (a, b) = (Noise::new(1), Noise::new(2));
+        assert!(samples().any(|p| a.perlin(p) != b.perlin(p)));
+    }
+
+    #[test]
+    fn unit_outputs_stay_in_range() {
+        let noise = Noise::new(0xC0FFEE);
+        for (i, p) in samples().enumerate() {
+            let random = noise.random(i as i32, -(i as i32), 3, 0);
+            assert!((0.0..1.0).contains(&random), "random = {}", random);
+
+            let cells = noise.worley(p);
+            assert!((0.0..1.0).contains(&cells.cell), "cell = {}", cells.cell);
+            assert!(cells.f1 >= 0.0 && cells.f1 <= cells.f2);
+
+            let ridged = noise.ridged(p, 4, 0.5, 2.0);
+            let billow = noise.billow(p, 4, 0.5, 2.0);
+            assert!((0.0..=1.0).contains(&ridged), "ridged = {}", ridged);
+            assert!((0.0..=1.0).contains(&billow), "billow = {}", billow);
+
+            for n in [noise.perlin(p), noise.simplex3(p), noise.fbm(p, 5, 0.5, 2.0)] {
+                assert!((-1.0..=1.0).contains(&n), "n = {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_rng_sequence() {
+        let mut a = Rng::new(Noise::new(9));
+        let mut b = Rng::new(Noise::new(9));
+        for _ in 0..100 {
+            assert_eq!(a.next(), b.next());
+            assert_eq!(a.next_u32(), b.next_u32());
+            assert_eq!(a.range_int(3, 17), b.range_int(3, 17));
+        }
+    }
+
+    #[test]
+    fn rng_outputs_stay_in_range() {
+        let mut rng = Rng::new(Noise::new(5));
+        for _ in 0..1000 {
+            let value = rng.next();
+            assert!((0.0..1.0).contains(&value), "next = {}", value);
+            let value = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&value), "range = {}", value);
+            let value = rng.range_int(10, 20);
+            assert!((10..=20).contains(&value), "range_int = {}", value);
+        }
+    }
+
+    #[test]
+    fn range_int_covers_the_whole_range() {
+        let mut rng = Rng::new(Noise::new(11));
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            seen[rng.range_int(0, 3) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn range_int_handles_the_edges_of_u32() {
+        let mut rng = Rng::new(Noise::new(3));
+        assert_eq!(rng.range_int(7, 7), 7);
+        assert_eq!(rng.range_int(u32::MAX, u32::MAX), u32::MAX);
+        for _ in 0..100 {
+            let value = rng.range_int(u32::MAX - 1, u32::MAX);
+            assert!(value >= u32::MAX - 1);
+            rng.range_int(0, u32::MAX);
+        }
+    }
+}
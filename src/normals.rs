@@ -0,0 +1,220 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::obj::Mesh;
+
+// Generación de normales por vértice para meshes que no las traen.
+//
+// Las normales suaves se acumulan por posición soldada (no por vértice), así una
+// costura de UV no deja una arista visible. Cada cara solo suma las caras vecinas
+// cuya normal está dentro del ángulo de quiebre; si dos esquinas del mismo vértice
+// terminan con normales distintas, el vértice se divide.
+
+// Ángulo de quiebre, en grados, cuando el archivo no trae normales
+pub const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NormalMode {
+    // Normales del archivo; los meshes sin ellas se generan con DEFAULT_CREASE_ANGLE
+    #[default]
+    FromFile,
+    // Una normal por cara, sin suavizado
+    Flat,
+    // Promedio ponderado por ángulo de todas las caras que tocan la posición
+    Smooth,
+    // Como Smooth, pero las caras a más de este ángulo (en grados) no se mezclan
+    Crease(f32),
+}
+
+// "file", "flat", "smooth" o un ángulo en grados
+impl FromStr for NormalMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(NormalMode::FromFile),
+            "flat" => Ok(NormalMode::Flat),
+            "smooth" => Ok(NormalMode::Smooth),
+            angle => angle
+                .parse::<f32>()
+                .map(NormalMode::Crease)
+                .map_err(|_| format!("modo de normales desconocido: {}", s)),
+        }
+    }
+}
+
+impl NormalMode {
+    // Coseno mínimo entre dos caras para promediarlas; None = nunca se promedian
+    fn crease_cos(&self) -> Option<f32> {
+        match self {
+            NormalMode::Flat => None,
+            NormalMode::Smooth => Some(-1.0),
+            NormalMode::Crease(angle) => Some(angle.clamp(0.0, 180.0).to_radians().cos()),
+            NormalMode::FromFile => NormalMode::Crease(DEFAULT_CREASE_ANGLE).crease_cos(),
+        }
+    }
+}
+
+// Devuelve el mesh con normales según `mode`. Con FromFile y normales completas no cambia nada.
+pub fn apply_normals(mesh: Mesh, mode: NormalMode) -> Mesh {
+    if mode == NormalMode::FromFile && mesh.normals.len() == mesh.vertices.len() {
+        return mesh;
+    }
+    generate_normals(&mesh, mode)
+}
+
+pub fn generate_normals(mesh: &Mesh, mode: NormalMode) -> Mesh {
+    let faces: Vec<[usize; 3]> = mesh.indices
+        .chunks_exact(3)
+        .map(|f| [f[0] as usize, f[1] as usize, f[2] as usize])
+        .collect();
+
+    // Normal unitaria de cada cara (cero si es degenerada)
+    let face_normals: Vec<Vec3> = faces.iter().map(|&[a, b, c]| {
+        let (pa, pb, pc) = (mesh.vertices[a], mesh.vertices[b], mesh.vertices[c]);
+        let normal = (pb - pa).cross(&(pc - pa));
+        let length = normal.magnitude();
+        if length > 0.0 { normal / length } else { Vec3::zeros() }
+    }).collect();
+
+    // Posiciones soldadas por bits exactos y, para cada una, las esquinas (cara, peso) que la tocan
+    let mut weld: HashMap<[u32; 3], usize> = HashMap::new();
+    let position_of: Vec<usize> = mesh.vertices.iter().map(|v| {
+        let key = [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+        let next = weld.len();
+        *weld.entry(key).or_insert(next)
+    }).collect();
+
+    let mut corners: Vec<Vec<(usize, f32)>> = vec![Vec::new(); weld.len()];
+    for (f, face) in faces.iter().enumerate() {
+        for k in 0..3 {
+            let p = mesh.vertices[face[k]];
+            let e1 = mesh.vertices[face[(k + 1) % 3]] - p;
+            let e2 = mesh.vertices[face[(k + 2) % 3]] - p;
+            let angle = if e1.magnitude() > 0.0 && e2.magnitude() > 0.0 {
+                nalgebra_glm::angle(&e1, &e2)
+            } else {
+                0.0
+            };
+            corners[position_of[face[k]]].push((f, angle));
+        }
+    }
+
+    let crease_cos = mode.crease_cos();
+    let corner_normal = |f: usize, vertex: usize| -> Vec3 {
+        let own = face_normals[f];
+        let crease_cos = match crease_cos {
+            Some(c) if own != Vec3::zeros() => c,
+            _ => return own,
+        };
+
+        let sum = corners[position_of[vertex]]
+            .iter()
+            .filter(|&&(g, _)| face_normals[g].dot(&own) >= crease_cos)
+            .fold(Vec3::zeros(), |acc, &(g, angle)| acc + face_normals[g] * angle);
+        let length = sum.magnitude();
+        if length > 0.0 { sum / length } else { own }
+    };
+
+    // Un vértice de salida por (vértice original, normal); los que coinciden se comparten
+    let mut out = Mesh {
+        name: mesh.name.clone(),
+        material: mesh.material,
+        vertices: Vec::new(),
+        normals: Vec::new(),
+        texcoords: Vec::new(),
+        indices: Vec::with_capacity(mesh.indices.len()),
+    };
+    let has_uv = mesh.texcoords.len() == mesh.vertices.len();
+    let mut remap: HashMap<(usize, [u32; 3]), u32> = HashMap::new();
+
+    for (f, face) in faces.iter().enumerate() {
+        for &vertex in face {
+            let mut normal = corner_normal(f, vertex);
+            if normal == Vec3::zeros() {
+                // Cara degenerada: cualquier normal sirve, no se ve
+                normal = Vec3::new(0.0, 1.0, 0.0);
+            }
+
+            let key = (vertex, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()]);
+            let index = *remap.entry(key).or_insert_with(|| {
+                out.vertices.push(mesh.vertices[vertex]);
+                out.normals.push(normal);
+                if has_uv {
+                    out.texcoords.push(mesh.texcoords[vertex]);
+                }
+                (out.vertices.len() - 1) as u32
+            });
+            out.indices.push(index);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cubo de lado 2 con las 8 esquinas compartidas entre caras y sin normales
+    fn welded_cube() -> Mesh {
+        let vertices: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new(if i & 1 == 0 { -1.0 } else { 1.0 }, if i & 2 == 0 { -1.0 } else { 1.0 }, if i & 4 == 0 { -1.0 } else { 1.0 }))
+            .collect();
+        let mut indices = Vec::new();
+        for axis in 0..3 {
+            for side in [0, 1] {
+                // Las esquinas de la cara en orden alrededor de ella, giradas hacia afuera
+                let (u, v) = (1 << ((axis + 1) % 3), 1 << ((axis + 2) % 3));
+                let base = side << axis;
+                let mut quad = [base, base | u, base | u | v, base | v];
+                if side == 0 {
+                    quad.reverse();
+                }
+                indices.extend([quad[0], quad[1], quad[2], quad[0], quad[2], quad[3]].map(|i| i as u32));
+            }
+        }
+        Mesh { name: "cubo".to_string(), vertices, normals: Vec::new(), texcoords: Vec::new(), indices, material: None }
+    }
+
+    #[test]
+    fn crease_splits_the_cube_faces() {
+        let mesh = generate_normals(&welded_cube(), NormalMode::Crease(DEFAULT_CREASE_ANGLE));
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        for face in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| face[k] as usize);
+            let face_normal = (mesh.vertices[b] - mesh.vertices[a]).cross(&(mesh.vertices[c] - mesh.vertices[a])).normalize();
+            // Hacia afuera y alineada con un eje
+            assert!(face_normal.dot(&mesh.vertices[a]) > 0.0);
+            assert_eq!(face_normal.iter().filter(|c| c.abs() == 1.0).count(), 1);
+            for k in [a, b, c] {
+                assert!((mesh.normals[k] - face_normal).magnitude() < 1e-6, "{:?}", mesh.normals[k]);
+            }
+        }
+
+        // Las caras del cubo son planas, así que sin suavizado sale lo mismo
+        assert_eq!(generate_normals(&welded_cube(), NormalMode::Flat).vertices.len(), 24);
+    }
+
+    #[test]
+    fn smooth_keeps_the_cube_welded() {
+        let mesh = generate_normals(&welded_cube(), NormalMode::Smooth);
+        assert_eq!(mesh.vertices.len(), 8);
+        for (position, normal) in mesh.vertices.iter().zip(&mesh.normals) {
+            assert!((normal - position.normalize()).magnitude() < 1e-6, "{:?}", normal);
+        }
+    }
+
+    #[test]
+    fn normal_mode_parses_names_and_angles() {
+        assert_eq!("file".parse(), Ok(NormalMode::FromFile));
+        assert_eq!("flat".parse(), Ok(NormalMode::Flat));
+        assert_eq!("smooth".parse(), Ok(NormalMode::Smooth));
+        assert_eq!("45".parse(), Ok(NormalMode::Crease(45.0)));
+        for bad in ["", "suave", "45deg", "Flat"] {
+            let error = bad.parse::<NormalMode>().unwrap_err();
+            assert!(error.contains(bad), "{}", error);
+        }
+    }
+}
@@ -1,50 +1,257 @@
-use tobj;
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{self as glm, Mat4, Vec2, Vec3, Vec4};
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
 use crate::vertex::Vertex;
+use crate::error::{MeshProblem, ModelError};
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::simplify::{simplify_mesh, SimplifyOptions};
+use crate::mesh_cache;
+use crate::normals::NormalMode;
+use crate::tangents::generate_tangents;
+use crate::import::load_model;
+use crate::shaders::{ProceduralSurface, SurfaceMaterial};
+use crate::texture::Texture;
+
+// Área mínima de una cara, relativa al cuadrado de la diagonal de la caja del mesh. Por
+// debajo, el área es la que deja el redondeo en vértices alineados o repetidos.
+const MIN_FACE_AREA: f32 = 1e-10;
 
 pub struct Obj {
     meshes: Vec<Mesh>,
+    materials: Vec<Material>,
+    bounding_box: Aabb,
+    bounding_sphere: BoundingSphere,
+    // Problemas que no impidieron cargar el modelo
+    warnings: Vec<ModelError>,
 }
 
-struct Mesh {
-    vertices: Vec<Vec3>,
-    normals: Vec<Vec3>,
-    texcoords: Vec<Vec2>,
-    indices: Vec<u32>,
+// Un objeto del archivo con índice único: posición, normal y UV comparten índice
+#[derive(Debug)]
+pub struct Mesh {
+    pub name: String,
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub texcoords: Vec<Vec2>,
+    pub indices: Vec<u32>,
+    // Índice en los materiales del modelo
+    pub material: Option<usize>,
 }
 
-impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
-            single_index: true,
-            triangulate: true,
-            ..Default::default()
-        })?;
-
-        let meshes = models.into_iter().map(|model| {
-            let mesh = model.mesh;
-            Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], -v[1], -v[2]))
-                    .collect(),
-                normals: mesh.normals.chunks(3)
-                    .map(|n| Vec3::new(n[0], -n[1], -n[2]))
-                    .collect(),
-                texcoords: mesh.texcoords.chunks(2)
-                    .map(|t| Vec2::new(t[0], 1.0 - t[1]))
-                    .collect(),
-                indices: mesh.indices,
+// Material común a todos los formatos, en términos de PBR metálico-rugoso
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,
+    // RGBA lineal; alpha es la opacidad
+    pub base_color: Vec4,
+    // Ruta de la textura de color, relativa al archivo del modelo
+    pub base_color_texture: Option<String>,
+    // Ruta de la textura de luz emitida, relativa al archivo del modelo
+    pub emission_texture: Option<String>,
+    pub metallic: f32,
+    pub roughness: f32,
+    // Rutas de las texturas en escala de grises de metal y rugosidad
+    pub metallic_texture: Option<String>,
+    pub roughness_texture: Option<String>,
+    // Superficie procedural que da el color; se puede hornear a una textura al guardar
+    pub procedural: Option<ProceduralSurface>,
+    // Las texturas de arriba que se pudieron leer (ver Material::load_maps)
+    pub maps: Option<Rc<MaterialMaps>>,
+}
+
+// Texturas de un material, muestreadas con las UV del mesh en fragment_shader
+#[derive(Default)]
+pub struct MaterialMaps {
+    pub base_color: Option<Texture>,
+    pub emission: Option<Texture>,
+    pub metallic: Option<Texture>,
+    pub roughness: Option<Texture>,
+}
+
+impl MaterialMaps {
+    // Los mapas multiplican los valores constantes del material, como en glTF; la emisión
+    // sale solo del mapa
+    pub fn sample(&self, material: SurfaceMaterial, uv: Vec2) -> SurfaceMaterial {
+        let gray = |texture: &Texture| texture.sample(uv).x;
+        SurfaceMaterial {
+            albedo: self.base_color.as_ref().map_or(material.albedo, |t| material.albedo.component_mul(&t.sample(uv))),
+            roughness: material.roughness * self.roughness.as_ref().map_or(1.0, gray),
+            metallic: material.metallic * self.metallic.as_ref().map_or(1.0, gray),
+            emission: self.emission.as_ref().map_or(material.emission, |t| t.sample(uv)),
+        }
+    }
+}
+
+impl fmt::Debug for MaterialMaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = |texture: &Option<Texture>| texture.as_ref().map(|t| (t.width, t.height));
+        f.debug_struct("MaterialMaps")
+            .field("base_color", &size(&self.base_color))
+            .field("emission", &size(&self.emission))
+            .field("metallic", &size(&self.metallic))
+            .field("roughness", &size(&self.roughness))
+            .finish()
+    }
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            base_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            base_color_texture: None,
+            emission_texture: None,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_texture: None,
+            roughness_texture: None,
+            procedural: None,
+            maps: None,
+        }
+    }
+
+    pub fn with_procedural(mut self, surface: ProceduralSurface) -> Self {
+        self.procedural = Some(surface);
+        self
+    }
+
+    // Lee las texturas que existen junto al modelo. Solo se leen TGA (ver Texture::load_tga);
+    // las que faltan ya las reporta el importador y las que no se pueden leer quedan como aviso.
+    pub fn load_maps(&mut self, directory: &Path, warnings: &mut Vec<ModelError>) {
+        let mut load = |texture: &Option<String>| {
+            let path = directory.join(texture.as_ref()?);
+            if !path.exists() {
+                return None;
             }
+            let path = path.display().to_string();
+            Texture::load_tga(&path)
+                .map_err(|source| warnings.push(ModelError::Io { path, source }))
+                .ok()
+        };
+
+        let maps = MaterialMaps {
+            base_color: load(&self.base_color_texture),
+            emission: load(&self.emission_texture),
+            metallic: load(&self.metallic_texture),
+            roughness: load(&self.roughness_texture),
+        };
+        let loaded = [&maps.base_color, &maps.emission, &maps.metallic, &maps.roughness].iter().any(|t| t.is_some());
+        self.maps = loaded.then(|| Rc::new(maps));
+    }
+
+    // Material con el que lo ilumina fragment_shader, antes de aplicar los mapas. Si el color
+    // viene de una textura que no se pudo leer no hay material, y la parte se dibuja con el
+    // shader de la nave en vez de quedar con el Kd (blanco si el MTL no lo trae).
+    pub fn surface(&self) -> Option<SurfaceMaterial> {
+        let base_color_read = self.maps.as_ref().is_some_and(|maps| maps.base_color.is_some());
+        if self.base_color_texture.is_some() && !base_color_read {
+            return None;
+        }
+        Some(SurfaceMaterial::diffuse(self.base_color.xyz()).with_roughness(self.roughness).with_metallic(self.metallic))
+    }
+}
+
+// Rango del arreglo de índices de get_vertex_and_index_arrays que usa un mismo material
+#[derive(Debug, Clone)]
+pub struct MaterialGroup {
+    pub indices: Range<usize>,
+    // None para los meshes sin material, que usan el shader de la nave
+    pub material: Option<SurfaceMaterial>,
+    pub maps: Option<Rc<MaterialMaps>>,
+}
+
+impl Obj {
+    pub fn load(filename: &str) -> Result<Self, ModelError> {
+        Obj::load_with_normals(filename, NormalMode::default())
+    }
+
+    // Carga un modelo en cualquiera de los formatos de import/ (OBJ, STL, PLY, glTF),
+    // lo valida y genera las normales según `mode` (ver normals.rs)
+    pub fn load_with_normals(filename: &str, mode: NormalMode) -> Result<Self, ModelError> {
+        load_model(filename, mode)
+    }
+
+    pub fn from_meshes(meshes: Vec<Mesh>) -> Self {
+        // Volúmenes envolventes de todo el modelo, para el culling por objeto
+        let positions = meshes.iter().flat_map(|mesh| mesh.vertices.iter());
+        let bounding_box = Aabb::from_points(positions.clone());
+        let bounding_sphere = BoundingSphere::from_points(positions);
+
+        Obj { meshes, materials: Vec::new(), bounding_box, bounding_sphere, warnings: Vec::new() }
+    }
+
+    pub fn with_materials(mut self, materials: Vec<Material>) -> Self {
+        self.materials = materials;
+        self
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<ModelError>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn warnings(&self) -> &[ModelError] {
+        &self.warnings
+    }
+
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        self.bounding_sphere
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.meshes.iter().map(|m| m.indices.len() / 3).sum()
+    }
+
+    // Versión simplificada del modelo; el objetivo de triángulos se reparte entre
+    // los meshes en proporción a su tamaño
+    pub fn simplify(&self, options: &SimplifyOptions) -> Obj {
+        let total = self.triangle_count().max(1);
+        let meshes = self.meshes.iter().map(|mesh| {
+            let share = (mesh.indices.len() / 3) * options.target_triangles / total;
+            simplify_mesh(mesh, &SimplifyOptions { target_triangles: share, ..*options })
         }).collect();
 
-        Ok(Obj { meshes })
+        Obj::from_meshes(meshes).with_materials(self.materials.clone())
+    }
+
+    // Como `simplify`, pero las mallas se guardan en `cache` y se reutilizan mientras el
+    // archivo `source` del que salió el modelo no cambie (ver mesh_cache.rs). Si la caché no
+    // se puede escribir, el modelo simplificado lo lleva como aviso.
+    pub fn simplify_cached(&self, options: &SimplifyOptions, source: &str, cache: &Path) -> Obj {
+        if let Some(meshes) = mesh_cache::read(cache, source) {
+            return Obj::from_meshes(meshes).with_materials(self.materials.clone());
+        }
+
+        let simplified = self.simplify(options);
+        match mesh_cache::write(cache, source, &simplified.meshes) {
+            Ok(()) => simplified,
+            Err(source) => simplified.with_warnings(vec![ModelError::Io { path: cache.display().to_string(), source }]),
+        }
     }
 
+    #[allow(dead_code)] // el renderer usa la versión indexada
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
         let mut vertices = Vec::new();
 
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
+            // Las caras con índices fuera de rango se saltan en vez de entrar en pánico
+            let faces = mesh.indices
+                .chunks_exact(3)
+                .filter(|face| face.iter().all(|&i| (i as usize) < mesh.vertices.len()));
+            for &index in faces.flatten() {
                 let position = mesh.vertices[index as usize];
                 let normal = mesh.normals.get(index as usize)
                     .cloned()
@@ -57,6 +264,9 @@ impl Obj {
             }
         }
 
+        // Cada esquina ya es un vértice propio, así que no se separa ninguno
+        let mut indices: Vec<u32> = (0..vertices.len() as u32).collect();
+        generate_tangents(&mut vertices, &mut indices);
         vertices
     }
 
@@ -89,9 +299,34 @@ impl Obj {
             vertex_offset += mesh.vertices.len() as u32;
         }
 
+        generate_tangents(&mut all_vertices, &mut all_indices);
         (all_vertices, all_indices)
     }
 
+    // Los índices de get_vertex_and_index_arrays agrupados por el material de su mesh; los
+    // meshes seguidos con el mismo material (o sin material) van en un solo grupo
+    pub fn material_groups(&self) -> Vec<MaterialGroup> {
+        let mut groups: Vec<MaterialGroup> = Vec::new();
+        let mut start = 0;
+        for mesh in &self.meshes {
+            let indices = start..start + mesh.indices.len();
+            start = indices.end;
+            let source = mesh.material.and_then(|i| self.materials.get(i));
+            let material = source.and_then(Material::surface);
+            let maps = source.and_then(|m| m.maps.clone()).filter(|_| material.is_some());
+            let same_maps = |other: &Option<Rc<MaterialMaps>>| match (other, &maps) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            };
+            match groups.last_mut() {
+                Some(last) if last.material == material && same_maps(&last.maps) => last.indices.end = indices.end,
+                _ => groups.push(MaterialGroup { indices, material, maps }),
+            }
+        }
+        groups
+    }
+
     // Método para obtener información del modelo
     pub fn get_model_info(&self) -> String {
         let total_vertices: usize = self.meshes.iter().map(|m| m.vertices.len()).sum();
@@ -105,4 +340,206 @@ impl Obj {
             total_triangles
         )
     }
-}
\ No newline at end of file
+}
+
+impl Mesh {
+    // Mesh a partir de los arreglos de primitives.rs o de get_vertex_and_index_arrays
+    pub fn from_vertex_arrays(name: &str, vertices: &[Vertex], indices: &[u32]) -> Self {
+        Mesh {
+            name: name.to_string(),
+            vertices: vertices.iter().map(|v| v.position).collect(),
+            normals: vertices.iter().map(|v| v.normal).collect(),
+            texcoords: vertices.iter().map(|v| v.tex_coords).collect(),
+            indices: indices.to_vec(),
+            material: None,
+        }
+    }
+
+    // Aplica una matriz de modelo; las normales usan la inversa transpuesta. Una matriz
+    // singular (escala 0 en algún eje) no tiene inversa: la malla queda aplastada y sus
+    // normales se dejan como estaban en vez de volverse NaN
+    pub fn transform(&mut self, matrix: &Mat4) {
+        let normal_matrix = glm::mat4_to_mat3(matrix).try_inverse().map(|inverse| inverse.transpose());
+        for v in &mut self.vertices {
+            *v = (matrix * v.push(1.0)).xyz();
+        }
+        if let Some(normal_matrix) = normal_matrix {
+            for n in &mut self.normals {
+                *n = (normal_matrix * *n).normalize();
+            }
+        }
+        if matrix.determinant() < 0.0 {
+            for face in self.indices.chunks_exact_mut(3) {
+                face.swap(1, 2);
+            }
+        }
+    }
+
+    // Primer problema que impide usar el mesh
+    pub fn validate(&self) -> Result<(), MeshProblem> {
+        if !self.indices.len().is_multiple_of(3) {
+            return Err(MeshProblem::IncompleteFace { indices: self.indices.len() % 3 });
+        }
+        if let Some(vertex) = self.vertices.iter().position(|v| !v.iter().all(|c| c.is_finite())) {
+            return Err(MeshProblem::NonFinitePosition { vertex });
+        }
+        for (face, indices) in self.indices.chunks_exact(3).enumerate() {
+            if let Some(&index) = indices.iter().find(|&&i| i as usize >= self.vertices.len()) {
+                return Err(MeshProblem::IndexOutOfRange { face, index, vertex_count: self.vertices.len() });
+            }
+        }
+        Ok(())
+    }
+
+    // Quita las caras sin área (ver MIN_FACE_AREA) y devuelve cuántas se quitaron
+    pub fn remove_degenerate_faces(&mut self) -> usize {
+        let before = self.indices.len() / 3;
+        let vertices = &self.vertices;
+        let min = vertices.iter().fold(Vec3::repeat(f32::INFINITY), |min, v| min.inf(v));
+        let max = vertices.iter().fold(Vec3::repeat(f32::NEG_INFINITY), |max, v| max.sup(v));
+        let min_area = (max - min).magnitude_squared() * MIN_FACE_AREA;
+        let faces: Vec<u32> = self.indices
+            .chunks_exact(3)
+            .filter(|f| {
+                let (a, b, c) = (vertices[f[0] as usize], vertices[f[1] as usize], vertices[f[2] as usize]);
+                (b - a).cross(&(c - a)).magnitude() * 0.5 > min_area
+            })
+            .flatten()
+            .copied()
+            .collect();
+        self.indices = faces;
+        before - self.indices.len() / 3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    #[test]
+    fn meshes_without_material_keep_the_shader_fallback() {
+        let (vertices, indices) = primitives::uv_sphere(1.0, 8, 4);
+        let mesh = |material| Mesh { material, ..Mesh::from_vertex_arrays("esfera", &vertices, &indices) };
+        let mut red = Material::new("rojo");
+        red.base_color = Vec4::new(1.0, 0.0, 0.0, 1.0);
+        // El índice 5 no existe y cuenta como si no tuviera material
+        let obj = Obj::from_meshes(vec![mesh(None), mesh(Some(5)), mesh(Some(0)), mesh(Some(0))]).with_materials(vec![red]);
+
+        let groups = obj.material_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].material, None);
+        assert_eq!(groups[0].indices, 0..indices.len() * 2);
+        assert_eq!(groups[1].material.map(|m| m.albedo), Some(Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(groups[1].indices.end, indices.len() * 4);
+    }
+
+    #[test]
+    fn texture_maps_are_sampled_or_fall_back_to_the_shader() {
+        let directory = std::env::temp_dir().join(format!("proyecto_nave_maps_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut texture = Texture::new(2, 1);
+        texture.set(0, 0, Vec3::new(1.0, 0.0, 0.0));
+        texture.set(1, 0, Vec3::new(0.0, 0.0, 1.0));
+        texture.save_tga(directory.join("color.tga").to_str().unwrap()).unwrap();
+        std::fs::write(directory.join("metal.png"), b"no es un TGA").unwrap();
+
+        let mut warnings = Vec::new();
+        let material = |texture: &str| {
+            let mut material = Material::new(texture);
+            material.base_color = Vec4::new(0.5, 0.5, 0.5, 1.0);
+            material.base_color_texture = Some(texture.to_string());
+            material
+        };
+        let (mut tga, mut png, mut missing) = (material("color.tga"), material("metal.png"), material("falta.png"));
+        for material in [&mut tga, &mut png, &mut missing] {
+            material.load_maps(&directory, &mut warnings);
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // El TGA se muestrea con las UV y multiplica al Kd
+        let maps = tga.maps.as_ref().expect("el TGA se lee");
+        let surface = tga.surface().expect("con la textura leída hay material");
+        let left = maps.sample(surface, Vec2::new(0.25, 0.5)).albedo;
+        assert!((left - Vec3::new(0.5, 0.0, 0.0)).magnitude() < 0.01);
+
+        // El PNG no se puede leer (aviso) y el archivo que falta lo reporta el importador;
+        // en los dos casos esa parte usa el shader de la nave
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(&warnings[0], ModelError::Io { path, .. } if path.ends_with("metal.png")));
+        assert!(png.maps.is_none() && png.surface().is_none());
+        assert!(missing.maps.is_none() && missing.surface().is_none());
+
+        let (vertices, indices) = primitives::uv_sphere(1.0, 8, 4);
+        let mesh = |material| Mesh { material: Some(material), ..Mesh::from_vertex_arrays("esfera", &vertices, &indices) };
+        let obj = Obj::from_meshes(vec![mesh(0), mesh(1)]).with_materials(vec![tga, png]);
+        let groups = obj.material_groups();
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].material.is_some() && groups[0].maps.is_some());
+        assert!(groups[1].material.is_none() && groups[1].maps.is_none());
+    }
+
+    #[test]
+    fn transform_keeps_normals_finite() {
+        let (vertices, indices) = primitives::uv_sphere(1.0, 8, 4);
+        let sphere = Mesh::from_vertex_arrays("esfera", &vertices, &indices);
+
+        // Un espejo en X invierte el giro de las caras y refleja las normales
+        let mut mirrored = Mesh::from_vertex_arrays("esfera", &vertices, &indices);
+        mirrored.transform(&glm::scaling(&Vec3::new(-2.0, 2.0, 2.0)));
+        assert_eq!(mirrored.indices[..3], [indices[0], indices[2], indices[1]]);
+        for (normal, original) in mirrored.normals.iter().zip(&sphere.normals) {
+            assert!((normal - Vec3::new(-original.x, original.y, original.z)).magnitude() < 1e-5);
+        }
+
+        // Aplastada en Y: las posiciones se aplastan y las normales no cambian
+        let mut flat = Mesh::from_vertex_arrays("esfera", &vertices, &indices);
+        flat.transform(&glm::scaling(&Vec3::new(1.0, 0.0, 1.0)));
+        assert!(flat.vertices.iter().all(|v| v.y == 0.0));
+        assert_eq!(flat.normals, sphere.normals);
+    }
+
+    #[test]
+    fn bounds_cover_every_mesh() {
+        let (vertices, indices) = primitives::uv_sphere(1.0, 8, 4);
+        let mut moved = Mesh::from_vertex_arrays("lejos", &vertices, &indices);
+        moved.transform(&glm::translation(&Vec3::new(4.0, 0.0, 0.0)));
+        let obj = Obj::from_meshes(vec![Mesh::from_vertex_arrays("esfera", &vertices, &indices), moved]);
+
+        let aabb = obj.bounding_box();
+        assert!((aabb.min - Vec3::new(-1.0, -1.0, -1.0)).magnitude() < 1e-5);
+        assert!((aabb.max - Vec3::new(5.0, 1.0, 1.0)).magnitude() < 1e-5);
+        let sphere = obj.bounding_sphere();
+        assert!((sphere.center - Vec3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((sphere.radius - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn degenerate_faces_are_relative_to_the_mesh_size() {
+        // Un triángulo pequeño pero real y dos casi sin área: uno con un vértice a una distancia
+        // de redondeo de la recta de los otros dos y otro con un vértice repetido
+        let build = |scale: f32| {
+            let vertices = [
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(100.0, 0.0, 0.0),
+                Vec3::new(0.0, 100.0, 0.0),
+                Vec3::new(0.01, 0.0, 0.0),
+                Vec3::new(0.0, 0.01, 0.0),
+                Vec3::new(50.0, 1e-8, 0.0),
+            ];
+            Mesh {
+                name: "caras".to_string(),
+                vertices: vertices.iter().map(|v| v * scale).collect(),
+                normals: Vec::new(),
+                texcoords: Vec::new(),
+                indices: vec![0, 1, 2, 0, 3, 4, 0, 5, 1, 2, 2, 1],
+                material: None,
+            }
+        };
+        for scale in [1e-3, 1.0, 1e3] {
+            let mut mesh = build(scale);
+            assert_eq!(mesh.remove_degenerate_faces(), 2);
+            assert_eq!(mesh.indices, vec![0, 1, 2, 0, 3, 4]);
+        }
+    }
+}
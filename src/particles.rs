@@ -0,0 +1,459 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use std::f32::consts::PI;
+use crate::framebuffer::{BlendMode, Framebuffer};
+use crate::noise::{Noise, Rng};
+use crate::vertex::Vertex;
+
+// Sistema de partículas en CPU: un emisor suelta partículas que viven un rato, se mueven con
+// su velocidad y una aceleración común, y cambian de tamaño y color a lo largo de su vida.
+// Se dibujan como sprites redondos que miran a la cámara, con prueba de profundidad contra lo
+// que ya está en el framebuffer pero sin escribirla.
+
+// Forma de la que salen las partículas, en el espacio local del emisor
+pub enum Emitter {
+    // Todas desde el origen, en cualquier dirección
+    Point,
+    // Dentro de una esfera centrada en el origen
+    Sphere { radius: f32 },
+    // Sobre la superficie de una malla: el triángulo se elige según su área
+    Mesh { triangles: Vec<[Vec3; 3]>, cumulative_area: Vec<f32> },
+}
+
+impl Emitter {
+    pub fn mesh(vertices: &[Vertex], indices: &[u32]) -> Self {
+        let mut triangles = Vec::with_capacity(indices.len() / 3);
+        let mut cumulative_area = Vec::with_capacity(indices.len() / 3);
+        let mut total = 0.0;
+        for face in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[face[i] as usize].position);
+            total += (b - a).cross(&(c - a)).magnitude() * 0.5;
+            triangles.push([a, b, c]);
+            cumulative_area.push(total);
+        }
+        Emitter::Mesh { triangles, cumulative_area }
+    }
+
+    // Punto de salida y dirección hacia afuera del emisor (al azar para un punto)
+    pub fn sample(&self, rng: &mut Rng) -> (Vec3, Vec3) {
+        match self {
+            Emitter::Point => (Vec3::zeros(), random_direction(rng)),
+            Emitter::Sphere { radius } => {
+                let direction = random_direction(rng);
+                // La raíz cúbica reparte los puntos de forma uniforme en el volumen
+                (direction * *radius * rng.next().cbrt(), direction)
+            }
+            Emitter::Mesh { triangles, cumulative_area } => {
+                let Some(&total) = cumulative_area.last() else {
+                    return (Vec3::zeros(), random_direction(rng));
+                };
+                let target = rng.next() * total;
+                let index = cumulative_area.partition_point(|&area| area < target).min(triangles.len() - 1);
+                let [a, b, c] = triangles[index];
+
+                // Coordenadas baricéntricas uniformes sobre el triángulo
+                let (r1, r2) = (rng.next().sqrt(), rng.next());
+                let point = a * (1.0 - r1) + b * (r1 * (1.0 - r2)) + c * (r1 * r2);
+                let normal = (b - a).cross(&(c - a)).try_normalize(1e-12).unwrap_or_else(|| random_direction(rng));
+                (point, normal)
+            }
+        }
+    }
+}
+
+// Matriz que lleva el origen a `position` y +Y a `up`, para orientar un emisor sobre una
+// superficie
+pub fn aligned(position: Vec3, up: Vec3) -> Mat4 {
+    let y = up.try_normalize(1e-6).unwrap_or_else(Vec3::y);
+    let helper = if y.x.abs() < 0.9 { Vec3::x() } else { Vec3::z() };
+    let x = helper.cross(&y).normalize();
+    let z = x.cross(&y);
+    Mat4::new(
+        x.x, y.x, z.x, position.x,
+        x.y, y.y, z.y, position.y,
+        x.z, y.z, z.z, position.z,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+fn random_direction(rng: &mut Rng) -> Vec3 {
+    let z = rng.range(-1.0, 1.0);
+    let angle = rng.range(0.0, 2.0 * PI);
+    let r = (1.0 - z * z).sqrt();
+    Vec3::new(r * angle.cos(), r * angle.sin(), z)
+}
+
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+}
+
+pub struct ParticleSystem {
+    pub emitter: Emitter,
+    // Matriz del emisor en el mundo; se actualiza cada cuadro si el emisor se mueve. Las
+    // partículas ya emitidas quedan en el mundo y no lo siguen.
+    pub transform: Mat4,
+    // Partículas por unidad de tiempo; con 0 solo salen las de `burst`
+    pub rate: f32,
+    pub lifetime: (f32, f32),
+    // Velocidad inicial, en el espacio del emisor: una parte fija, otra hacia afuera del
+    // emisor y otra al azar en cualquier dirección
+    pub velocity: Vec3,
+    pub outward_speed: f32,
+    pub jitter: f32,
+    // Aceleración en el mundo (gravedad, viento solar) y frenado por unidad de tiempo
+    pub acceleration: Vec3,
+    pub drag: f32,
+    // Tamaño en el mundo al nacer y al morir
+    pub size: (f32, f32),
+    // Color y opacidad a lo largo de la vida: pares (fracción de la vida, RGBA) ordenados
+    pub colors: Vec<(f32, Vec4)>,
+    pub blend_mode: BlendMode,
+    pub max_particles: usize,
+    particles: Vec<Particle>,
+    // Parte de partícula que quedó sin emitir en el cuadro anterior
+    pending: f32,
+    rng: Rng,
+}
+
+impl ParticleSystem {
+    pub fn new(emitter: Emitter, seed: u32) -> Self {
+        ParticleSystem {
+            emitter,
+            transform: Mat4::identity(),
+            rate: 0.0,
+            lifetime: (1.0, 1.0),
+            velocity: Vec3::zeros(),
+            outward_speed: 0.0,
+            jitter: 0.0,
+            acceleration: Vec3::zeros(),
+            drag: 0.0,
+            size: (0.1, 0.1),
+            colors: vec![(0.0, Vec4::new(1.0, 1.0, 1.0, 1.0)), (1.0, Vec4::new(1.0, 1.0, 1.0, 0.0))],
+            blend_mode: BlendMode::Additive,
+            max_particles: 2000,
+            particles: Vec::new(),
+            pending: 0.0,
+            rng: Rng::new(Noise::new(seed)),
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3, outward_speed: f32, jitter: f32) -> Self {
+        self.velocity = velocity;
+        self.outward_speed = outward_speed;
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    pub fn with_size(mut self, start: f32, end: f32) -> Self {
+        self.size = (start, end);
+        self
+    }
+
+    pub fn with_colors(mut self, colors: Vec<(f32, Vec4)>) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    // Suelta `count` partículas de una vez (un impacto, una explosión)
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.max_particles {
+            return;
+        }
+
+        let (point, outward) = self.emitter.sample(&mut self.rng);
+        let local_velocity = self.velocity + outward * self.outward_speed + random_direction(&mut self.rng) * self.jitter * self.rng.next();
+        let position = (self.transform * point.push(1.0)).xyz();
+        let velocity = (self.transform * local_velocity.push(0.0)).xyz();
+        let lifetime = self.rng.range(self.lifetime.0, self.lifetime.1).max(1e-3);
+        self.particles.push(Particle { position, velocity, age: 0.0, lifetime });
+    }
+
+    // Avanza `dt` unidades de tiempo: emite las que tocan según `rate`, mueve las vivas y
+    // quita las que terminaron su vida
+    pub fn update(&mut self, dt: f32) {
+        self.pending += self.rate * dt;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            self.spawn();
+        }
+
+        let damping = (1.0 - self.drag * dt).max(0.0);
+        for particle in self.particles.iter_mut() {
+            particle.velocity = (particle.velocity + self.acceleration * dt) * damping;
+            particle.position += particle.velocity * dt;
+            particle.age += dt;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    // Centro de las partículas vivas en el mundo, para ordenarlas con los objetos transparentes
+    pub fn center(&self) -> Option<Vec3> {
+        if self.particles.is_empty() {
+            return None;
+        }
+        let sum = self.particles.iter().fold(Vec3::zeros(), |sum, particle| sum + particle.position);
+        Some(sum / self.particles.len() as f32)
+    }
+
+    // Color y opacidad en la fracción `t` de la vida, interpolando entre las claves
+    fn color_at(&self, t: f32) -> Vec4 {
+        let Some(&(first_t, first)) = self.colors.first() else {
+            return Vec4::new(1.0, 1.0, 1.0, 1.0);
+        };
+        if t <= first_t {
+            return first;
+        }
+        for pair in self.colors.windows(2) {
+            let ((t0, c0), (t1, c1)) = (pair[0], pair[1]);
+            if t <= t1 {
+                return c0.lerp(&c1, ((t - t0) / (t1 - t0).max(1e-6)).clamp(0.0, 1.0));
+            }
+        }
+        self.colors[self.colors.len() - 1].1
+    }
+
+    // Dibuja las partículas como discos suaves del tamaño que tendrían en el mundo. Con mezcla
+    // alfa van de atrás hacia adelante; las aditivas además emiten, así el bloom les da halo.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, view_matrix: &Mat4, projection_matrix: &Mat4) {
+        let view_projection = projection_matrix * view_matrix;
+        let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+        // projection[(1, 1)] = 1 / tan(fov / 2): de unidades del mundo a píxeles a distancia w
+        let pixels_per_unit = projection_matrix[(1, 1)] * height * 0.5;
+
+        // (x, y, profundidad, radio en píxeles, color, distancia a la cámara)
+        let mut sprites: Vec<(f32, f32, f32, f32, Vec4, f32)> = self
+            .particles
+            .iter()
+            .filter_map(|particle| {
+                let clip = view_projection * particle.position.push(1.0);
+                if clip.w <= 0.0 || clip.z < -clip.w || clip.z > clip.w {
+                    return None;
+                }
+                let t = particle.age / particle.lifetime;
+                let color = self.color_at(t);
+                let size = self.size.0 + (self.size.1 - self.size.0) * t;
+                let radius = (size * pixels_per_unit / clip.w).max(0.75);
+                let x = (clip.x / clip.w * 0.5 + 0.5) * width;
+                let y = (1.0 - (clip.y / clip.w * 0.5 + 0.5)) * height;
+                if color.w <= 0.0 || x + radius < 0.0 || y + radius < 0.0 || x - radius > width || y - radius > height {
+                    return None;
+                }
+                Some((x, y, clip.z / clip.w, radius, color, clip.w))
+            })
+            .collect();
+        if self.blend_mode != BlendMode::Additive {
+            sprites.sort_by(|a, b| b.5.total_cmp(&a.5));
+        }
+
+        framebuffer.set_blend_mode(self.blend_mode);
+        framebuffer.set_depth_write(false);
+        framebuffer.set_current_emission(Vec3::zeros());
+        for (x, y, depth, radius, color, _) in sprites {
+            let rgb = color.xyz();
+            let packed = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u32);
+            framebuffer.set_current_color((packed.x << 16) | (packed.y << 8) | packed.z);
+
+            let x_start = (x - radius).max(0.0) as usize;
+            let y_start = (y - radius).max(0.0) as usize;
+            let x_end = ((x + radius).ceil().max(0.0) as usize).min(framebuffer.width);
+            let y_end = ((y + radius).ceil().max(0.0) as usize).min(framebuffer.height);
+            for py in y_start..y_end {
+                for px in x_start..x_end {
+                    let dx = (px as f32 + 0.5 - x) / radius;
+                    let dy = (py as f32 + 0.5 - y) / radius;
+                    let d2 = dx * dx + dy * dy;
+                    if d2 >= 1.0 {
+                        continue;
+                    }
+                    // Borde suave, sin corte
+                    let falloff = (1.0 - d2) * (1.0 - d2);
+                    framebuffer.set_current_alpha(color.w * falloff);
+                    if self.blend_mode == BlendMode::Additive {
+                        framebuffer.set_current_emission(rgb);
+                    }
+                    framebuffer.point(px, py, depth);
+                }
+            }
+        }
+
+        framebuffer.set_blend_mode(BlendMode::Opaque);
+        framebuffer.set_depth_write(true);
+        framebuffer.set_current_alpha(1.0);
+        framebuffer.set_current_emission(Vec3::zeros());
+    }
+}
+
+// Cola de un cometa: el gas y el polvo salen de toda la superficie del núcleo (la malla del
+// cuerpo) y el viento solar los empuja lejos de la estrella (ver `acceleration`, que hay que
+// apuntar cada cuadro)
+pub fn comet_tail(vertices: &[Vertex], indices: &[u32], seed: u32) -> ParticleSystem {
+    ParticleSystem::new(Emitter::mesh(vertices, indices), seed)
+        .with_rate(400.0)
+        .with_lifetime(1.0, 2.2)
+        .with_velocity(Vec3::zeros(), 1.5, 0.6)
+        .with_drag(0.3)
+        .with_size(0.06, 0.3)
+        .with_colors(vec![
+            (0.0, Vec4::new(0.85, 0.95, 1.0, 0.0)),
+            (0.1, Vec4::new(0.85, 0.95, 1.0, 0.3)),
+            (0.6, Vec4::new(0.4, 0.65, 1.0, 0.15)),
+            (1.0, Vec4::new(0.2, 0.35, 0.9, 0.0)),
+        ])
+        .with_max_particles(1200)
+}
+
+// Escape de un motor iónico: sale hacia -X del emisor (colocar con `transform`) y se enfría
+// de blanco a azul
+pub fn engine_exhaust(seed: u32) -> ParticleSystem {
+    ParticleSystem::new(Emitter::Sphere { radius: 0.15 }, seed)
+        .with_rate(300.0)
+        .with_lifetime(0.2, 0.45)
+        .with_velocity(Vec3::new(-6.0, 0.0, 0.0), 0.0, 0.8)
+        .with_size(0.08, 0.02)
+        .with_colors(vec![
+            (0.0, Vec4::new(1.0, 1.0, 1.0, 0.9)),
+            (0.3, Vec4::new(0.6, 0.8, 1.0, 0.7)),
+            (1.0, Vec4::new(0.2, 0.3, 1.0, 0.0)),
+        ])
+        .with_max_particles(400)
+}
+
+// Escombros de un impacto: salen en ráfaga (ver `burst`) del punto de impacto hacia +Y del
+// emisor, que se alinea con la normal de la superficie, y se frenan mientras se apagan
+pub fn impact_debris(seed: u32) -> ParticleSystem {
+    ParticleSystem::new(Emitter::Point, seed)
+        .with_lifetime(0.4, 1.0)
+        .with_velocity(Vec3::new(0.0, 1.2, 0.0), 0.4, 0.8)
+        .with_drag(1.5)
+        .with_size(0.03, 0.015)
+        .with_colors(vec![
+            (0.0, Vec4::new(1.0, 0.8, 0.4, 1.0)),
+            (0.2, Vec4::new(0.6, 0.5, 0.4, 0.9)),
+            (1.0, Vec4::new(0.35, 0.3, 0.28, 0.0)),
+        ])
+        .with_blend_mode(BlendMode::Alpha)
+        .with_max_particles(600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+
+    fn system() -> ParticleSystem {
+        ParticleSystem::new(Emitter::Point, 1).with_lifetime(10.0, 10.0)
+    }
+
+    #[test]
+    fn color_is_interpolated_between_keys_and_clamped() {
+        let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+        let blue = Vec4::new(0.0, 0.0, 1.0, 0.0);
+        let colors = system().with_colors(vec![(0.2, red), (0.6, blue)]);
+        assert_eq!(colors.color_at(0.0), red);
+        assert_eq!(colors.color_at(0.2), red);
+        assert!((colors.color_at(0.4) - Vec4::new(0.5, 0.0, 0.5, 0.5)).magnitude() < 1e-6);
+        assert_eq!(colors.color_at(0.6), blue);
+        assert_eq!(colors.color_at(1.0), blue);
+        assert_eq!(system().with_colors(Vec::new()).color_at(0.5), Vec4::new(1.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn particles_die_at_their_lifetime() {
+        let mut particles = system().with_lifetime(0.5, 0.5);
+        particles.burst(3);
+        particles.update(0.25);
+        assert_eq!(particles.particles.len(), 3);
+        particles.update(0.25);
+        assert!(particles.particles.is_empty());
+        assert_eq!(particles.center(), None);
+    }
+
+    #[test]
+    fn rate_accumulates_across_frames() {
+        // 4 por unidad de tiempo en pasos de 1/8: una partícula cada dos cuadros
+        let mut particles = system().with_rate(4.0);
+        particles.update(0.125);
+        assert!(particles.particles.is_empty());
+        assert_eq!(particles.pending, 0.5);
+        particles.update(0.125);
+        assert_eq!(particles.particles.len(), 1);
+        for _ in 0..6 {
+            particles.update(0.125);
+        }
+        assert_eq!(particles.particles.len(), 4);
+        assert_eq!(particles.pending, 0.0);
+    }
+
+    #[test]
+    fn max_particles_is_respected() {
+        let mut particles = system().with_rate(1000.0).with_max_particles(5);
+        particles.burst(10);
+        assert_eq!(particles.particles.len(), 5);
+        particles.update(1.0);
+        assert_eq!(particles.particles.len(), 5);
+    }
+
+    #[test]
+    fn mesh_emitter_samples_points_on_the_triangles() {
+        // Dos triángulos en planos distintos y uno degenerado, que no tiene área
+        let positions = [
+            Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 2.0, 2.0), Vec3::new(0.0, 0.0, 4.0),
+            Vec3::new(5.0, 5.0, 5.0),
+        ];
+        let vertices: Vec<Vertex> = positions.iter().map(|&p| Vertex::new(p, Vec3::z(), Vec2::zeros())).collect();
+        let emitter = Emitter::mesh(&vertices, &[0, 1, 2, 3, 4, 5, 6, 6, 6]);
+        let Emitter::Mesh { triangles, .. } = &emitter else { unreachable!() };
+
+        let mut rng = Rng::new(Noise::new(3));
+        let mut hits = [0; 3];
+        for _ in 0..500 {
+            let (point, normal) = emitter.sample(&mut rng);
+            let index = triangles.iter().position(|&[a, b, c]| {
+                // Dentro del triángulo si las tres subáreas suman el área entera
+                let area = |p: Vec3, q: Vec3, r: Vec3| (q - p).cross(&(r - p)).magnitude();
+                (area(point, b, c) + area(a, point, c) + area(a, b, point) - area(a, b, c)).abs() < 1e-4
+            });
+            let index = index.expect("el punto cae sobre algún triángulo");
+            let [a, b, c] = triangles[index];
+            assert!((normal - (b - a).cross(&(c - a)).normalize()).magnitude() < 1e-5);
+            hits[index] += 1;
+        }
+        // El segundo triángulo tiene cuatro veces el área del primero
+        assert_eq!(hits[2], 0);
+        assert!(hits[1] > hits[0] * 2, "{:?}", hits);
+    }
+}
@@ -0,0 +1,253 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::noise::{Noise, Rng};
+
+// Generador de planetas: a partir de una clase y una semilla elige los parámetros que
+// usan los shaders de superficie (paleta, nivel del mar, nubes, casquetes, lava, bandas,
+// tormenta y ciudades). La misma semilla da siempre el mismo planeta. Las estrellas no son
+// planetas: su paleta sale de su clase espectral (ver star.rs).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanetClass {
+    Rocky,
+    GasGiant,
+    Ice,
+    Desert,
+    Volcanic,
+}
+
+impl PlanetClass {
+    pub const ALL: [PlanetClass; 5] = [
+        PlanetClass::Rocky,
+        PlanetClass::GasGiant,
+        PlanetClass::Ice,
+        PlanetClass::Desert,
+        PlanetClass::Volcanic,
+    ];
+
+    // Número de shader de fragment_shader que dibuja esta clase
+    pub fn shader_type(&self) -> u32 {
+        match self {
+            PlanetClass::Rocky => 1,
+            PlanetClass::GasGiant => 2,
+            PlanetClass::Ice => 4,
+            PlanetClass::Desert => 5,
+            PlanetClass::Volcanic => 6,
+        }
+    }
+
+    pub fn from_shader_type(shader_type: u32) -> Option<PlanetClass> {
+        PlanetClass::ALL.into_iter().find(|class| class.shader_type() == shader_type)
+    }
+}
+
+// Parámetros de la superficie de un planeta. El significado de la paleta depende de la clase:
+// - Estrellas (sin clase): núcleo, borde, llamaradas (el cuarto color no se usa)
+// - Rocky: océano profundo, océano somero, tierra baja, tierra alta
+// - GasGiant: banda clara, banda oscura, tormenta, reflejos
+// - Ice: hielo, grietas, nieve, hielo sucio de los casquetes
+// - Desert: arena oscura, arena clara, crestas de las dunas, roca de los casquetes
+// - Volcanic: roca, lava, brillo de la lava, detalle de la roca
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanetParams {
+    // None para las superficies que no son de un planeta (las estrellas)
+    pub class: Option<PlanetClass>,
+    pub palette: [Vec3; 4],
+    // Elevación en [0, 1] por debajo de la cual hay océano
+    pub ocean_level: f32,
+    // Frecuencia de las formas grandes (continentes, placas, manchas) sobre la esfera unitaria
+    pub continent_frequency: f32,
+    // Fracción del cielo cubierta de nubes, en [0, 1]; los rocosos las llevan en su propia
+    // capa (ver shaders::Clouds) y los demás las pintan sobre la superficie
+    pub cloud_cover: f32,
+    // Extensión de los casquetes polares en latitud (0 = sin casquetes, 1 = todo el planeta)
+    pub ice_caps: f32,
+    // Fracción de la superficie volcánica cubierta de lava, en [0, 1]
+    pub lava_amount: f32,
+    // Pares de bandas claras y oscuras de polo a polo
+    pub band_count: f32,
+    // Centro de la tormenta sobre la esfera unitaria y su radio (0 = sin tormenta)
+    pub storm_position: Vec3,
+    pub storm_size: f32,
+    // Cuánto está poblado un planeta rocoso: luces de ciudades en el lado nocturno (0 = deshabitado)
+    pub city_lights: f32,
+}
+
+impl PlanetParams {
+    // Valores por defecto de todos los parámetros con la paleta dada
+    pub fn from_palette(palette: [Vec3; 4]) -> Self {
+        PlanetParams {
+            class: None,
+            palette,
+            ocean_level: 0.55,
+            continent_frequency: 3.0,
+            cloud_cover: 0.0,
+            ice_caps: 0.0,
+            lava_amount: 0.55,
+            band_count: 2.5,
+            storm_position: latitude_longitude(-0.35, 0.0),
+            storm_size: 0.25,
+            city_lights: 0.0,
+        }
+    }
+
+    // Los valores con los que se diseñaron los shaders del sistema solar; solo la tormenta
+    // cambia de lugar con la semilla
+    pub fn new(class: PlanetClass, seed: u32) -> Self {
+        let rgb = Vec3::new;
+        let (palette, continent_frequency) = match class {
+            PlanetClass::Rocky => ([rgb(0.0, 0.1, 0.3), rgb(0.1, 0.3, 0.7), rgb(0.1, 0.4, 0.1), rgb(0.6, 0.5, 0.3)], 2.0),
+            PlanetClass::GasGiant => ([rgb(0.8, 0.7, 0.5), rgb(0.6, 0.4, 0.2), rgb(0.95, 0.3, 0.15), rgb(1.0, 1.0, 1.0)], 3.0),
+            PlanetClass::Ice => ([rgb(0.8, 0.9, 1.0), rgb(0.3, 0.4, 0.6), rgb(1.0, 1.0, 1.0), rgb(0.7, 0.75, 0.8)], 3.0),
+            PlanetClass::Desert => ([rgb(0.6, 0.4, 0.1), rgb(0.9, 0.7, 0.3), rgb(0.95, 0.8, 0.4), rgb(0.95, 0.93, 0.9)], 4.0),
+            PlanetClass::Volcanic => ([rgb(0.2, 0.15, 0.1), rgb(1.0, 0.3, 0.0), rgb(1.0, 0.5, 0.0), rgb(0.3, 0.25, 0.2)], 3.0),
+        };
+
+        PlanetParams {
+            class: Some(class),
+            continent_frequency,
+            cloud_cover: if class == PlanetClass::Rocky { 0.45 } else { 0.0 },
+            storm_position: storm_position(seed),
+            city_lights: if class == PlanetClass::Rocky { 0.6 } else { 0.0 },
+            ..PlanetParams::from_palette(palette)
+        }
+    }
+
+    // Un planeta al azar pero verosímil de la clase dada
+    pub fn generate(class: PlanetClass, seed: u32) -> Self {
+        let mut rng = Rng::new(Noise::new(seed).derive(0x504C));
+        let mut params = PlanetParams::new(class, seed);
+
+        match class {
+            PlanetClass::Rocky => {
+                // Océanos entre el cian y el azul profundo; tierra de verde a ocre o rojiza
+                let ocean_hue = rng.range(0.5, 0.68);
+                let land_hue = rng.range(0.0, 0.36);
+                params.palette = [
+                    hsv(ocean_hue, rng.range(0.8, 1.0), rng.range(0.2, 0.35)),
+                    hsv(ocean_hue - 0.03, rng.range(0.6, 0.85), rng.range(0.55, 0.75)),
+                    hsv(land_hue, rng.range(0.5, 0.8), rng.range(0.3, 0.45)),
+                    hsv(rng.range(0.05, 0.12), rng.range(0.3, 0.55), rng.range(0.5, 0.7)),
+                ];
+                params.ocean_level = rng.range(0.4, 0.65);
+                params.continent_frequency = rng.range(1.2, 2.8);
+                params.cloud_cover = rng.range(0.0, 0.7);
+                params.ice_caps = rng.range(0.0, 0.25);
+                params.city_lights = if rng.chance(0.6) { rng.range(0.2, 1.0) } else { 0.0 };
+            }
+            PlanetClass::GasGiant => {
+                // Casi siempre tonos de Júpiter y Saturno; a veces un gigante helado azul
+                let hue = if rng.chance(0.3) { rng.range(0.5, 0.62) } else { rng.range(0.03, 0.14) };
+                params.palette = [
+                    hsv(hue, rng.range(0.2, 0.4), rng.range(0.75, 0.9)),
+                    hsv(hue - 0.02, rng.range(0.5, 0.75), rng.range(0.45, 0.65)),
+                    hsv(rng.range(-0.03, 0.06), rng.range(0.6, 0.85), rng.range(0.8, 0.95)),
+                    hsv(hue, 0.05, 1.0),
+                ];
+                params.band_count = rng.range(1.5, 5.0);
+                params.storm_size = if rng.chance(0.25) { 0.0 } else { rng.range(0.12, 0.35) };
+            }
+            PlanetClass::Ice => {
+                let hue = rng.range(0.5, 0.62);
+                params.palette = [
+                    hsv(hue, rng.range(0.1, 0.25), rng.range(0.9, 1.0)),
+                    hsv(hue + 0.02, rng.range(0.35, 0.55), rng.range(0.5, 0.65)),
+                    hsv(hue, 0.02, 1.0),
+                    hsv(rng.range(0.05, 0.6), rng.range(0.05, 0.2), rng.range(0.7, 0.85)),
+                ];
+                params.continent_frequency = rng.range(2.0, 4.5);
+                params.cloud_cover = rng.range(0.0, 0.3);
+                params.ice_caps = rng.range(0.0, 0.25);
+            }
+            PlanetClass::Desert => {
+                let hue = rng.range(0.02, 0.12);
+                params.palette = [
+                    hsv(hue, rng.range(0.7, 0.85), rng.range(0.5, 0.65)),
+                    hsv(hue + 0.01, rng.range(0.55, 0.7), rng.range(0.85, 0.95)),
+                    hsv(hue + 0.02, rng.range(0.45, 0.6), rng.range(0.9, 1.0)),
+                    hsv(hue, 0.05, 0.95),
+                ];
+                params.continent_frequency = rng.range(3.0, 5.0);
+                params.cloud_cover = rng.range(0.0, 0.2);
+                params.ice_caps = rng.range(0.0, 0.2);
+            }
+            PlanetClass::Volcanic => {
+                let hue = rng.range(0.0, 0.08);
+                params.palette = [
+                    hsv(rng.range(0.02, 0.1), rng.range(0.3, 0.5), rng.range(0.12, 0.22)),
+                    hsv(hue, 1.0, 1.0),
+                    hsv(hue + 0.05, 1.0, 1.0),
+                    hsv(rng.range(0.02, 0.1), rng.range(0.2, 0.35), rng.range(0.25, 0.35)),
+                ];
+                params.continent_frequency = rng.range(2.0, 4.0);
+                params.lava_amount = rng.range(0.3, 0.7);
+            }
+        }
+
+        params
+    }
+}
+
+// Centro de la tormenta de la semilla, en latitudes medias y a cualquier longitud
+fn storm_position(seed: u32) -> Vec3 {
+    let mut rng = Rng::new(Noise::new(seed).derive(0x5354));
+    latitude_longitude(rng.range(-0.6, 0.6), rng.range(0.0, 2.0 * PI))
+}
+
+// Punto de la esfera unitaria en una latitud y longitud (radianes)
+fn latitude_longitude(latitude: f32, longitude: f32) -> Vec3 {
+    Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin())
+}
+
+// Color a partir de tono (vueltas, se repite), saturación y valor
+fn hsv(hue: f32, saturation: f32, value: f32) -> Vec3 {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let channel = |offset: f32| {
+        let k = (h + offset) % 6.0;
+        value - value * saturation * (k.min(4.0 - k)).clamp(0.0, 1.0)
+    };
+    Vec3::new(channel(5.0), channel(3.0), channel(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storm_position_follows_the_seed() {
+        let storm = |seed| PlanetParams::new(PlanetClass::GasGiant, seed).storm_position;
+        assert_eq!(storm(7), storm(7));
+        assert_ne!(storm(7), storm(8));
+        assert!((storm(7).magnitude() - 1.0).abs() < 1e-5);
+        // El generador parte de los mismos valores, así que la tormenta no cambia de lugar
+        assert_eq!(PlanetParams::generate(PlanetClass::GasGiant, 7).storm_position, storm(7));
+    }
+
+    #[test]
+    fn generate_depends_only_on_the_seed() {
+        for class in PlanetClass::ALL {
+            for seed in 0..50 {
+                assert_eq!(PlanetParams::generate(class, seed), PlanetParams::generate(class, seed));
+                assert_ne!(PlanetParams::generate(class, seed), PlanetParams::generate(class, seed + 1), "{:?} {}", class, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_values_stay_in_range() {
+        let unit = 0.0..=1.0;
+        for class in PlanetClass::ALL {
+            for seed in 0..500 {
+                let params = PlanetParams::generate(class, seed);
+                assert_eq!(params.class, Some(class));
+                for color in params.palette {
+                    assert!(color.iter().all(|c| unit.contains(c)), "{:?} {}: {:?}", class, seed, color);
+                }
+                assert!(unit.contains(&params.ocean_level), "{:?} {}", class, seed);
+                assert!(unit.contains(&params.cloud_cover), "{:?} {}", class, seed);
+                assert!(unit.contains(&params.lava_amount), "{:?} {}", class, seed);
+                assert!(unit.contains(&params.ice_caps), "{:?} {}", class, seed);
+            }
+        }
+    }
+}
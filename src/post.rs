@@ -0,0 +1,380 @@
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use crate::framebuffer::Framebuffer;
+use crate::bounds::BoundingSphere;
+use crate::lod::projected_radius;
+
+// Efectos de post-proceso sobre el framebuffer ya dibujado
+
+// Reducción del buffer de emisión antes de desenfocarlo: el halo sale más ancho y barato
+const BLOOM_DOWNSAMPLE: usize = 4;
+// Desviación del desenfoque gaussiano, en píxeles del buffer reducido
+const BLOOM_SIGMA: f32 = 3.0;
+// Solo brilla la emisión por encima de este valor, para no lavar el interior de la estrella
+const BLOOM_THRESHOLD: f32 = 0.6;
+
+// Los rayos de luz se calculan en un buffer reducido, como el bloom
+const SHAFTS_DOWNSAMPLE: usize = 4;
+// Muestras de cada rayo hacia la estrella, fracción del camino que recorren y cuánto pesa
+// cada muestra respecto de la anterior
+const SHAFTS_SAMPLES: usize = 48;
+const SHAFTS_DENSITY: f32 = 0.9;
+const SHAFTS_DECAY: f32 = 0.96;
+// Hasta cuántos radios de la estrella llega la luz que se dispersa en rayos
+const SHAFTS_REACH: f32 = 4.0;
+
+// Reflejos del lente: posición a lo largo de la recta de la estrella al centro de la pantalla
+// (0 en la estrella, 1 en el centro, 2 en el punto opuesto), radio en fracciones del alto de
+// la imagen, color y si es un anillo en vez de un disco
+const FLARE_GHOSTS: [(f32, f32, [f32; 3], bool); 7] = [
+    (0.45, 0.020, [0.9, 0.6, 0.3], false),
+    (0.7, 0.045, [0.3, 0.7, 0.4], false),
+    (1.1, 0.015, [0.8, 0.8, 1.0], false),
+    (1.35, 0.070, [0.4, 0.5, 0.9], false),
+    (1.6, 0.030, [0.9, 0.4, 0.6], false),
+    (1.9, 0.110, [0.5, 0.8, 0.9], true),
+    (2.2, 0.050, [0.9, 0.7, 0.3], false),
+];
+
+// Bloom: la luz emitida (estrella, lava, ciudades) se desenfoca y se suma al color, de modo
+// que lo que brilla deja un halo sobre lo que tiene alrededor. Solo usa el buffer de
+// emisión, así que lo que solo está iluminado no brilla.
+pub fn bloom(framebuffer: &mut Framebuffer, strength: f32) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let small_width = width.div_ceil(BLOOM_DOWNSAMPLE);
+    let small_height = height.div_ceil(BLOOM_DOWNSAMPLE);
+
+    // Promedio de cada bloque de BLOOM_DOWNSAMPLE x BLOOM_DOWNSAMPLE
+    let mut small = vec![Vec3::zeros(); small_width * small_height];
+    for y in 0..height {
+        for x in 0..width {
+            let emission = framebuffer.emission[y * width + x];
+            if emission.max() > BLOOM_THRESHOLD {
+                small[(y / BLOOM_DOWNSAMPLE) * small_width + x / BLOOM_DOWNSAMPLE] += emission.map(|e| (e - BLOOM_THRESHOLD).max(0.0));
+            }
+        }
+    }
+    if small.iter().all(|e| e.max() <= 0.0) {
+        return;
+    }
+    let area = (BLOOM_DOWNSAMPLE * BLOOM_DOWNSAMPLE) as f32;
+    for e in small.iter_mut() {
+        *e /= area;
+    }
+
+    // Gaussiano separable: primero filas, después columnas
+    let radius = (BLOOM_SIGMA * 3.0).ceil() as usize;
+    let weights: Vec<f32> = (0..=2 * radius)
+        .map(|i| {
+            let d = i as f32 - radius as f32;
+            (-d * d / (2.0 * BLOOM_SIGMA * BLOOM_SIGMA)).exp()
+        })
+        .collect();
+    let horizontal = blur(&small, small_width, small_height, 1, small_width, &weights);
+    let blurred = blur(&horizontal, small_height, small_width, small_width, 1, &weights);
+
+    add_upscaled(framebuffer, &blurred, BLOOM_DOWNSAMPLE, Vec3::repeat(strength));
+}
+
+// Vuelve a tamaño completo un buffer reducido `downsample` veces con interpolación bilineal y
+// lo suma al color, multiplicado por `tint`. Cada píxel cae entre cuatro centros del buffer
+// reducido; los bloques donde no hay nada se saltan enteros.
+fn add_upscaled(framebuffer: &mut Framebuffer, small: &[Vec3], downsample: usize, tint: Vec3) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let small_width = width.div_ceil(downsample);
+    let small_height = height.div_ceil(downsample);
+    let sample = |x: usize, y: usize| small[y.min(small_height - 1) * small_width + x.min(small_width - 1)];
+    let half = downsample / 2;
+    for cell_y in 0..small_height {
+        for cell_x in 0..small_width {
+            let corners = [sample(cell_x, cell_y), sample(cell_x + 1, cell_y), sample(cell_x, cell_y + 1), sample(cell_x + 1, cell_y + 1)];
+            if corners.iter().all(|c| c.max() <= 0.0) {
+                continue;
+            }
+
+            // Los píxeles antes del primer centro se quedan con la primera celda
+            let start = |cell: usize| if cell == 0 { 0 } else { cell * downsample + half };
+            let (x_start, x_end) = (start(cell_x), ((cell_x + 1) * downsample + half).min(width));
+            let (y_start, y_end) = (start(cell_y), ((cell_y + 1) * downsample + half).min(height));
+            for y in y_start..y_end {
+                let ty = ((y as f32 + 0.5) / downsample as f32 - 0.5 - cell_y as f32).clamp(0.0, 1.0);
+                let left = corners[0].lerp(&corners[2], ty).component_mul(&tint);
+                let right = corners[1].lerp(&corners[3], ty).component_mul(&tint);
+                for x in x_start..x_end {
+                    let tx = ((x as f32 + 0.5) / downsample as f32 - 0.5 - cell_x as f32).clamp(0.0, 1.0);
+                    let index = y * width + x;
+                    framebuffer.buffer[index] = add_color(framebuffer.buffer[index], left.lerp(&right, tx));
+                }
+            }
+        }
+    }
+}
+
+// Suma un color en [0, 1] por canal a un píxel, saturando en blanco
+fn add_color(pixel: u32, color: Vec3) -> u32 {
+    let add = |shift: u32, value: f32| -> u32 {
+        let channel = ((pixel >> shift) & 0xFF) as f32 + value * 255.0;
+        (channel.clamp(0.0, 255.0) as u32) << shift
+    };
+    add(16, color.x) | add(8, color.y) | add(0, color.z)
+}
+
+// Un paso del desenfoque a lo largo de `lines` líneas de `length` muestras. `step` separa dos
+// muestras seguidas de una línea y `stride` el comienzo de dos líneas. En los bordes solo
+// cuentan los pesos que caen dentro de la imagen.
+fn blur(source: &[Vec3], length: usize, lines: usize, step: usize, stride: usize, weights: &[f32]) -> Vec<Vec3> {
+    let radius = weights.len() / 2;
+    let mut out = vec![Vec3::zeros(); source.len()];
+    for line in 0..lines {
+        let start = line * stride;
+        for i in 0..length {
+            let (first, last) = (i.saturating_sub(radius), (i + radius).min(length - 1));
+            let mut sum = Vec3::zeros();
+            let mut total = 0.0;
+            for j in first..=last {
+                let weight = weights[j + radius - i];
+                sum += source[start + j * step] * weight;
+                total += weight;
+            }
+            out[start + i * step] = sum / total;
+        }
+    }
+    out
+}
+
+// Estrella vista desde la cámara: centro y radio en píxeles, profundidad (la del z-buffer)
+// de su punto más cercano a la cámara y color de su luz
+#[derive(Debug, Clone, Copy)]
+pub struct SunScreen {
+    pub position: Vec2,
+    pub radius: f32,
+    pub depth: f32,
+    pub color: Vec3,
+}
+
+impl SunScreen {
+    // Proyecta una estrella de centro `center` y radio `radius`. None si está detrás de la
+    // cámara o la cámara está dentro de ella.
+    pub fn project(center: Vec3, radius: f32, color: Vec3, camera_position: Vec3, view_matrix: &Mat4, projection_matrix: &Mat4, framebuffer: &Framebuffer) -> Option<Self> {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let view_projection = projection_matrix * view_matrix;
+        let project = |point: Vec3| {
+            let clip = view_projection * point.push(1.0);
+            (clip.w > 0.0).then(|| clip.xyz() / clip.w)
+        };
+
+        let to_camera = camera_position - center;
+        if to_camera.magnitude() <= radius {
+            return None;
+        }
+        let ndc = project(center)?;
+        // Un poco por delante de la superficie, para que la estrella nunca se tape a sí misma
+        let front = project(center + to_camera.normalize() * radius * 1.02)?;
+        let screen_radius = projected_radius(&BoundingSphere { center, radius }, camera_position, projection_matrix, height as f32);
+
+        Some(SunScreen {
+            position: Vec2::new((ndc.x * 0.5 + 0.5) * width as f32, (1.0 - (ndc.y * 0.5 + 0.5)) * height as f32),
+            radius: screen_radius,
+            depth: front.z,
+            color,
+        })
+    }
+
+    // Si lo que se ve en el píxel está detrás de la cara visible de la estrella (o es el fondo)
+    fn is_lit(&self, framebuffer: &Framebuffer, x: usize, y: usize) -> bool {
+        framebuffer.zbuffer[y * framebuffer.width + x] >= self.depth
+    }
+}
+
+// Fracción del disco de la estrella que se ve: se muestrea una rejilla sobre el disco y cada
+// punto cuenta si cae en pantalla y nada más cercano lo tapa en el z-buffer. Es 0 con la
+// estrella fuera de la pantalla o detrás de un planeta, y baja gradualmente mientras entra.
+pub fn sun_visibility(framebuffer: &Framebuffer, sun: &SunScreen) -> f32 {
+    const GRID: i32 = 6;
+    let mut total = 0;
+    let mut visible = 0;
+    for j in -GRID..=GRID {
+        for i in -GRID..=GRID {
+            let offset = Vec2::new(i as f32, j as f32) / GRID as f32;
+            if offset.magnitude() > 1.0 {
+                continue;
+            }
+            total += 1;
+            let point = sun.position + offset * sun.radius;
+            if point.x < 0.0 || point.y < 0.0 {
+                continue;
+            }
+            let (x, y) = (point.x as usize, point.y as usize);
+            if x < framebuffer.width && y < framebuffer.height && sun.is_lit(framebuffer, x, y) {
+                visible += 1;
+            }
+        }
+    }
+    visible as f32 / total as f32
+}
+
+// Rayos de luz (dispersión volumétrica en espacio de pantalla): una máscara marca dónde se ve
+// la estrella o el cielo a su alrededor y pone a 0 lo que tapan los planetas según el
+// z-buffer; cada píxel suma la máscara a lo largo de la recta hacia la estrella, cada vez con
+// menos peso. Los planetas delante de la estrella dejan así haces de sombra.
+pub fn light_shafts(framebuffer: &mut Framebuffer, sun: &SunScreen, visibility: f32, strength: f32) {
+    if visibility <= 0.0 {
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let small_width = width.div_ceil(SHAFTS_DOWNSAMPLE);
+    let small_height = height.div_ceil(SHAFTS_DOWNSAMPLE);
+    let reach = sun.radius * SHAFTS_REACH;
+
+    // Máscara en el centro de cada bloque
+    let mut mask = vec![0.0; small_width * small_height];
+    for small_y in 0..small_height {
+        for small_x in 0..small_width {
+            let x = (small_x * SHAFTS_DOWNSAMPLE + SHAFTS_DOWNSAMPLE / 2).min(width - 1);
+            let y = (small_y * SHAFTS_DOWNSAMPLE + SHAFTS_DOWNSAMPLE / 2).min(height - 1);
+            let distance = (Vec2::new(x as f32, y as f32) - sun.position).magnitude();
+            if distance < reach && sun.is_lit(framebuffer, x, y) {
+                let falloff = 1.0 - distance / reach;
+                mask[small_y * small_width + small_x] = falloff * falloff;
+            }
+        }
+    }
+
+    // Marcha radial hacia la estrella
+    let sun_small = sun.position / SHAFTS_DOWNSAMPLE as f32;
+    let mut shafts = vec![Vec3::zeros(); small_width * small_height];
+    for small_y in 0..small_height {
+        for small_x in 0..small_width {
+            let start = Vec2::new(small_x as f32 + 0.5, small_y as f32 + 0.5);
+            let step = (start - sun_small) * SHAFTS_DENSITY / SHAFTS_SAMPLES as f32;
+            let mut point = start;
+            let mut weight = 1.0;
+            let mut sum = 0.0;
+            for _ in 0..SHAFTS_SAMPLES {
+                point -= step;
+                if point.x >= 0.0 && point.y >= 0.0 && (point.x as usize) < small_width && (point.y as usize) < small_height {
+                    sum += mask[point.y as usize * small_width + point.x as usize] * weight;
+                }
+                weight *= SHAFTS_DECAY;
+            }
+            shafts[small_y * small_width + small_x] = Vec3::repeat(sum / SHAFTS_SAMPLES as f32);
+        }
+    }
+
+    add_upscaled(framebuffer, &shafts, SHAFTS_DOWNSAMPLE, sun.color * strength * visibility);
+}
+
+// Lens flare: un resplandor con una franja horizontal sobre la estrella y reflejos (ghosts)
+// a lo largo de la recta que pasa por el centro de la pantalla, como los que dejan las
+// lentes de una cámara. Todo se escala con la parte visible de la estrella.
+pub fn lens_flare(framebuffer: &mut Framebuffer, sun: &SunScreen, visibility: f32, strength: f32) {
+    if visibility <= 0.0 {
+        return;
+    }
+
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    let intensity = strength * visibility;
+
+    // Resplandor y franja, más anchos que la estrella
+    let glare_radius = (sun.radius * 1.5).max(height * 0.04);
+    let streak_length = width * 0.35;
+    let glare = |offset: Vec2| {
+        let radial = (-offset.magnitude() / glare_radius).exp() * 0.6;
+        let streak = (-(offset.y / (glare_radius * 0.08)).powi(2)).exp() * (1.0 - offset.x.abs() / streak_length).max(0.0).powi(2) * 0.5;
+        radial + streak
+    };
+    let extent = Vec2::new(streak_length, glare_radius * 5.0);
+    draw_additive(framebuffer, sun.position, extent, |offset| sun.color * glare(offset) * intensity);
+
+    // Reflejos a lo largo de la recta de la estrella al centro
+    let axis = Vec2::new(width, height) * 0.5 - sun.position;
+    for &(t, size, [r, g, b], ring) in FLARE_GHOSTS.iter() {
+        let center = sun.position + axis * t;
+        let radius = size * height;
+        let tint = Vec3::new(r, g, b).component_mul(&sun.color) * intensity * 0.25;
+        draw_additive(framebuffer, center, Vec2::repeat(radius), |offset| {
+            let d = offset.magnitude() / radius;
+            let shape = if ring {
+                (1.0 - ((d - 0.9) / 0.1).abs()).max(0.0)
+            } else {
+                // Disco con el borde un poco más brillante y suave
+                (1.0 - ((d - 0.85) / 0.15).max(0.0)).max(0.0) * (0.6 + 0.4 * d * d)
+            };
+            tint * shape
+        });
+    }
+}
+
+// Suma `color(desplazamiento desde el centro)` a los píxeles del rectángulo de semiancho y
+// semialto `extent` alrededor de `center`
+fn draw_additive(framebuffer: &mut Framebuffer, center: Vec2, extent: Vec2, color: impl Fn(Vec2) -> Vec3) {
+    let x_start = (center.x - extent.x).max(0.0) as usize;
+    let y_start = (center.y - extent.y).max(0.0) as usize;
+    let x_end = ((center.x + extent.x).max(0.0) as usize).min(framebuffer.width);
+    let y_end = ((center.y + extent.y).max(0.0) as usize).min(framebuffer.height);
+    for y in y_start..y_end {
+        for x in x_start..x_end {
+            let value = color(Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center);
+            if value.max() > 0.0 {
+                let index = y * framebuffer.width + x;
+                framebuffer.buffer[index] = add_color(framebuffer.buffer[index], value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{look_at, perspective};
+    use std::f32::consts::PI;
+
+    // Cámara en el origen mirando hacia -Z y una estrella de radio 1 en `center`
+    fn sun(framebuffer: &Framebuffer, center: Vec3) -> Option<SunScreen> {
+        let view = look_at(&Vec3::zeros(), &Vec3::new(0.0, 0.0, -1.0), &Vec3::y());
+        let projection = perspective(framebuffer.width as f32 / framebuffer.height as f32, PI / 4.0, 0.1, 100.0);
+        SunScreen::project(center, 1.0, Vec3::new(1.0, 1.0, 1.0), Vec3::zeros(), &view, &projection, framebuffer)
+    }
+
+    #[test]
+    fn unoccluded_sun_is_fully_visible() {
+        let mut framebuffer = Framebuffer::new(160, 120);
+        let sun = sun(&framebuffer, Vec3::new(0.0, 0.0, -20.0)).unwrap();
+        assert!((sun.position - Vec2::new(80.0, 60.0)).magnitude() < 1e-3);
+        assert!(sun.radius > 1.0);
+        assert_eq!(sun_visibility(&framebuffer, &sun), 1.0);
+
+        // Lo que está detrás de la estrella no la tapa
+        framebuffer.zbuffer.fill(sun.depth + 1e-3);
+        assert_eq!(sun_visibility(&framebuffer, &sun), 1.0);
+    }
+
+    #[test]
+    fn sun_off_screen_or_behind_is_not_visible() {
+        let framebuffer = Framebuffer::new(160, 120);
+        assert!(sun(&framebuffer, Vec3::new(0.0, 0.0, 20.0)).is_none());
+        // Dentro de la estrella tampoco hay disco que mirar
+        assert!(sun(&framebuffer, Vec3::new(0.0, 0.0, -0.5)).is_none());
+
+        let aside = sun(&framebuffer, Vec3::new(100.0, 0.0, -20.0)).unwrap();
+        assert!(aside.position.x > framebuffer.width as f32);
+        assert_eq!(sun_visibility(&framebuffer, &aside), 0.0);
+    }
+
+    #[test]
+    fn closer_depth_hides_the_sun() {
+        let mut framebuffer = Framebuffer::new(160, 120);
+        let sun = sun(&framebuffer, Vec3::new(0.0, 0.0, -20.0)).unwrap();
+
+        // Un planeta que tapa la mitad izquierda del disco
+        let half = sun.position.x as usize;
+        for y in 0..framebuffer.height {
+            framebuffer.zbuffer[y * framebuffer.width..y * framebuffer.width + half].fill(sun.depth - 1e-3);
+        }
+        let partial = sun_visibility(&framebuffer, &sun);
+        assert!(partial > 0.3 && partial < 0.7, "{}", partial);
+
+        framebuffer.zbuffer.fill(sun.depth - 1e-3);
+        assert_eq!(sun_visibility(&framebuffer, &sun), 0.0);
+    }
+}
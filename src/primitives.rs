@@ -0,0 +1,425 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use crate::vertex::Vertex;
+use crate::tangents::generate_tangents;
+
+// Generadores de mallas procedurales. Todos devuelven el mismo par (vértices, índices)
+// que Obj::get_vertex_and_index_arrays, así que se dibujan igual que un modelo cargado.
+// Las caras quedan en sentido antihorario vistas desde afuera, que es lo que espera
+// el backface culling de triangle.rs.
+
+// Agrega una cuadrícula paramétrica de (rows + 1) x (cols + 1) vértices.
+// `f(s, t)` recibe s = fila / rows y t = columna / cols; la orientación es correcta
+// cuando dP/dt x dP/ds apunta hacia afuera. Los triángulos degenerados (polos) se omiten.
+fn push_grid(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, rows: u32, cols: u32, f: impl Fn(f32, f32) -> Vertex) {
+    let base = vertices.len() as u32;
+
+    for i in 0..=rows {
+        for j in 0..=cols {
+            vertices.push(f(i as f32 / rows as f32, j as f32 / cols as f32));
+        }
+    }
+
+    let row = cols + 1;
+    for i in 0..rows {
+        for j in 0..cols {
+            let a = base + i * row + j;
+            let b = a + row;
+            let c = a + 1;
+            let d = b + 1;
+
+            for [x, y, z] in [[a, c, b], [c, d, b]] {
+                let (px, py, pz) = (
+                    vertices[x as usize].position,
+                    vertices[y as usize].position,
+                    vertices[z as usize].position,
+                );
+                if (py - px).cross(&(pz - px)).magnitude() > 1e-10 {
+                    indices.extend_from_slice(&[x, y, z]);
+                }
+            }
+        }
+    }
+}
+
+// Todas las mallas salen con tangentes para poder usar mapas de normales
+fn with_tangents(mut vertices: Vec<Vertex>, mut indices: Vec<u32>) -> (Vec<Vertex>, Vec<u32>) {
+    generate_tangents(&mut vertices, &mut indices);
+    (vertices, indices)
+}
+
+// Anillo plano en el plano XZ, centrado en el origen.
+// UV: u va de 0 (borde interior) a 1 (borde exterior), v recorre el ángulo.
+// Se generan las dos caras porque el rasterizador descarta las caras traseras.
+pub fn ring(inner_radius: f32, outer_radius: f32, segments: u32, radial_segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let radial_segments = radial_segments.max(1);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for side in [1.0, -1.0] {
+        push_grid(&mut vertices, &mut indices, radial_segments, segments, |s, t| {
+            let radius = inner_radius + (outer_radius - inner_radius) * s;
+            let (sin, cos) = (t * 2.0 * PI * side).sin_cos();
+            Vertex::new(
+                Vec3::new(cos * radius, 0.0, sin * radius),
+                Vec3::new(0.0, side, 0.0),
+                Vec2::new(s, t),
+            )
+        });
+    }
+
+    with_tangents(vertices, indices)
+}
+
+// Esfera UV con el eje en Y. La columna de la costura se duplica (u = 0 y u = 1)
+// y cada triángulo de los polos tiene su propio vértice con u centrado.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    push_grid(&mut vertices, &mut indices, rings, segments, |s, t| {
+        let at_pole = s == 0.0 || s == 1.0;
+        let (sin_phi, cos_phi) = if at_pole { (0.0, 1.0 - 2.0 * s) } else { (s * PI).sin_cos() };
+        let (sin_theta, cos_theta) = (t * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(sin_phi * cos_theta, cos_phi, sin_phi * sin_theta);
+
+        let u = if at_pole { t + 0.5 / segments as f32 } else { t };
+        Vertex::new(normal * radius, normal, Vec2::new(u, s))
+    });
+
+    with_tangents(vertices, indices)
+}
+
+// Icoesfera: icosaedro subdividido `subdivisions` veces y proyectado a la esfera.
+// Los triángulos que cruzan la costura duplican sus vértices con u + 1 y los
+// vértices de los polos se duplican por triángulo para que u no se deforme.
+pub fn icosphere(radius: f32, subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut positions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vec3>| -> u32 {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) * 0.5).normalize());
+                positions.len() as u32 - 1
+            })
+        };
+
+        let mut next = Vec::with_capacity(faces.len() * 4);
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut positions);
+            let bc = midpoint(b, c, &mut positions);
+            let ca = midpoint(c, a, &mut positions);
+            next.extend_from_slice(&[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = next;
+    }
+
+    let spherical_uv = |p: &Vec3| -> Vec2 {
+        let u = p.z.atan2(p.x) / (2.0 * PI);
+        Vec2::new(if u < 0.0 { u + 1.0 } else { u }, p.y.clamp(-1.0, 1.0).acos() / PI)
+    };
+
+    let mut vertices: Vec<Vertex> = positions
+        .iter()
+        .map(|p| Vertex::new(p * radius, *p, spherical_uv(p)))
+        .collect();
+    let mut indices = Vec::with_capacity(faces.len() * 3);
+
+    for face in faces {
+        let mut corners = face;
+        let is_pole = |vertex: &Vertex| vertex.normal.y.abs() > 0.9999;
+        let us: Vec<f32> = corners
+            .iter()
+            .map(|&i| &vertices[i as usize])
+            .filter(|v| !is_pole(v))
+            .map(|v| v.tex_coords.x)
+            .collect();
+
+        // Triángulo que atraviesa la costura: los vértices del lado u ~ 0 pasan a u + 1
+        if us.iter().cloned().fold(f32::MIN, f32::max) - us.iter().cloned().fold(f32::MAX, f32::min) > 0.5 {
+            for corner in corners.iter_mut() {
+                let vertex = &vertices[*corner as usize];
+                if !is_pole(vertex) && vertex.tex_coords.x < 0.5 {
+                    let mut copy = vertex.clone();
+                    copy.tex_coords.x += 1.0;
+                    vertices.push(copy);
+                    *corner = vertices.len() as u32 - 1;
+                }
+            }
+        }
+
+        // Polos: u es el promedio de los otros dos vértices del triángulo
+        for k in 0..3 {
+            let vertex = &vertices[corners[k] as usize];
+            if is_pole(vertex) {
+                let u = (vertices[corners[(k + 1) % 3] as usize].tex_coords.x
+                    + vertices[corners[(k + 2) % 3] as usize].tex_coords.x) * 0.5;
+                let mut copy = vertex.clone();
+                copy.tex_coords.x = u;
+                vertices.push(copy);
+                corners[k] = vertices.len() as u32 - 1;
+            }
+        }
+
+        indices.extend_from_slice(&corners);
+    }
+
+    with_tangents(vertices, indices)
+}
+
+// Cubo centrado en el origen con normales y UV por cara
+pub fn cube(size: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let half = size * 0.5;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    // (normal, eje s) de cada cara; el eje t sale de s x normal
+    let faces = [
+        (Vec3::x(), Vec3::z()),
+        (-Vec3::x(), -Vec3::z()),
+        (Vec3::y(), Vec3::x()),
+        (-Vec3::y(), Vec3::x()),
+        (Vec3::z(), -Vec3::x()),
+        (-Vec3::z(), Vec3::x()),
+    ];
+
+    for (normal, s_axis) in faces {
+        let t_axis = s_axis.cross(&normal);
+        push_grid(&mut vertices, &mut indices, 1, 1, |s, t| {
+            let position = (normal + s_axis * (s * 2.0 - 1.0) + t_axis * (t * 2.0 - 1.0)) * half;
+            Vertex::new(position, normal, Vec2::new(t, s))
+        });
+    }
+
+    with_tangents(vertices, indices)
+}
+
+// Plano en XZ mirando hacia +Y, centrado en el origen
+pub fn plane(size: f32, subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let subdivisions = subdivisions.max(1);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    push_grid(&mut vertices, &mut indices, subdivisions, subdivisions, |s, t| {
+        Vertex::new(
+            Vec3::new((s - 0.5) * size, 0.0, (t - 0.5) * size),
+            Vec3::y(),
+            Vec2::new(s, t),
+        )
+    });
+
+    with_tangents(vertices, indices)
+}
+
+// Toro alrededor del eje Y. u recorre el círculo mayor y v el tubo.
+pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let sides = sides.max(3);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    push_grid(&mut vertices, &mut indices, sides, segments, |s, t| {
+        let (sin_phi, cos_phi) = (s * 2.0 * PI).sin_cos();
+        let (sin_theta, cos_theta) = (t * 2.0 * PI).sin_cos();
+        let around = Vec3::new(cos_theta, 0.0, sin_theta);
+        let normal = around * cos_phi - Vec3::y() * sin_phi;
+        let position = around * major_radius + normal * minor_radius;
+        Vertex::new(position, normal, Vec2::new(t, s))
+    });
+
+    with_tangents(vertices, indices)
+}
+
+// Tubo a lo largo de un arco de circunferencia en el plano XY, de 0 a `angle` radianes desde
+// +X. u recorre el arco y v el tubo; los extremos quedan abiertos.
+pub fn arc(radius: f32, thickness: f32, angle: f32, segments: u32, sides: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(1);
+    let sides = sides.max(3);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    push_grid(&mut vertices, &mut indices, sides, segments, |s, t| {
+        let (sin_phi, cos_phi) = (s * 2.0 * PI).sin_cos();
+        let (sin_theta, cos_theta) = (t * angle).sin_cos();
+        let around = Vec3::new(cos_theta, sin_theta, 0.0);
+        let normal = around * cos_phi + Vec3::z() * sin_phi;
+        Vertex::new(around * radius + normal * thickness, normal, Vec2::new(t, s))
+    });
+
+    with_tangents(vertices, indices)
+}
+
+// Cilindro a lo largo de Y con tapas, centrado en el origen
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let half = height * 0.5;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    push_grid(&mut vertices, &mut indices, 1, segments, |s, t| {
+        let (sin, cos) = (t * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(cos, 0.0, sin);
+        Vertex::new(
+            normal * radius + Vec3::new(0.0, half - s * height, 0.0),
+            normal,
+            Vec2::new(t, s),
+        )
+    });
+
+    // Tapas como abanicos alrededor del centro
+    for side in [1.0f32, -1.0] {
+        let normal = Vec3::new(0.0, side, 0.0);
+        let center = vertices.len() as u32;
+        vertices.push(Vertex::new(normal * half, normal, Vec2::new(0.5, 0.5)));
+
+        for j in 0..=segments {
+            let (sin, cos) = (j as f32 / segments as f32 * 2.0 * PI).sin_cos();
+            vertices.push(Vertex::new(
+                Vec3::new(cos * radius, side * half, sin * radius),
+                normal,
+                Vec2::new(0.5 + cos * 0.5, 0.5 + sin * 0.5),
+            ));
+        }
+
+        for j in 0..segments {
+            let (a, b) = (center + 1 + j, center + 2 + j);
+            if side > 0.0 {
+                indices.extend_from_slice(&[center, b, a]);
+            } else {
+                indices.extend_from_slice(&[center, a, b]);
+            }
+        }
+    }
+
+    with_tangents(vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Índices en rango y cada cara en sentido antihorario vista desde afuera, es decir,
+    // con su normal geométrica del mismo lado que las normales de sus vértices
+    fn assert_valid((vertices, indices): &(Vec<Vertex>, Vec<u32>)) {
+        assert!(!indices.is_empty() && indices.len() % 3 == 0);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+
+        for face in indices.chunks_exact(3) {
+            let [a, b, c] = [face[0], face[1], face[2]].map(|i| &vertices[i as usize]);
+            let geometric = (b.position - a.position).cross(&(c.position - a.position));
+            let shading = a.normal + b.normal + c.normal;
+            assert!(geometric.dot(&shading) > 0.0, "cara {:?} al revés", face);
+        }
+    }
+
+    fn assert_radial_normals((vertices, _): &(Vec<Vertex>, Vec<u32>), radius: f32) {
+        for vertex in vertices {
+            assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-5);
+            assert!((vertex.position - vertex.normal * radius).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn sphere_normals_are_unit_and_along_the_position() {
+        let uv = uv_sphere(2.0, 16, 8);
+        let ico = icosphere(2.0, 2);
+        assert_valid(&uv);
+        assert_valid(&ico);
+        assert_radial_normals(&uv, 2.0);
+        assert_radial_normals(&ico, 2.0);
+    }
+
+    #[test]
+    fn uv_sphere_seam_vertices_share_positions() {
+        let (vertices, _) = uv_sphere(1.0, 16, 8);
+        let seam: Vec<_> = vertices.iter().filter(|v| v.tex_coords.x == 0.0).collect();
+        assert_eq!(seam.len(), 7, "una fila por anillo sin contar los polos");
+
+        for start in seam {
+            let end = vertices
+                .iter()
+                .find(|v| v.tex_coords.x == 1.0 && v.tex_coords.y == start.tex_coords.y)
+                .expect("cada vértice en u = 0 tiene su copia en u = 1");
+            assert!((end.position - start.position).magnitude() < 1e-5);
+            assert!((end.normal - start.normal).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cube_has_flat_axis_aligned_faces() {
+        let cube = cube(2.0);
+        assert_valid(&cube);
+        let (vertices, indices) = &cube;
+        assert_eq!((vertices.len(), indices.len() / 3), (24, 12));
+
+        for vertex in vertices {
+            let n = vertex.normal;
+            assert_eq!(n.abs().sum(), 1.0, "normal {:?} no está sobre un eje", n);
+            // La cara queda a media arista del centro, en la dirección de su normal
+            assert!((vertex.position.dot(&n) - 1.0).abs() < 1e-6);
+            assert!(vertex.position.abs().max() <= 1.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn torus_surface_is_at_the_minor_radius() {
+        let torus = torus(1.0, 0.25, 24, 12);
+        assert_valid(&torus);
+
+        for vertex in &torus.0 {
+            let p = vertex.position;
+            let center = Vec3::new(p.x, 0.0, p.z).normalize();
+            assert!(((p - center).magnitude() - 0.25).abs() < 1e-5);
+            assert!(((p - center) / 0.25 - vertex.normal).magnitude() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn cylinder_has_closed_caps() {
+        let cylinder = cylinder(0.5, 2.0, 16);
+        assert_valid(&cylinder);
+        let (vertices, indices) = &cylinder;
+
+        for vertex in vertices {
+            assert!(vertex.position.y.abs() <= 1.0 + 1e-6);
+            if vertex.normal.y == 0.0 {
+                assert!((Vec2::new(vertex.position.x, vertex.position.z).magnitude() - 0.5).abs() < 1e-5);
+            }
+        }
+
+        // Cada tapa es un abanico de `segments` triángulos alrededor de su centro
+        let caps = indices
+            .chunks_exact(3)
+            .filter(|face| face.iter().all(|&i| vertices[i as usize].normal.y != 0.0))
+            .count();
+        assert_eq!(caps, 32);
+    }
+
+    #[test]
+    fn plane_and_ring_face_their_normals() {
+        assert_valid(&plane(2.0, 4));
+        assert_valid(&ring(1.0, 2.0, 16, 2));
+        assert_valid(&arc(1.0, 0.2, PI, 8, 6));
+    }
+}
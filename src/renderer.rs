@@ -0,0 +1,267 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::bounds::{Aabb, BoundingSphere, Frustum};
+use crate::framebuffer::{Framebuffer, BlendMode};
+use crate::vertex::Vertex;
+use crate::triangle::triangle;
+use crate::shaders::{vertex_shader, fragment_shader};
+use crate::particles::ParticleSystem;
+use crate::Uniforms;
+
+// Un objeto listo para dibujar: malla + uniforms + cómo se mezcla con lo que ya hay
+pub struct RenderObject<'a> {
+    pub uniforms: Uniforms,
+    pub vertices: &'a [Vertex],
+    pub indices: &'a [u32],
+    pub blend_mode: BlendMode,
+    pub depth_write: bool,
+    // Volúmenes envolventes en espacio de objeto; sin ellos el objeto nunca se descarta
+    pub bounds: Option<(BoundingSphere, Aabb)>,
+    // Copias de la malla en un solo dibujo (ver render_instanced); los volúmenes envolventes
+    // se prueban entonces por copia
+    pub instances: Option<&'a [Instance]>,
+    // Sistema de partículas en vez de malla; solo usa la vista y la proyección de los uniforms
+    pub particles: Option<&'a ParticleSystem>,
+}
+
+// Una copia de la malla en un dibujo instanciado: su matriz de modelo, un id que no se repite
+// entre los dibujos de una misma escena y parámetros libres y escala por eje que el shader
+// lee en `Uniforms::instance`, `instance_params` e `instance_stretch`
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub model_matrix: Mat4,
+    pub id: u32,
+    pub params: Vec4,
+    pub stretch: Vec3,
+}
+
+// Contadores del último render_scene
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenderStats {
+    pub drawn: usize,
+    pub culled: usize,
+    pub triangles: usize,
+}
+
+impl<'a> RenderObject<'a> {
+    pub fn opaque(uniforms: Uniforms, vertices: &'a [Vertex], indices: &'a [u32]) -> Self {
+        RenderObject {
+            uniforms,
+            vertices,
+            indices,
+            blend_mode: BlendMode::Opaque,
+            depth_write: true,
+            bounds: None,
+            instances: None,
+            particles: None,
+        }
+    }
+
+    // Muchas copias opacas de la misma malla, cada una con su matriz y sus parámetros
+    pub fn instanced(uniforms: Uniforms, vertices: &'a [Vertex], indices: &'a [u32], instances: &'a [Instance]) -> Self {
+        RenderObject { instances: Some(instances), ..RenderObject::opaque(uniforms, vertices, indices) }
+    }
+
+    // Los objetos transparentes no escriben profundidad por defecto para no taparse entre sí
+    pub fn transparent(uniforms: Uniforms, vertices: &'a [Vertex], indices: &'a [u32], blend_mode: BlendMode) -> Self {
+        RenderObject {
+            uniforms,
+            vertices,
+            indices,
+            blend_mode,
+            depth_write: false,
+            bounds: None,
+            instances: None,
+            particles: None,
+        }
+    }
+
+    // Partículas que se ordenan con los demás transparentes según su centro
+    pub fn particles(uniforms: Uniforms, system: &'a ParticleSystem) -> Self {
+        RenderObject { particles: Some(system), ..RenderObject::transparent(uniforms, &[], &[], system.blend_mode) }
+    }
+
+    pub fn with_bounds(mut self, sphere: BoundingSphere, aabb: Aabb) -> Self {
+        self.bounds = Some((sphere, aabb));
+        self
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.blend_mode != BlendMode::Opaque
+    }
+
+    // Profundidad del centro del objeto en espacio de vista (más negativo = más lejos)
+    fn view_depth(&self) -> f32 {
+        let world = match self.particles.and_then(ParticleSystem::center) {
+            Some(center) => center.push(1.0),
+            None => self.uniforms.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0),
+        };
+        (self.uniforms.view_matrix * world).z
+    }
+
+    // Radio en el mundo; entre capas con el mismo centro (nubes, atmósfera, anillos) la más
+    // chica queda adentro y va primero
+    fn world_radius(&self) -> f32 {
+        self.bounds.map_or(0.0, |(sphere, _)| sphere.transformed(&self.uniforms.model_matrix).radius)
+    }
+
+    // Esfera primero (barata); si la toca, la caja transformada para afinar
+    fn is_visible(&self, frustum: &Frustum) -> bool {
+        if self.instances.is_some() {
+            return true;
+        }
+        match &self.bounds {
+            Some((sphere, aabb)) => {
+                frustum.intersects_sphere(&sphere.transformed(&self.uniforms.model_matrix))
+                    && frustum.intersects_aabb(&aabb.transformed(&self.uniforms.model_matrix))
+            }
+            None => true,
+        }
+    }
+}
+
+// Primero los opacos, luego los transparentes ordenados de atrás hacia adelante (y las capas
+// con el mismo centro, de adentro hacia afuera).
+// Los objetos fuera del frustum se descartan antes de procesar sus vértices.
+pub fn render_scene(framebuffer: &mut Framebuffer, objects: &[RenderObject]) -> RenderStats {
+    let mut stats = RenderStats::default();
+
+    let visible: Vec<&RenderObject> = objects
+        .iter()
+        .filter(|object| {
+            let uniforms = &object.uniforms;
+            let frustum = Frustum::from_matrix(&(uniforms.projection_matrix * uniforms.view_matrix));
+            object.is_visible(&frustum)
+        })
+        .collect();
+    stats.culled = objects.len() - visible.len();
+
+    for object in visible.iter().filter(|o| !o.is_transparent()) {
+        draw_object(framebuffer, object, &mut stats);
+    }
+
+    let mut transparent: Vec<&RenderObject> = visible.into_iter().filter(|o| o.is_transparent()).collect();
+    transparent.sort_by(|a, b| {
+        a.view_depth().total_cmp(&b.view_depth()).then_with(|| a.world_radius().total_cmp(&b.world_radius()))
+    });
+    for object in transparent {
+        draw_object(framebuffer, object, &mut stats);
+    }
+
+    framebuffer.set_blend_mode(BlendMode::Opaque);
+    framebuffer.set_depth_write(true);
+    framebuffer.set_current_alpha(1.0);
+    framebuffer.set_current_emission(Vec3::zeros());
+
+    stats
+}
+
+fn draw_object(framebuffer: &mut Framebuffer, object: &RenderObject, stats: &mut RenderStats) {
+    framebuffer.set_blend_mode(object.blend_mode);
+    framebuffer.set_depth_write(object.depth_write);
+    if let Some(system) = object.particles {
+        stats.drawn += 1;
+        system.draw(framebuffer, &object.uniforms.view_matrix, &object.uniforms.projection_matrix);
+        return;
+    }
+    if let Some(instances) = object.instances {
+        let sphere = object.bounds.map(|(sphere, _)| sphere);
+        let drawn = render_instanced(framebuffer, &object.uniforms, object.vertices, object.indices, instances, sphere);
+        stats.drawn += drawn;
+        stats.culled += instances.len() - drawn;
+        stats.triangles += drawn * object.indices.len() / 3;
+        return;
+    }
+
+    stats.drawn += 1;
+    stats.triangles += object.indices.len() / 3;
+    render_model(framebuffer, &object.uniforms, object.vertices, object.indices);
+}
+
+pub fn render_model(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertices: &[Vertex], indices: &[u32]) {
+    let mut transformed_vertices = Vec::with_capacity(vertices.len());
+    draw_triangles(framebuffer, uniforms, vertices, indices, &mut transformed_vertices);
+}
+
+// Dibuja la misma malla una vez por copia. Los uniforms, el frustum y el buffer de vértices
+// transformados se preparan una sola vez; por copia solo cambian la matriz de modelo, el
+// índice (`Uniforms::instance`, que reciben el vertex y el fragment shader) y sus
+// parámetros. Las copias cuya esfera envolvente queda fuera de vista se saltan. Devuelve
+// cuántas se dibujaron.
+pub fn render_instanced(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    vertices: &[Vertex],
+    indices: &[u32],
+    instances: &[Instance],
+    bounds: Option<BoundingSphere>,
+) -> usize {
+    let frustum = Frustum::from_matrix(&(uniforms.projection_matrix * uniforms.view_matrix));
+    let mut instance_uniforms = uniforms.clone();
+    let mut transformed_vertices = Vec::with_capacity(vertices.len());
+    let mut drawn = 0;
+
+    for instance in instances {
+        if let Some(sphere) = &bounds {
+            if !frustum.intersects_sphere(&sphere.transformed(&instance.model_matrix)) {
+                continue;
+            }
+        }
+
+        instance_uniforms.model_matrix = instance.model_matrix;
+        instance_uniforms.instance = instance.id;
+        instance_uniforms.instance_params = instance.params;
+        instance_uniforms.instance_stretch = instance.stretch;
+        draw_triangles(framebuffer, &instance_uniforms, vertices, indices, &mut transformed_vertices);
+        drawn += 1;
+    }
+
+    drawn
+}
+
+// Vertex shader sobre todos los vértices (en `transformed_vertices`, que se reutiliza) y
+// rasterizado de los triángulos
+fn draw_triangles(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertices: &[Vertex], indices: &[u32], transformed_vertices: &mut Vec<Vertex>) {
+    transformed_vertices.clear();
+    for vertex in vertices {
+        transformed_vertices.push(vertex_shader(vertex, uniforms));
+    }
+
+    // Process triangles with early culling
+    for i in (0..indices.len()).step_by(3) {
+        let v1 = &transformed_vertices[indices[i] as usize];
+        let v2 = &transformed_vertices[indices[i+1] as usize];
+        let v3 = &transformed_vertices[indices[i+2] as usize];
+
+        // Early clip space culling - skip triangles completely outside view
+        let clip_coords = [v1.transformed_position, v2.transformed_position, v3.transformed_position];
+        if clip_coords.iter().all(|v| v.x.abs() > v.w.abs() * 1.5 || v.y.abs() > v.w.abs() * 1.5 || v.z < -v.w || v.z > v.w) {
+            continue;
+        }
+
+        let fragments = triangle(v1, v2, v3, uniforms);
+        for fragment in fragments {
+            let x = fragment.position.x as usize;
+            let y = fragment.position.y as usize;
+
+            if x < framebuffer.width && y < framebuffer.height {
+                let output = fragment_shader(&fragment, uniforms);
+                let color_vec = output.color;
+
+                // Fragmentos totalmente transparentes no tocan el buffer
+                if color_vec.w <= 0.0 {
+                    continue;
+                }
+
+                let r = (color_vec.x * 255.0).clamp(0.0, 255.0) as u32;
+                let g = (color_vec.y * 255.0).clamp(0.0, 255.0) as u32;
+                let b = (color_vec.z * 255.0).clamp(0.0, 255.0) as u32;
+                let color = (r << 16) | (g << 8) | b;
+
+                framebuffer.set_current_color(color);
+                framebuffer.set_current_alpha(color_vec.w);
+                framebuffer.set_current_emission(output.emission);
+                framebuffer.point(x, y, fragment.depth);
+            }
+        }
+    }
+}
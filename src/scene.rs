@@ -0,0 +1,274 @@
+use nalgebra_glm::{Vec3, Mat4};
+use std::f32::consts::PI;
+use std::rc::Rc;
+use crate::planet::PlanetParams;
+use crate::noise::Noise;
+use crate::shaders::{default_planet, Atmosphere, Clouds, Corona, ProceduralSurface, Prominence, Rings};
+use crate::star::StarClass;
+use crate::bake::BakedSurface;
+use crate::belt::AsteroidBelt;
+use crate::create_model_matrix;
+
+// Órbita circular horizontal: radio, velocidad angular, ángulo inicial y altura sobre el plano
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Orbit {
+    pub radius: f32,
+    pub speed: f32,
+    pub phase: f32,
+    pub height: f32,
+}
+
+impl Orbit {
+    pub fn new(radius: f32, speed: f32, phase: f32, height: f32) -> Self {
+        Orbit { radius, speed, phase, height }
+    }
+
+    // Posición relativa al centro de la órbita
+    pub fn position(&self, time: f32) -> Vec3 {
+        let angle = self.phase + time * self.speed;
+        Vec3::new(angle.cos() * self.radius, self.height, angle.sin() * self.radius)
+    }
+}
+
+// Un cuerpo celeste en órbita circular alrededor del sol (en el origen) o, si es una luna,
+// alrededor de un planeta
+pub struct CelestialBody {
+    pub name: String,
+    pub orbit: Orbit,
+    // Órbita del planeta alrededor del que gira una luna
+    pub primary: Option<Orbit>,
+    pub scale: f32,
+    pub spin_speed: f32,
+    pub shader_type: u32,
+    // Semilla del ruido de los shaders: dos cuerpos del mismo tipo no se ven iguales
+    pub seed: u32,
+    // Paleta y parámetros de la superficie (ver planet.rs)
+    pub planet: PlanetParams,
+    pub atmosphere: Option<Atmosphere>,
+    pub clouds: Option<Clouds>,
+    pub rings: Option<Rings>,
+    // Solo las estrellas: su clase espectral (ver star.rs) y su corona
+    pub star: Option<StarClass>,
+    pub corona: Option<Corona>,
+    // Solo los cometas: suelta una cola de partículas (ver particles::comet_tail)
+    pub comet: bool,
+    // Nivel de detalle elegido en el cuadro anterior (para la histéresis)
+    pub lod: usize,
+    // Albedo y emisión horneados (ver bake.rs), para no evaluar el shader procedural
+    pub baked: Option<Rc<BakedSurface>>,
+}
+
+impl CelestialBody {
+    pub fn new(name: &str, shader_type: u32, scale: f32) -> Self {
+        let seed = name_seed(name);
+        CelestialBody {
+            name: name.to_string(),
+            orbit: Orbit::default(),
+            primary: None,
+            scale,
+            spin_speed: 0.0,
+            shader_type,
+            seed,
+            planet: default_planet(shader_type, seed),
+            atmosphere: None,
+            clouds: None,
+            rings: None,
+            star: None,
+            corona: None,
+            comet: false,
+            lod: 0,
+            baked: None,
+        }
+    }
+
+    pub fn with_orbit(mut self, radius: f32, speed: f32, phase: f32, height: f32) -> Self {
+        self.orbit = Orbit::new(radius, speed, phase, height);
+        self
+    }
+
+    // Convierte el cuerpo en una luna de `primary`; su órbita pasa a ser relativa al planeta
+    pub fn with_primary(mut self, primary: &CelestialBody) -> Self {
+        self.primary = Some(primary.orbit);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // Cambia también el shader al de la clase del planeta
+    pub fn with_planet(mut self, planet: PlanetParams) -> Self {
+        if let Some(class) = planet.class {
+            self.shader_type = class.shader_type();
+        }
+        self.planet = planet;
+        self
+    }
+
+    // Convierte el cuerpo en una estrella de la clase dada, con su paleta y su luminosidad
+    pub fn with_star(mut self, star: StarClass) -> Self {
+        self.shader_type = 0;
+        self.planet = star.surface();
+        self.star = Some(star);
+        self
+    }
+
+    pub fn surface(&self) -> ProceduralSurface {
+        ProceduralSurface::new(self.shader_type, self.seed).with_planet(self.planet)
+    }
+
+    pub fn is_moon(&self) -> bool {
+        self.primary.is_some()
+    }
+
+    pub fn with_spin(mut self, spin_speed: f32) -> Self {
+        self.spin_speed = spin_speed;
+        self
+    }
+
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    pub fn with_clouds(mut self, clouds: Clouds) -> Self {
+        self.clouds = Some(clouds);
+        self
+    }
+
+    pub fn with_rings(mut self, rings: Rings) -> Self {
+        self.rings = Some(rings);
+        self
+    }
+
+    pub fn with_corona(mut self, corona: Corona) -> Self {
+        self.corona = Some(corona);
+        self
+    }
+
+    pub fn with_comet(mut self) -> Self {
+        self.comet = true;
+        self
+    }
+
+    pub fn position(&self, time: f32) -> Vec3 {
+        let center = self.primary.map(|primary| primary.position(time)).unwrap_or_default();
+        center + self.orbit.position(time)
+    }
+
+    pub fn rotation(&self, time: f32) -> Vec3 {
+        Vec3::new(0.0, time * self.spin_speed, 0.0)
+    }
+
+    pub fn model_matrix(&self, time: f32) -> Mat4 {
+        create_model_matrix(self.position(time), self.scale, self.rotation(time))
+    }
+
+    // Matriz de la capa atmosférica, un poco más grande que el planeta
+    pub fn atmosphere_matrix(&self, time: f32) -> Option<Mat4> {
+        self.atmosphere.map(|atmosphere| {
+            create_model_matrix(self.position(time), self.scale * atmosphere.shell_scale(), self.rotation(time))
+        })
+    }
+
+    // La capa de nubes gira alrededor del mismo eje que el planeta, pero a su propia velocidad
+    pub fn clouds_matrix(&self, time: f32) -> Option<Mat4> {
+        self.clouds.map(|clouds| {
+            create_model_matrix(self.position(time), self.scale * clouds.shell_scale(), Vec3::new(0.0, time * clouds.spin, 0.0))
+        })
+    }
+
+    // Billboard de la corona: el plano XZ de primitives::plane de lado 2, girado para que su
+    // normal apunte a la cámara y escalado a `extent` radios
+    pub fn corona_matrix(&self, time: f32, camera_position: Vec3) -> Option<Mat4> {
+        self.corona.map(|corona| {
+            let center = self.position(time);
+            let to_camera = (camera_position - center).try_normalize(1e-6).unwrap_or_else(Vec3::z);
+            let up = if to_camera.y.abs() < 0.99 { Vec3::y() } else { Vec3::z() };
+            let size = self.scale * corona.extent;
+            let right = to_camera.cross(&up).normalize();
+            let x = right * size;
+            let y = to_camera * size;
+            let z = right.cross(&to_camera) * size;
+            Mat4::new(
+                x.x, y.x, z.x, center.x,
+                x.y, y.y, z.y, center.y,
+                x.z, y.z, z.z, center.z,
+                0.0, 0.0, 0.0, 1.0,
+            )
+        })
+    }
+
+    // Protuberancias activas con la matriz de cada arco
+    pub fn prominences(&self, time: f32) -> Vec<(Prominence, Mat4)> {
+        let Some(corona) = self.corona else { return Vec::new() };
+        let center = self.position(time);
+        corona
+            .prominences(&Noise::new(self.seed), time)
+            .into_iter()
+            .map(|prominence| (prominence, prominence.matrix(center, self.scale)))
+            .collect()
+    }
+
+    // Los anillos siguen al planeta pero con su propia inclinación, sin girar con él
+    pub fn rings_matrix(&self, time: f32) -> Option<Mat4> {
+        self.rings.map(|rings| create_model_matrix(self.position(time), self.scale, rings.tilt))
+    }
+}
+
+// Semilla por defecto a partir del nombre (FNV-1a), estable entre ejecuciones
+fn name_seed(name: &str) -> u32 {
+    name.bytes().fold(0x811C_9DC5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+// Lo que muestra el renderer: los cuerpos (el primero es la estrella, en el origen), un
+// cinturón de asteroides opcional y desde dónde empieza la cámara
+pub struct Scene {
+    pub name: String,
+    pub bodies: Vec<CelestialBody>,
+    pub belt: Option<AsteroidBelt>,
+    pub camera_position: Vec3,
+}
+
+// El sistema solar de la práctica: sol fijo, cinco planetas, un cometa y un cinturón de
+// asteroides
+pub fn solar_system() -> Scene {
+    let bodies = vec![
+        // Sol: centro, sin rotación y mucho más grande
+        CelestialBody::new("Sol", 0, 5.0).with_star(StarClass::G).with_corona(Corona::new(2.2, 0.7, 3)),
+        CelestialBody::new("Rocoso", 1, 0.8)
+            .with_orbit(8.0, 0.3, 0.0, 0.0)
+            .with_spin(0.5)
+            .with_atmosphere(Atmosphere::new(Vec3::new(0.35, 0.6, 1.0), 0.12, 1.2))
+            .with_clouds(Clouds::new(0.08, 0.65)),
+        // El gigante gaseoso orbita en sentido contrario
+        CelestialBody::new("Gaseoso", 2, 1.2)
+            .with_orbit(12.0, -0.15, PI, 0.5)
+            .with_spin(0.3)
+            .with_rings(Rings::new(1.35, 2.4, Vec3::new(0.45, 0.0, 0.2))),
+        CelestialBody::new("Helado", 4, 0.7)
+            .with_orbit(10.0, 0.25, PI * 0.5, -0.3)
+            .with_spin(0.4),
+        CelestialBody::new("Desierto", 5, 0.6)
+            .with_orbit(6.5, 0.35, PI, 0.2)
+            .with_spin(0.6)
+            .with_atmosphere(Atmosphere::new(Vec3::new(1.0, 0.6, 0.25), 0.08, 0.7)),
+        CelestialBody::new("Volcanico", 6, 0.9)
+            .with_orbit(14.0, 0.4, PI * 1.5, -0.5)
+            .with_spin(0.7),
+        // Cometa helado en una órbita lejana e inclinada, con la cola opuesta al sol
+        CelestialBody::new("Cometa", 4, 0.2)
+            .with_orbit(18.0, 0.2, PI * 0.25, 2.0)
+            .with_spin(1.0)
+            .with_comet(),
+    ];
+
+    Scene {
+        name: "Sistema Solar".to_string(),
+        bodies,
+        // Entre la órbita del volcánico y la del cometa
+        belt: Some(AsteroidBelt::new(15.2, 16.8, 1200, 0xA57E)),
+        camera_position: Vec3::new(0.0, 4.0, 15.0),
+    }
+}
@@ -1,12 +1,29 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3};
+use nalgebra_glm::{Vec3, Vec4, Mat3, Mat4, rotation};
+use std::f32::consts::PI;
 use crate::vertex::Vertex;
+use crate::fragment::Fragment;
 use crate::Uniforms;
+use crate::bake::equirectangular_uv;
+use crate::noise::{Noise, Rng};
+use crate::planet::{PlanetClass, PlanetParams};
+use crate::star::StarClass;
+use crate::tangents::generate_tangents;
+
+// Altura máxima del relieve, en radios del planeta
+pub const MAX_DISPLACEMENT: f32 = 0.06;
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
-    let position = Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
+    // El relieve de los planetas ya viene en la malla (ver displace_mesh)
+    let (displaced, normal) = if uniforms.shader_type == 12 {
+        asteroid_stretch(vertex, uniforms)
+    } else {
+        (vertex.position, vertex.normal)
+    };
+    let position = Vec4::new(displaced.x, displaced.y, displaced.z, 1.0);
     
     // Aplicar transformación completa: Model -> View -> Projection
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
+    let world = uniforms.model_matrix * position;
+    let transformed = uniforms.projection_matrix * uniforms.view_matrix * world;
 
     // La normal se transforma solo con la matriz del modelo
     let model_mat3 = Mat3::from_columns(&[
@@ -15,222 +32,1146 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         uniforms.model_matrix.column(2).xyz(),
     ]);
     let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or_else(Mat3::identity);
-    let transformed_normal = (normal_matrix * vertex.normal).normalize();
+    let transformed_normal = (normal_matrix * normal).normalize();
+
+    // La tangente sigue a la superficie, así que usa la matriz del modelo directamente
+    let tangent = model_mat3 * vertex.tangent.xyz();
+    let tangent = if tangent.magnitude() > 0.0 { tangent.normalize() } else { tangent };
 
     Vertex {
-        position: vertex.position,
-        normal: vertex.normal,
+        position: displaced,
+        normal,
         tex_coords: vertex.tex_coords,
         color: vertex.color,
+        tangent: vertex.tangent,
         transformed_position: transformed,
         transformed_normal,
+        transformed_tangent: Vec4::new(tangent.x, tangent.y, tangent.z, vertex.tangent.w),
+        world_position: world.xyz(),
+    }
+}
+
+// Shaders cuyos vértices se desplazan con el relieve: el rocoso y el volcánico
+fn has_terrain(shader_type: u32) -> bool {
+    matches!(shader_type, 1 | 6)
+}
+
+// Relieve en [0, 1] en la dirección unitaria p, a partir del mismo ruido que el color.
+// El océano y la lava quedan a nivel 0.
+fn terrain_height(p: Vec3, shader_type: u32, noise: &Noise, planet: &PlanetParams) -> f32 {
+    match shader_type {
+        1 => {
+            let elevation = rocky_elevation(p, noise, planet);
+            ((elevation - planet.ocean_level) / (1.0 - planet.ocean_level)).max(0.0)
+        }
+        6 => {
+            // La roca se eleva sobre las coladas de lava, que ocupan las zonas bajas
+            let threshold = 1.0 - planet.lava_amount;
+            let n = volcanic_elevation(p, noise, planet);
+            ((threshold - n) / threshold.max(1e-3)).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+// Malla con el relieve de un cuerpo: cada vértice se desplaza a lo largo de la normal según
+// la elevación, la normal se recalcula con diferencias finitas sobre dos vecinos en el plano
+// tangente y las tangentes se regeneran sobre la superficie desplazada. Se calcula una vez por
+// nivel de detalle en vez de en cada cuadro. None si el shader no tiene relieve.
+pub fn displace_mesh(vertices: &[Vertex], indices: &[u32], shader_type: u32, noise: &Noise, planet: &PlanetParams) -> Option<(Vec<Vertex>, Vec<u32>)> {
+    if !has_terrain(shader_type) {
+        return None;
+    }
+
+    let mut displaced = vertices.to_vec();
+    for vertex in displaced.iter_mut() {
+        let radius = vertex.position.magnitude();
+        if radius < 1e-6 {
+            continue;
+        }
+
+        let surface = |direction: Vec3| {
+            let direction = direction.normalize();
+            direction * radius * (1.0 + terrain_height(direction, shader_type, noise, planet) * MAX_DISPLACEMENT)
+        };
+
+        let n = vertex.position / radius;
+        let axis = if n.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        let t = n.cross(&axis).normalize();
+        let b = n.cross(&t);
+        let epsilon = 0.005;
+
+        let center = surface(n);
+        let normal = (surface(n + t * epsilon) - center).cross(&(surface(n + b * epsilon) - center));
+        let normal = if normal.dot(&n) < 0.0 { -normal } else { normal };
+        vertex.position = center;
+        vertex.normal = normal.normalize();
+    }
+
+    let mut indices = indices.to_vec();
+    generate_tangents(&mut displaced, &mut indices);
+    Some((displaced, indices))
+}
+
+// Cada asteroide del cinturón se estira distinto a lo largo de sus ejes según su índice de
+// instancia, así unas pocas mallas dan rocas de formas variadas. La normal se divide por el
+// mismo factor para seguir siendo perpendicular a la superficie.
+fn asteroid_stretch(vertex: &Vertex, uniforms: &Uniforms) -> (Vec3, Vec3) {
+    let stretch = uniforms.instance_stretch;
+    (vertex.position.component_mul(&stretch), vertex.normal.component_div(&stretch).normalize())
+}
+
+// Parámetros de la atmósfera de un planeta; thickness es relativo al radio del planeta
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    pub color: Vec3,
+    pub thickness: f32,
+    pub density: f32,
+}
+
+impl Atmosphere {
+    pub fn new(color: Vec3, thickness: f32, density: f32) -> Self {
+        Atmosphere { color, thickness, density }
+    }
+
+    // Escala de la capa exterior respecto al planeta
+    pub fn shell_scale(&self) -> f32 {
+        1.0 + self.thickness
+    }
+}
+
+// Capa de nubes sobre el planeta: altura sobre la superficie (relativa al radio, por encima
+// de las montañas) y velocidad de giro propia. La fracción del cielo cubierta es la
+// cloud_cover del planeta.
+#[derive(Debug, Clone, Copy)]
+pub struct Clouds {
+    pub altitude: f32,
+    pub spin: f32,
+}
+
+impl Clouds {
+    pub fn new(altitude: f32, spin: f32) -> Self {
+        Clouds { altitude, spin }
+    }
+
+    pub fn shell_scale(&self) -> f32 {
+        1.0 + self.altitude
+    }
+}
+
+// Sistema de anillos; los radios están en radios del planeta y la inclinación
+// usa los mismos ángulos de Euler que create_model_matrix
+#[derive(Debug, Clone, Copy)]
+pub struct Rings {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub tilt: Vec3,
+}
+
+impl Rings {
+    pub fn new(inner_radius: f32, outer_radius: f32, tilt: Vec3) -> Self {
+        Rings { inner_radius, outer_radius, tilt }
+    }
+
+    // Normal del plano de los anillos en espacio de mundo
+    pub fn normal(&self) -> Vec3 {
+        let tilt_matrix = rotation(self.tilt.z, &Vec3::z())
+            * rotation(self.tilt.y, &Vec3::y())
+            * rotation(self.tilt.x, &Vec3::x());
+        (tilt_matrix * Vec4::new(0.0, 1.0, 0.0, 0.0)).xyz().normalize()
+    }
+}
+
+// Corona de una estrella: un billboard aditivo de `extent` radios de la estrella que mira a
+// la cámara, con rayos (streamers) animados de intensidad `streamers`. Hasta `prominences`
+// protuberancias aparecen sobre la superficie de vez en cuando.
+#[derive(Debug, Clone, Copy)]
+pub struct Corona {
+    pub extent: f32,
+    pub streamers: f32,
+    pub prominences: u32,
+}
+
+impl Corona {
+    pub fn new(extent: f32, streamers: f32, prominences: u32) -> Self {
+        Corona { extent: extent.max(1.0), streamers, prominences }
+    }
+
+    // Protuberancias activas en el instante `time`. Cada una tiene su propio ciclo: está
+    // encendida durante una parte y en cada ciclo sale en otro lugar de la superficie.
+    pub fn prominences(&self, noise: &Noise, time: f32) -> Vec<Prominence> {
+        (0..self.prominences)
+            .filter_map(|slot| {
+                let layer = noise.derive(70 + slot);
+                let mut rng = Rng::new(layer);
+                let period = rng.range(4.0, 8.0);
+                let cycle_time = time / period + rng.next();
+                let life = cycle_time.fract() / PROMINENCE_ACTIVE;
+                if life >= 1.0 {
+                    return None;
+                }
+
+                // Lugar y tamaño del arco en este ciclo
+                let mut rng = Rng::new(layer.derive(cycle_time.floor() as u32));
+                let normal = Vec3::new(rng.range(-1.0, 1.0), rng.range(-0.7, 0.7), rng.range(-1.0, 1.0));
+                let normal = if normal.magnitude() > 1e-3 { normal.normalize() } else { Vec3::x() };
+                let axis = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+                let angle = rng.range(0.0, 2.0 * PI);
+                let t = normal.cross(&axis).normalize();
+                let b = normal.cross(&t);
+                let tangent = t * angle.cos() + b * angle.sin();
+                Some(Prominence { normal, tangent, size: rng.range(0.12, 0.25), life, seed: rng.next_u32() })
+            })
+            .collect()
+    }
+}
+
+// Fracción de su ciclo en que una protuberancia está encendida
+const PROMINENCE_ACTIVE: f32 = 0.6;
+
+// Protuberancia: arco de plasma con los pies sobre la superficie de la estrella. `normal` es
+// la dirección del punto medio entre los pies, `tangent` la recta que los une, `size` el
+// radio del arco en radios de la estrella y `life` en [0, 1] cuánto de su vida lleva.
+#[derive(Debug, Clone, Copy)]
+pub struct Prominence {
+    pub normal: Vec3,
+    pub tangent: Vec3,
+    pub size: f32,
+    pub life: f32,
+    pub seed: u32,
+}
+
+impl Prominence {
+    // Aparece, se mantiene y se desvanece
+    pub fn fade(&self) -> f32 {
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        smooth((self.life / 0.2).clamp(0.0, 1.0)) * (1.0 - smooth(((self.life - 0.7) / 0.3).clamp(0.0, 1.0)))
+    }
+
+    // Matriz del arco unitario de primitives::arc (semicírculo en XY) sobre una estrella de
+    // centro `center` y radio `radius`; el arco crece mientras la protuberancia se forma
+    pub fn matrix(&self, center: Vec3, radius: f32) -> Mat4 {
+        let size = self.size * radius;
+        let growth = 0.4 + 0.6 * (self.life / 0.5).min(1.0);
+        // Los pies quedan justo sobre la esfera
+        let base = center + self.normal * (radius * radius - size * size).max(0.0).sqrt();
+        let x = self.tangent * size;
+        let y = self.normal * size * growth;
+        let z = self.tangent.cross(&self.normal) * size;
+        Mat4::new(
+            x.x, y.x, z.x, base.x,
+            x.y, y.y, z.y, base.y,
+            x.z, y.z, z.z, base.z,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+}
+
+// Centro y radio en mundo de una esfera unitaria transformada por la matriz de modelo
+fn sphere_bounds(model_matrix: &Mat4) -> (Vec3, f32) {
+    (model_matrix.column(3).xyz(), model_matrix.column(0).xyz().magnitude())
+}
+
+// Salida de fragment_shader: el color final con alpha y la parte de ese color que emite
+// la propia superficie, que no depende de la luz y alimenta el bloom (ver post.rs)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FragmentOutput {
+    pub color: Vec4,
+    pub emission: Vec3,
+}
+
+// Los shaders transparentes no emiten luz
+impl From<Vec4> for FragmentOutput {
+    fn from(color: Vec4) -> Self {
+        FragmentOutput { color, emission: Vec3::zeros() }
     }
 }
 
-// Funciones de ruido procedural
-fn noise(p: Vec3) -> f32 {
-    let i = p.map(|x| x.floor());
-    let f = p.map(|x| x.fract());
-    let u = f.component_mul(&f).map(|x| x * (3.0 - 2.0 * x));
+// Selecciona el shader según el tipo de objeto
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> FragmentOutput {
+    // Con texturas horneadas (ver bake.rs) el material se lee de ellas en vez de evaluar el ruido
+    let base = |shade: ProceduralFn| match &uniforms.baked {
+        Some(baked) => baked.sample(equirectangular_uv(&fragment.vertex_position)),
+        None => shade(fragment.vertex_position, uniforms.time, &uniforms.noise, &uniforms.planet),
+    };
+    let normal = fragment.normal.normalize();
+
+    // Material, normal para la luz y sombra sobre la luz directa
+    let (material, normal, shadow) = match uniforms.shader_type {
+        0 => {
+            let material = base(shade_star);
+            (material.with_emission(material.emission.component_mul(&limb_darkening(fragment, uniforms))), normal, 1.0)
+        }
+        1 => {
+            // Las luces de las ciudades solo se ven de noche
+            let material = base(shade_rocky);
+            let material = material.with_emission(material.emission * night_side(fragment, uniforms));
+            (material, crater_normal(fragment, uniforms), cloud_shadow(fragment, uniforms))
+        }
+        2 => (base(shade_gas_giant), normal, ring_shadow(fragment, uniforms)),
+        // Un modelo cargado trae sus materiales del MTL o del glTF
+        3 => {
+            let material = match (uniforms.material, &uniforms.maps) {
+                (Some(material), Some(maps)) => maps.sample(material, fragment.tex_coords),
+                (Some(material), None) => material,
+                (None, _) => base(shade_spaceship),
+            };
+            (material, panel_normal(fragment, &uniforms.noise), 1.0)
+        }
+        4 => (base(shade_ice_planet), normal, 1.0),
+        5 => (base(shade_desert_planet), normal, 1.0),
+        6 => (base(shade_volcanic_planet), normal, 1.0),
+        7 => return shade_atmosphere(fragment, uniforms).into(),
+        8 => return shade_rings(fragment, uniforms).into(),
+        9 => return shade_clouds(fragment, uniforms).into(),
+        10 => return shade_corona(fragment, uniforms).into(),
+        11 => return shade_prominence(fragment, uniforms),
+        12 => (shade_asteroid(fragment, uniforms), normal, 1.0),
+        _ => (SurfaceMaterial::diffuse(Vec3::new(0.5, 0.5, 0.5)), normal, 1.0), // Gris por defecto
+    };
+
+    // La luz solo afecta a la parte reflejada; la emisión se suma tal cual
+    let color = surface_light(&material, &normal, fragment, uniforms, shadow) + material.emission;
+    FragmentOutput { color: Vec4::new(color.x, color.y, color.z, 1.0), emission: material.emission }
+}
+
+// Material de una superficie: cómo refleja la luz (albedo, rugosidad y cuánto es metal) y
+// la luz que emite por sí misma (la estrella, la lava, las ciudades)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceMaterial {
+    pub albedo: Vec3,
+    // 0 = espejo, 1 = mate
+    pub roughness: f32,
+    // 0 = dieléctrico (roca, agua, hielo), 1 = metal
+    pub metallic: f32,
+    pub emission: Vec3,
+}
+
+impl SurfaceMaterial {
+    // Superficie mate que solo refleja luz
+    pub fn diffuse(albedo: Vec3) -> Self {
+        SurfaceMaterial { albedo, roughness: 1.0, metallic: 0.0, emission: Vec3::zeros() }
+    }
+
+    // Superficie que solo emite luz
+    pub fn emissive(emission: Vec3) -> Self {
+        SurfaceMaterial { emission, ..SurfaceMaterial::diffuse(Vec3::zeros()) }
+    }
 
-    let mix = |a, b, t| a + t * (b - a);
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
 
-    mix(
-        mix(
-            mix(rand(i + Vec3::new(0.0, 0.0, 0.0)), rand(i + Vec3::new(1.0, 0.0, 0.0)), u.x),
-            mix(rand(i + Vec3::new(0.0, 1.0, 0.0)), rand(i + Vec3::new(1.0, 1.0, 0.0)), u.x),
-            u.y,
-        ),
-        mix(
-            mix(rand(i + Vec3::new(0.0, 0.0, 1.0)), rand(i + Vec3::new(1.0, 0.0, 1.0)), u.x),
-            mix(rand(i + Vec3::new(0.0, 1.0, 1.0)), rand(i + Vec3::new(1.0, 1.0, 1.0)), u.x),
-            u.y,
-        ),
-        u.z,
-    )
+    pub fn with_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Vec3) -> Self {
+        self.emission = emission;
+        self
+    }
 }
 
-fn rand(p: Vec3) -> f32 {
-    (p.dot(&Vec3::new(12.9898, 78.233, 45.5432)).sin() * 43758.5453).fract()
+// Shader de material: punto de la esfera, tiempo, ruido con la semilla del cuerpo y
+// parámetros del planeta
+pub type ProceduralFn = fn(Vec3, f32, &Noise, &PlanetParams) -> SurfaceMaterial;
+
+// Superficie procedural de un cuerpo: qué shader, con qué semilla y con qué parámetros
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProceduralSurface {
+    pub shader_type: u32,
+    pub seed: u32,
+    pub planet: PlanetParams,
 }
 
-fn fbm(p: Vec3, octaves: i32, persistence: f32, lacunarity: f32) -> f32 {
-    let mut total = 0.0;
-    let mut frequency = 1.0;
-    let mut amplitude = 1.0;
-    let mut max_value = 0.0;
+impl ProceduralSurface {
+    pub fn new(shader_type: u32, seed: u32) -> Self {
+        ProceduralSurface { shader_type, seed, planet: default_planet(shader_type, seed) }
+    }
 
-    for _ in 0..octaves {
-        total += noise(p * frequency) * amplitude;
-        max_value += amplitude;
-        amplitude *= persistence;
-        frequency *= lacunarity;
+    pub fn with_planet(mut self, planet: PlanetParams) -> Self {
+        self.planet = planet;
+        self
     }
+}
 
-    total / max_value
+// Parámetros con los que se diseñó cada shader procedural: la estrella es de clase G y la
+// nave usa los del planeta rocoso
+pub fn default_planet(shader_type: u32, seed: u32) -> PlanetParams {
+    match PlanetClass::from_shader_type(shader_type) {
+        Some(class) => PlanetParams::new(class, seed),
+        None if shader_type == 0 => StarClass::G.surface(),
+        None => PlanetParams::new(PlanetClass::Rocky, seed),
+    }
+}
+
+// Material de los shaders que solo dependen del punto de la esfera, del tiempo, de
+// la semilla y de los parámetros del planeta (sin iluminación), para hornearlos a texturas
+pub fn procedural_shader(shader_type: u32) -> Option<ProceduralFn> {
+    match shader_type {
+        0 => Some(shade_star),
+        1 => Some(shade_rocky),
+        2 => Some(shade_gas_giant),
+        3 => Some(shade_spaceship),
+        4 => Some(shade_ice_planet),
+        5 => Some(shade_desert_planet),
+        6 => Some(shade_volcanic_planet),
+        _ => None,
+    }
+}
+
+// Gradiente del relieve fino que fragment_shader suma a la normal, en el espacio del objeto
+pub type BumpFn = fn(Vec3, &Noise) -> Vec3;
+
+// Relieve fino de los shaders que lo tienen; solo depende de la dirección y de la semilla,
+// así que se hornea junto con el material
+pub fn procedural_bump(shader_type: u32) -> Option<BumpFn> {
+    match shader_type {
+        1 => Some(crater_gradient),
+        _ => None,
+    }
+}
+
+// Lleva un ruido con signo (ver noise.rs) a [0, 1] centrado en 0.5, con un contraste
+// parecido al del ruido de valor que usaban antes los shaders
+fn unit(n: f32) -> f32 {
+    (n * 0.85 + 0.5).clamp(0.0, 1.0)
+}
+
+// Mapas de normales
+
+// Lleva una normal del espacio tangente (x = +u, y = +v, z = afuera) a mundo
+pub fn tangent_to_world(fragment: &Fragment, tangent_normal: Vec3) -> Vec3 {
+    let normal = fragment.normal;
+    let tangent = fragment.tangent.xyz();
+    let mut t = tangent - normal * normal.dot(&tangent);
+    if t.magnitude() < 1e-6 {
+        // Sin tangente: solo importa que sea perpendicular
+        let axis = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        t = axis - normal * normal.dot(&axis);
+    }
+    let t = t.normalize();
+    let sign = if fragment.tangent.w < 0.0 { -1.0 } else { 1.0 };
+    let b = normal.cross(&t) * sign;
+
+    (t * tangent_normal.x + b * tangent_normal.y + normal * tangent_normal.z).normalize()
+}
+
+// Inclina una normal unitaria según el gradiente de una altura sobre la superficie
+fn bump(normal: Vec3, gradient: Vec3) -> Vec3 {
+    (normal - (gradient - normal * gradient.dot(&normal))).normalize()
+}
+
+// Luz del sol sobre un material, con un mínimo ambiental. El especular es el de
+// Cook-Torrance con distribución GGX, geometría de Smith-Schlick y Fresnel de Schlick: con
+// poca rugosidad el sol deja un reflejo pequeño e intenso (el océano), con mucha se reparte
+// en un brillo suave (el hielo) o desaparece (la roca). El difuso se lleva lo que no refleja
+// Fresnel y los metales no tienen. `shadow` atenúa solo la luz directa, igual que la sombra
+// de los demás objetos.
+fn surface_light(material: &SurfaceMaterial, normal: &Vec3, fragment: &Fragment, uniforms: &Uniforms, shadow: f32) -> Vec3 {
+    let ambient = 0.2;
+    let ambient_light = material.albedo * ambient;
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let n_dot_l = normal.dot(&light_dir);
+    if n_dot_l <= 0.0 || shadow <= 0.0 {
+        return ambient_light;
+    }
+    let shadow = shadow * sun_visibility(fragment, uniforms);
+    if shadow <= 0.0 {
+        return ambient_light;
+    }
+
+    let view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    let half = (light_dir + view_dir).normalize();
+    let n_dot_v = normal.dot(&view_dir).max(1e-3);
+    let n_dot_h = normal.dot(&half).max(0.0);
+    let v_dot_h = view_dir.dot(&half).max(0.0);
+
+    let alpha = (material.roughness * material.roughness).max(2e-3);
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    let distribution = a2 / (PI * d * d);
+    let k = (material.roughness + 1.0).powi(2) / 8.0;
+    let geometry = n_dot_l / (n_dot_l * (1.0 - k) + k) * n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let f0 = Vec3::repeat(0.04).lerp(&material.albedo, material.metallic);
+    let fresnel = f0 + (Vec3::repeat(1.0) - f0) * (1.0 - v_dot_h).powi(5);
+
+    let specular = fresnel * (distribution * geometry / (4.0 * n_dot_l * n_dot_v));
+    let diffuse = (Vec3::repeat(1.0) - fresnel).component_mul(&material.albedo) * (1.0 - material.metallic);
+    // La luz directa vale PI para que el difuso (albedo / PI) quede igual que el Lambert de antes
+    ambient_light + (diffuse + specular * PI) * (n_dot_l * (1.0 - ambient) * shadow * uniforms.light_intensity)
+}
+
+// Parte de la luz del sol que no tapan otros cuerpos ni la nave (ver shadow.rs)
+fn sun_visibility(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    uniforms.shadows.as_ref().map_or(1.0, |shadows| {
+        shadows.visibility(fragment.world_position, uniforms.light_position, uniforms.shadow_id)
+    })
+}
+
+// Oscurecimiento del borde de una estrella: hacia el limbo la vista roza la superficie y solo
+// llega la luz de capas más altas y frías. Ley lineal I(mu) = 1 - u (1 - mu) con un
+// coeficiente por canal, mayor en el azul, así que el borde además enrojece.
+fn limb_darkening(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let (center, _) = sphere_bounds(&uniforms.model_matrix);
+    let normal = (fragment.world_position - center).normalize();
+    let view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    let mu = normal.dot(&view_dir).clamp(0.0, 1.0);
+    let coefficients = Vec3::new(0.5, 0.62, 0.78);
+    Vec3::repeat(1.0) - coefficients * (1.0 - mu)
+}
+
+// 1 en el lado nocturno y 0 de día, con una transición suave a lo largo del terminador para
+// que la emisión que solo se ve de noche no se corte de golpe
+fn night_side(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    let (center, _) = sphere_bounds(&uniforms.model_matrix);
+    let normal = (fragment.world_position - center).normalize();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let t = ((0.1 - normal.dot(&light_dir)) / 0.3).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Cráteres sobre la esfera unitaria: altura y gradiente analítico. Cada celda de una
+// rejilla 3D puede tener un cráter (cuenco con borde elevado) con centro y radio al azar.
+fn craters(p: Vec3, frequency: f32, noise: &Noise) -> (f32, Vec3) {
+    let q = p * frequency;
+    let cell = q.map(|x| x.floor());
+
+    let mut height = 0.0;
+    let mut gradient = Vec3::zeros();
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let (cx, cy, cz) = (cell.x as i32 + x, cell.y as i32 + y, cell.z as i32 + z);
+                let random = |k: i32| noise.random(cx, cy, cz, k);
+                if random(0) > 0.45 {
+                    continue;
+                }
+
+                let c = Vec3::new(cx as f32, cy as f32, cz as f32);
+                let center = c + Vec3::new(random(1), random(2), random(3));
+                let radius = 0.15 + 0.3 * random(4);
+                let offset = q - center;
+                let d = offset.magnitude();
+                let s = d / radius;
+                if s >= 2.0 || d < 1e-6 {
+                    continue;
+                }
+
+                // Perfil en radios: cuenco parabólico y borde gaussiano; depth es la pendiente máxima
+                let depth = 0.35;
+                let rim = 0.35 * (-((s - 1.0) / 0.3).powi(2)).exp();
+                let (profile, slope) = if s < 1.0 {
+                    (s * s - 1.0 + rim, 2.0 * s + rim * -2.0 * (s - 1.0) / 0.09)
+                } else {
+                    (rim, rim * -2.0 * (s - 1.0) / 0.09)
+                };
+
+                // La altura escala con el radio, así la pendiente no depende del tamaño
+                height += depth * profile * radius / frequency;
+                gradient += offset / d * depth * slope;
+            }
+        }
+    }
+
+    (height, gradient)
+}
+
+// Cráteres grandes y chicos del planeta rocoso
+fn crater_gradient(p: Vec3, noise: &Noise) -> Vec3 {
+    let p = p.normalize();
+    let (_, large) = craters(p, 5.0, &noise.derive(1));
+    let (_, small) = craters(p, 13.0, &noise.derive(2));
+    large + small * 0.6
+}
+
+// Normal del planeta rocoso: la del relieve desplazado (interpolada) con los cráteres de
+// los continentes encima, leídos de la textura horneada si la hay
+fn crater_normal(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let gradient = match uniforms.baked.as_ref().and_then(|baked| baked.bump.as_ref()) {
+        Some(bump) => bump.sample(equirectangular_uv(&fragment.vertex_position)),
+        None => crater_gradient(fragment.vertex_position, &uniforms.noise),
+    };
+
+    // Se desvanecen hacia la costa. La altura sale del radio de la malla desplazada (ver
+    // displace_mesh), que es 1 a nivel del mar, en vez de evaluar otra vez la elevación.
+    let ocean_level = uniforms.planet.ocean_level;
+    let height = (fragment.vertex_position.magnitude() - 1.0).max(0.0) / MAX_DISPLACEMENT * (1.0 - ocean_level);
+    let land = (height / 0.04).clamp(0.0, 1.0);
+
+    // La esfera tiene escala uniforme: el gradiente pasa a mundo rotándolo y quitando la escala
+    let (_, scale) = sphere_bounds(&uniforms.model_matrix);
+    let gradient = (uniforms.model_matrix * (gradient * land).push(0.0)).xyz() / scale;
+    bump(fragment.normal, gradient)
+}
+
+// Mapa de normales procedural de paneles en UV: líneas hundidas en una rejilla con
+// filas desplazadas como ladrillos
+fn panel_normal(fragment: &Fragment, noise: &Noise) -> Vec3 {
+    let panels = 14.0;
+    let width = 0.06;
+    let depth = 1.5;
+
+    let uv = fragment.tex_coords * panels;
+    let row = uv.y.floor();
+    let shift = noise.random(row as i32, 0, 0, 0) * 0.5;
+    let cell_u = uv.x + shift;
+
+    // Pendiente de la ranura en V (dh/dt) según la distancia con signo a la línea más cercana
+    let slope = |t: f32| -> f32 {
+        let d = t - t.round();
+        if d.abs() >= width { 0.0 } else { d.signum() * depth }
+    };
+
+    let tangent_normal = Vec3::new(-slope(cell_u), -slope(uv.y), 1.0).normalize();
+    tangent_to_world(fragment, tangent_normal)
 }
 
 // Shaders para los cuerpos celestes
-pub fn shade_star(point: Vec3, time: f32) -> Vec3 {
+pub fn shade_star(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
     
-    // Colores del sol más realistas: amarillo-naranja brillante
-    let mut color = Vec3::new(1.0, 0.9, 0.3); // Amarillo brillante
-    let dist_to_center = uv.magnitude();
-    color *= 1.2 - (dist_to_center * 0.6).powf(2.0); // Núcleo muy brillante
+    // Colores según la clase espectral (ver star.rs); para el sol, amarillo-naranja brillante
+    let [core_color, lane_color, flame_color, _] = planet.palette;
+    let mut color = core_color;
 
-    // Gradiente radial hacia naranja en los bordes
-    let radial_grad = (1.0 - uv.magnitude()).powf(2.5);
-    let grad_color = Vec3::new(1.0, 0.6, 0.1); // Naranja intenso
-    color = color.lerp(&grad_color, radial_grad * 0.7);
+    // Granulación: celdas de convección de Worley con el centro caliente y los bordes más
+    // fríos y oscuros. El dominio se deforma con el tiempo, así las celdas cambian de forma.
+    let drift = time * 0.15;
+    let p = uv * 32.0;
+    let q = Vec4::new(p.x * 0.15, p.y * 0.15, p.z * 0.15, drift);
+    let flow = Vec3::new(noise.derive(10).simplex4(q), noise.derive(11).simplex4(q), noise.derive(12).simplex4(q));
+    let cells = noise.derive(13).worley(p + flow * 0.8);
+    let granule = ((cells.f2 - cells.f1) / 0.5).clamp(0.0, 1.0).sqrt();
+    color = lane_color.lerp(&color, 0.75 + 0.25 * granule) * (0.9 + 0.06 * granule + 0.04 * cells.cell);
 
-    // Turbulencia de superficie solar (menos que antes)
+    // Turbulencia de superficie solar: el tiempo es la cuarta dimensión, así hierve en su lugar
     let turbulence_freq = 3.0;
     let turbulence_speed = 0.5;
-    let turbulence = fbm(uv * turbulence_freq + Vec3::new(0.0, 0.0, time * turbulence_speed), 2, 0.5, 2.0);
-    let flame_color = Vec3::new(1.0, 0.5, 0.0); // Naranja-rojo
+    let p = uv * turbulence_freq;
+    let turbulence = unit(noise.fbm4(Vec4::new(p.x, p.y, p.z, time * turbulence_speed), 2, 0.5, 2.0));
     color = color.lerp(&flame_color, turbulence * 0.3);
 
     // Pulsación sutil
     let pulse = ((time * 1.5).sin() * 0.5 + 0.5) * 0.15 + 0.95; // Varía entre 0.95 y 1.1
     color *= pulse;
 
-    color.map(|x| x.max(0.0).min(1.5)) // Permitir valores > 1.0 para brillo extra
+    // Toda la luz de la estrella es propia; valores > 1.0 para brillo extra. El borde se
+    // oscurece según el ángulo de vista en fragment_shader (ver limb_darkening).
+    // max/min en vez de clamp: si el ruido da NaN, max lo deja en 0 en lugar de propagarlo
+    // al bloom
+    #[allow(clippy::manual_clamp)]
+    SurfaceMaterial::emissive(color.map(|x| x.max(0.0).min(1.5)))
+}
+
+// Densidad de nubes en la dirección uv, según la fracción cover del cielo. El dominio
+// deformado y el tiempo como cuarta dimensión hacen que se formen y deshagan en su lugar;
+// con menos octavas el borde es más suave (para las sombras).
+fn clouds(uv: Vec3, time: f32, noise: &Noise, cover: f32, octaves: u32) -> f32 {
+    if cover <= 0.0 {
+        return 0.0;
+    }
+    let noise = noise.derive(50);
+    let p = noise.warp(uv * 2.0, 0.8, 1);
+    let n = unit(noise.fbm4(Vec4::new(p.x, p.y, p.z, time * 0.06), octaves, 0.55, 2.0));
+    // El ruido se concentra alrededor de 0.5, así que el umbral se mueve menos que cover
+    let threshold = 0.5 + (0.5 - cover) * 0.5;
+    let t = ((n - threshold) / 0.2).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Casquetes polares: 1 en latitudes por encima de 1 - caps (en fracción de 90°), con borde irregular
+fn polar_caps(uv: Vec3, noise: &Noise, caps: f32) -> f32 {
+    if caps <= 0.0 {
+        return 0.0;
+    }
+    let latitude = uv.y.abs().clamp(0.0, 1.0).asin() / (PI * 0.5);
+    let edge = 1.0 - caps + (unit(noise.derive(51).perlin(uv * 6.0)) - 0.5) * 0.1;
+    ((latitude - edge) / 0.03).clamp(0.0, 1.0)
 }
 
-pub fn shade_rocky(point: Vec3, time: f32) -> Vec3 {
+// Elevación del planeta rocoso en [0, 1]; por encima de ocean_level es tierra
+fn rocky_elevation(uv: Vec3, noise: &Noise, planet: &PlanetParams) -> f32 {
+    // Domain warping para costas irregulares en vez de manchas redondas
+    let p = noise.warp(uv * planet.continent_frequency, 0.5, 2);
+    unit(noise.fbm(p, 4, 0.5, 2.0))
+}
+
+// Luces de las ciudades sobre la tierra firme: puntos de Worley que se encienden donde el
+// ruido de población es alto, más juntos cerca de la costa y nunca sobre los casquetes
+fn city_lights(uv: Vec3, elevation: f32, noise: &Noise, planet: &PlanetParams) -> Vec3 {
+    let threshold = planet.ocean_level;
+    if planet.city_lights <= 0.0 || elevation <= threshold {
+        return Vec3::zeros();
+    }
+    let shore = ((elevation - threshold) / 0.01).clamp(0.0, 1.0);
+    let coast = (1.0 - (elevation - threshold) / (1.0 - threshold) * 2.5).clamp(0.25, 1.0);
+    let population = unit(noise.derive(60).fbm(uv * 4.0, 3, 0.5, 2.0));
+    let density = ((population - 0.65 + planet.city_lights * 0.35) / 0.2).clamp(0.0, 1.0) * coast;
+
+    // Cada celda tiene una ciudad si su valor aleatorio cae por debajo de la densidad
+    let light = |frequency: f32, radius: f32, salt: u32| {
+        let cells = noise.derive(salt).worley(uv * frequency);
+        if cells.cell >= density {
+            return 0.0;
+        }
+        let glow = (1.0 - cells.f1 / radius).clamp(0.0, 1.0);
+        glow * glow
+    };
+    let cities = light(40.0, 0.6, 61) + light(100.0, 0.5, 62) * 0.7;
+    // Resplandor tenue de las afueras alrededor de las zonas más pobladas
+    let sprawl = density * density * 0.15;
+
+    let caps = 1.0 - polar_caps(uv, noise, planet.ice_caps);
+    Vec3::new(1.0, 0.78, 0.45) * ((cities + sprawl) * shore * caps).min(1.0) * 1.2
+}
+
+pub fn shade_rocky(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
 
-    let base_freq = 2.0;
-    let n = fbm(uv * base_freq, 3, 0.5, 2.0);
+    let n = rocky_elevation(uv, noise, planet);
 
-    let threshold = 0.5;
+    let threshold = planet.ocean_level;
     let is_land = n > threshold;
     
-    let ocean_color_deep = Vec3::new(0.0, 0.1, 0.3);
-    let ocean_color_shallow = Vec3::new(0.1, 0.3, 0.7);
-    let land_color_low = Vec3::new(0.1, 0.4, 0.1);
-    let land_color_high = Vec3::new(0.6, 0.5, 0.3);
+    let [ocean_color_deep, ocean_color_shallow, land_color_low, land_color_high] = planet.palette;
 
+    // El agua es casi un espejo y deja el reflejo del sol; la tierra es mate
     let mut color;
+    let mut roughness;
     if is_land {
         let land_factor = (n - threshold) / (1.0 - threshold);
         color = land_color_low.lerp(&land_color_high, land_factor.powf(0.7));
+        roughness = 0.85;
     } else {
         let ocean_factor = n / threshold;
         color = ocean_color_deep.lerp(&ocean_color_shallow, ocean_factor);
+        roughness = 0.2;
     }
 
     // Simplified detail for better performance
-    let detail_noise = noise(uv * 5.0 + Vec3::new(0.0, 0.0, time * 0.1));
+    let detail_noise = unit(noise.derive(3).perlin(uv * 5.0 + Vec3::new(0.0, 0.0, time * 0.1)));
     if is_land {
         color = color.lerp(&Vec3::new(0.9, 0.9, 0.9), detail_noise * 0.15);
+    } else {
+        // Zonas de oleaje más picadas que desdibujan el reflejo
+        roughness += detail_noise * 0.12;
     }
 
-    color.map(|x| x.max(0.0).min(1.0))
+    // Las nubes van en su propia capa (ver shade_clouds)
+    let caps = polar_caps(uv, noise, planet.ice_caps);
+    color = color.lerp(&Vec3::new(0.95, 0.97, 1.0), caps);
+    roughness += (0.4 - roughness) * caps;
+
+    SurfaceMaterial::diffuse(color.map(|x| x.clamp(0.0, 1.0)))
+        .with_roughness(roughness)
+        .with_emission(city_lights(uv, n, noise, planet))
 }
 
-pub fn shade_gas_giant(point: Vec3, time: f32) -> Vec3 {
+pub fn shade_gas_giant(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
     let mut color;
 
-    let band_freq_y = 8.0;
+    let band_freq_y = planet.band_count * PI;
     let band_speed = 0.2;
-    let band_noise_freq = 15.0;
     
     let y_component = uv.y + time * band_speed * 0.1;
-    let band_noise = noise(uv * band_noise_freq + Vec3::new(time * band_speed, 0.0, 0.0));
-    let bands = (y_component * band_freq_y + band_noise * 2.0).sin();
+    // Bandas turbulentas: el ruido que las ondula se muestrea sobre un dominio deformado
+    let flow = Vec3::new(time * band_speed, 0.0, 0.0);
+    let band_noise = noise.fbm(noise.warp(uv * planet.continent_frequency + flow, 0.6, 2), 3, 0.5, 2.0);
+    let bands = (y_component * band_freq_y + band_noise * 4.0).sin();
 
-    let band_color1 = Vec3::new(0.8, 0.7, 0.5);
-    let band_color2 = Vec3::new(0.6, 0.4, 0.2);
+    let [band_color1, band_color2, storm_color, highlight] = planet.palette;
     color = band_color1.lerp(&band_color2, bands * 0.5 + 0.5);
 
-    let gas_texture_noise = noise(uv * 20.0 + Vec3::new(time * 0.3, 0.0, 0.0));
-    color = color.lerp(&Vec3::new(1.0, 1.0, 1.0), gas_texture_noise * 0.08);
+    let gas_texture_noise = unit(noise.derive(5).perlin(uv * 20.0 + Vec3::new(time * 0.3, 0.0, 0.0)));
+    color = color.lerp(&highlight, gas_texture_noise * 0.08);
 
-    // Simplified storm
-    let storm_pos = Vec3::new(0.0, -0.4, 0.0);
-    let dist_to_storm = (uv - storm_pos).magnitude();
-    if dist_to_storm < 0.25 {
-        let storm_factor = 1.0 - (dist_to_storm / 0.25);
-        let storm_color = Vec3::new(0.95, 0.3, 0.15);
-        color = color.lerp(&storm_color, storm_factor.powf(3.0) * 0.6);
+    if planet.storm_size > 0.0 {
+        let dist_to_storm = (uv - planet.storm_position).magnitude();
+        if dist_to_storm < planet.storm_size {
+            let storm_factor = 1.0 - (dist_to_storm / planet.storm_size);
+            color = color.lerp(&storm_color, storm_factor.powf(3.0) * 0.6);
+        }
     }
 
-    color.map(|x| x.max(0.0).min(1.0))
+    // Nubes de gas: un brillo muy difuso
+    SurfaceMaterial::diffuse(color.map(|x| x.clamp(0.0, 1.0))).with_roughness(0.8)
 }
 
-pub fn shade_spaceship(_point: Vec3, _time: f32) -> Vec3 {
-    // Color gris uniforme para toda la nave
-    Vec3::new(0.5, 0.5, 0.5)
+pub fn shade_spaceship(_point: Vec3, _time: f32, _noise: &Noise, _planet: &PlanetParams) -> SurfaceMaterial {
+    // Metal pintado de gris uniforme para toda la nave
+    SurfaceMaterial::diffuse(Vec3::new(0.5, 0.5, 0.5)).with_roughness(0.35).with_metallic(0.5)
 }
 
-pub fn shade_ice_planet(point: Vec3, time: f32) -> Vec3 {
+pub fn shade_ice_planet(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
     
     // Planeta helado con grietas y hielo
-    let base_freq = 3.0;
-    let n = fbm(uv * base_freq + Vec3::new(0.0, time * 0.05, 0.0), 3, 0.5, 2.0);
+    let n = unit(noise.fbm(uv * planet.continent_frequency + Vec3::new(0.0, time * 0.05, 0.0), 3, 0.5, 2.0));
     
-    let ice_color = Vec3::new(0.8, 0.9, 1.0);
-    let crack_color = Vec3::new(0.3, 0.4, 0.6);
+    let [ice_color, crack_color, snow_color, cap_color] = planet.palette;
     
     let mut color = ice_color.lerp(&crack_color, n * 0.6);
+
+    // Grietas: las crestas del ruido ridged forman líneas finas y ramificadas
+    let cracks = noise.derive(1).ridged(uv * 4.0, 3, 0.5, 2.0);
+    color = color.lerp(&crack_color, cracks.powi(8) * 0.8);
     
     // Añadir detalles de nieve
-    let detail = noise(uv * 8.0);
-    color = color.lerp(&Vec3::new(1.0, 1.0, 1.0), detail * 0.3);
-    
-    color.map(|x| x.max(0.0).min(1.0))
+    let detail = unit(noise.derive(2).perlin(uv * 8.0));
+    color = color.lerp(&snow_color, detail * 0.3);
+
+    color = color.lerp(&cap_color, polar_caps(uv, noise, planet.ice_caps));
+    let clouds = clouds(uv, time, noise, planet.cloud_cover, 5);
+    color = color.lerp(&snow_color, clouds * 0.7);
+
+    // Hielo pulido con un brillo suave; las grietas y las nubes son mates
+    let roughness = 0.35 + cracks.powi(8) * 0.4 + clouds * 0.5;
+    SurfaceMaterial::diffuse(color.map(|x| x.clamp(0.0, 1.0))).with_roughness(roughness)
 }
 
-pub fn shade_desert_planet(point: Vec3, time: f32) -> Vec3 {
+pub fn shade_desert_planet(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
     
     // Planeta desértico con dunas
-    let base_freq = 4.0;
-    let n = fbm(uv * base_freq + Vec3::new(time * 0.02, 0.0, 0.0), 2, 0.6, 2.0);
+    let n = unit(noise.fbm(uv * planet.continent_frequency + Vec3::new(time * 0.02, 0.0, 0.0), 2, 0.6, 2.0));
     
-    let sand_light = Vec3::new(0.9, 0.7, 0.3);
-    let sand_dark = Vec3::new(0.6, 0.4, 0.1);
+    let [sand_dark, sand_light, dune_color, cap_color] = planet.palette;
     
     let mut color = sand_dark.lerp(&sand_light, n.powf(0.8));
     
     // Dunas de arena
-    let dunes = (uv.y * 10.0 + noise(uv * 6.0) * 2.0).sin() * 0.5 + 0.5;
-    color = color.lerp(&Vec3::new(0.95, 0.8, 0.4), dunes * 0.3);
+    let dunes = (uv.y * 10.0 + unit(noise.derive(1).perlin(uv * 6.0)) * 2.0).sin() * 0.5 + 0.5;
+    color = color.lerp(&dune_color, dunes * 0.3);
+
+    // Ondulaciones redondeadas más pequeñas con ruido billow
+    let ripples = noise.derive(2).billow(uv * 14.0, 2, 0.5, 2.0);
+    color *= 0.9 + ripples * 0.2;
+
+    let caps = polar_caps(uv, noise, planet.ice_caps);
+    color = color.lerp(&cap_color, caps);
+    color = color.lerp(&Vec3::new(1.0, 1.0, 1.0), clouds(uv, time, noise, planet.cloud_cover, 5) * 0.6);
     
-    color.map(|x| x.max(0.0).min(1.0))
+    // Arena mate; los casquetes de escarcha brillan un poco
+    SurfaceMaterial::diffuse(color.map(|x| x.clamp(0.0, 1.0))).with_roughness(0.95 - caps * 0.45)
 }
 
-pub fn shade_volcanic_planet(point: Vec3, time: f32) -> Vec3 {
+// Elevación del planeta volcánico en [0, 1]; por encima de 1 - lava_amount hay lava
+fn volcanic_elevation(uv: Vec3, noise: &Noise, planet: &PlanetParams) -> f32 {
+    unit(noise.fbm(uv.normalize() * planet.continent_frequency, 3, 0.5, 2.0))
+}
+
+pub fn shade_volcanic_planet(point: Vec3, time: f32, noise: &Noise, planet: &PlanetParams) -> SurfaceMaterial {
     let uv = point.normalize();
     
-    // Planeta volcánico con lava
-    let base_freq = 3.0;
-    let n = fbm(uv * base_freq, 3, 0.5, 2.0);
+    // Planeta volcánico con lava: la roca refleja la luz y la lava brilla por sí misma,
+    // así que sigue viéndose en el lado nocturno
+    let n = volcanic_elevation(uv, noise, planet);
     
-    let rock_color = Vec3::new(0.2, 0.15, 0.1);
-    let lava_color = Vec3::new(1.0, 0.3, 0.0);
+    let [rock_color, lava_color, glow_color, rock_detail] = planet.palette;
     
-    let threshold = 0.45;
-    let mut color;
+    let threshold = 1.0 - planet.lava_amount;
+    let mut color = rock_color;
+    let mut emission = Vec3::zeros();
+    let mut roughness = 0.85;
     
     if n > threshold {
         // Zonas de lava
         let lava_factor = (n - threshold) / (1.0 - threshold);
-        color = rock_color.lerp(&lava_color, lava_factor.powf(2.0));
+        let glow = lava_factor.powf(2.0);
+        color *= 1.0 - glow;
+        emission = lava_color * glow;
+        // La costra de lava enfriada es vidriosa
+        roughness = 0.45;
         
         // Pulsación de lava
         let pulse = (time * 2.0 + uv.x * 5.0).sin() * 0.5 + 0.5;
-        color = color.lerp(&Vec3::new(1.0, 0.5, 0.0), pulse * lava_factor * 0.4);
+        emission = emission.lerp(&glow_color, pulse * lava_factor * 0.4);
     } else {
         // Roca oscura
-        color = rock_color;
-        let detail = noise(uv * 10.0);
-        color = color.lerp(&Vec3::new(0.3, 0.25, 0.2), detail * 0.3);
+        let detail = unit(noise.derive(1).perlin(uv * 10.0));
+        color = color.lerp(&rock_detail, detail * 0.3);
     }
+
+    // Grietas de lava entre placas de roca: donde las dos celdas de Worley más cercanas
+    // están a la misma distancia
+    let plates = noise.derive(2).worley(uv * 5.0);
+    let crack = (1.0 - (plates.f2 - plates.f1) / 0.1).clamp(0.0, 1.0);
+    let crack = crack * crack * 0.9;
+    color *= 1.0 - crack;
+    emission = emission.lerp(&lava_color, crack);
     
-    color.map(|x| x.max(0.0).min(1.0))
-}
\ No newline at end of file
+    SurfaceMaterial::diffuse(color.map(|x| x.clamp(0.0, 1.0)))
+        .with_roughness(roughness)
+        .with_emission(emission.map(|x| x.clamp(0.0, 1.0)))
+}
+
+// Halo atmosférico: se dibuja sobre una esfera un poco más grande que el planeta con mezcla aditiva.
+// La intensidad sigue el espesor óptico del rayo de vista a través de la capa, que es máximo
+// justo en el borde del planeta y cae a cero en el borde exterior de la atmósfera.
+pub fn shade_atmosphere(fragment: &Fragment, uniforms: &Uniforms) -> Vec4 {
+    let atmosphere = match uniforms.atmosphere {
+        Some(atmosphere) => atmosphere,
+        None => return Vec4::new(0.0, 0.0, 0.0, 0.0),
+    };
+
+    // La capa es una esfera: la normal sale del centro, así no depende de las normales de la malla
+    let (center, _) = sphere_bounds(&uniforms.model_matrix);
+    let normal = (fragment.world_position - center).normalize();
+    let view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+
+    // Parámetro de impacto del rayo de vista, en radios de la capa exterior
+    let n_dot_v = normal.dot(&view_dir).clamp(0.0, 1.0);
+    let impact = (1.0 - n_dot_v * n_dot_v).sqrt();
+    let planet_ratio = 1.0 / atmosphere.shell_scale();
+
+    let path = if impact < planet_ratio {
+        // El rayo choca con el planeta: solo cuenta el tramo hasta la superficie
+        (1.0 - impact * impact).sqrt() - (planet_ratio * planet_ratio - impact * impact).sqrt()
+    } else {
+        2.0 * (1.0 - impact * impact).sqrt()
+    };
+    let max_path = 2.0 * (1.0 - planet_ratio * planet_ratio).sqrt();
+    let optical_depth = path / max_path.max(1e-4);
+
+    // Lado iluminado con un terminador suave
+    let n_dot_l = normal.dot(&light_dir);
+    let t = ((n_dot_l + 0.3) / 0.8).clamp(0.0, 1.0);
+    let daylight = t * t * (3.0 - 2.0 * t) * sun_visibility(fragment, uniforms) * uniforms.light_intensity;
+
+    // Dispersión hacia adelante cuando se mira hacia el sol a través de la atmósfera
+    let forward = (-view_dir).dot(&light_dir).max(0.0).powf(8.0);
+    let phase = 1.0 + forward * 1.5;
+
+    // Con mezcla aditiva el alpha escala el color que se suma al fondo
+    let intensity = (1.0 - (-atmosphere.density * optical_depth * 3.0).exp()) * daylight * phase;
+
+    Vec4::new(atmosphere.color.x, atmosphere.color.y, atmosphere.color.z, intensity.clamp(0.0, 1.0))
+}
+
+// Capa de nubes: esfera transparente un poco por encima de la superficie, que gira a su
+// propio ritmo. Se ilumina con un terminador suave y deja pasar la luz donde es delgada.
+pub fn shade_clouds(fragment: &Fragment, uniforms: &Uniforms) -> Vec4 {
+    if uniforms.clouds.is_none() {
+        return Vec4::new(0.0, 0.0, 0.0, 0.0);
+    }
+
+    let density = clouds(fragment.vertex_position.normalize(), uniforms.time, &uniforms.noise, uniforms.planet.cloud_cover, 4);
+    if density <= 0.0 {
+        return Vec4::new(0.0, 0.0, 0.0, 0.0);
+    }
+
+    let (center, _) = sphere_bounds(&uniforms.model_matrix);
+    let normal = (fragment.world_position - center).normalize();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let t = ((normal.dot(&light_dir) + 0.2) / 0.6).clamp(0.0, 1.0);
+    let light = 0.08 + 0.92 * t * t * (3.0 - 2.0 * t) * sun_visibility(fragment, uniforms) * uniforms.light_intensity;
+
+    let color = Vec3::new(1.0, 1.0, 1.0) * light;
+    Vec4::new(color.x, color.y, color.z, (density * 0.95).clamp(0.0, 1.0))
+}
+
+// Factor de luz en la superficie por la sombra de las nubes: se busca dónde corta la capa
+// el rayo hacia el sol y se lee ahí la densidad, girada con la capa
+fn cloud_shadow(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    let clouds_layer = match uniforms.clouds {
+        Some(clouds_layer) => clouds_layer,
+        None => return 1.0,
+    };
+
+    let (center, radius) = sphere_bounds(&uniforms.model_matrix);
+    let shell_radius = radius * clouds_layer.shell_scale();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let offset = fragment.world_position - center;
+    if offset.dot(&light_dir) <= 0.0 {
+        // Lado nocturno: no hay luz directa que tapar
+        return 1.0;
+    }
+
+    // Salida del rayo de la esfera de la capa (el punto está dentro)
+    let b = offset.dot(&light_dir);
+    let c = offset.magnitude_squared() - shell_radius * shell_radius;
+    let distance = -b + (b * b - c).max(0.0).sqrt();
+    let hit = offset + light_dir * distance;
+
+    // Del mundo al espacio de la capa, que solo gira alrededor de Y
+    let direction = (rotation(-uniforms.time * clouds_layer.spin, &Vec3::y()) * hit.push(0.0)).xyz();
+    let density = clouds(direction.normalize(), uniforms.time, &uniforms.noise, uniforms.planet.cloud_cover, 2);
+    1.0 - density * 0.6
+}
+
+// Densidad de los anillos según la posición radial t (0 = borde interior, 1 = borde exterior).
+// La comparten el shader de los anillos y la sombra que proyectan sobre el planeta.
+fn ring_density(t: f32, noise: &Noise) -> f32 {
+    if !(0.0..=1.0).contains(&t) {
+        return 0.0;
+    }
+
+    // Bandas finas a partir de ruido a lo largo del radio
+    let bands = unit(noise.fbm(Vec3::new(t * 30.0, 0.5, 0.5), 3, 0.55, 2.3));
+    let fine = unit(noise.derive(1).perlin(Vec3::new(t * 120.0, 1.5, 1.5)));
+    let mut density = (bands * 1.6 - 0.3).clamp(0.0, 1.0) * (0.7 + fine * 0.3);
+
+    // Divisiones principales (como la de Cassini y la de Encke)
+    let gap = |center: f32, width: f32| ((t - center).abs() / width).clamp(0.0, 1.0);
+    density *= gap(0.62, 0.035) * gap(0.88, 0.012);
+
+    // Bordes suaves
+    let edge = (t / 0.05).clamp(0.0, 1.0) * ((1.0 - t) / 0.08).clamp(0.0, 1.0);
+    density * edge
+}
+
+pub fn shade_rings(fragment: &Fragment, uniforms: &Uniforms) -> Vec4 {
+    let t = fragment.tex_coords.x;
+    let density = ring_density(t, &uniforms.noise);
+    if density <= 0.0 {
+        return Vec4::new(0.0, 0.0, 0.0, 0.0);
+    }
+
+    let dust_light = Vec3::new(0.85, 0.78, 0.62);
+    let dust_dark = Vec3::new(0.55, 0.45, 0.32);
+    let tint = unit(uniforms.noise.derive(2).perlin(Vec3::new(t * 25.0, 3.0, 3.0)));
+    let mut color = dust_dark.lerp(&dust_light, tint);
+
+    // Sombra del planeta: el rayo hacia el sol pasa cerca del centro del planeta
+    let (center, radius) = sphere_bounds(&uniforms.model_matrix);
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let to_center = center - fragment.world_position;
+    let along = to_center.dot(&light_dir);
+    let mut lit = 1.0;
+    if along > 0.0 {
+        let closest = (to_center - light_dir * along).magnitude();
+        lit = ((closest - radius * 0.95) / (radius * 0.1)).clamp(0.0, 1.0);
+    }
+    color *= 0.15 + 0.85 * lit * uniforms.light_intensity;
+
+    Vec4::new(color.x, color.y, color.z, (density * 0.9).clamp(0.0, 1.0))
+}
+
+// Factor de luz en la superficie del planeta por la sombra de sus anillos
+fn ring_shadow(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    let rings = match uniforms.rings {
+        Some(rings) => rings,
+        None => return 1.0,
+    };
+
+    let (center, radius) = sphere_bounds(&uniforms.model_matrix);
+    let normal = rings.normal();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+
+    let denom = light_dir.dot(&normal);
+    if denom.abs() < 1e-4 {
+        return 1.0;
+    }
+    let distance = (center - fragment.world_position).dot(&normal) / denom;
+    if distance <= 0.0 {
+        return 1.0;
+    }
+
+    let hit = fragment.world_position + light_dir * distance;
+    let r = (hit - center).magnitude() / radius;
+    let t = (r - rings.inner_radius) / (rings.outer_radius - rings.inner_radius);
+
+    1.0 - ring_density(t, &uniforms.noise) * 0.7
+}
+
+// Corona: billboard aditivo centrado en la estrella que mira a la cámara. El brillo cae
+// rápido desde el limbo y los rayos salen de un ruido que varía con el ángulo y muy poco
+// con la distancia, así quedan estirados hacia afuera; el tiempo los hace ondular.
+pub fn shade_corona(fragment: &Fragment, uniforms: &Uniforms) -> Vec4 {
+    let corona = match uniforms.corona {
+        Some(corona) => corona,
+        None => return Vec4::new(0.0, 0.0, 0.0, 0.0),
+    };
+
+    // El billboard va de -1 a 1 en X y Z y mide `extent` radios de la estrella
+    let p = fragment.vertex_position;
+    let r = Vec3::new(p.x, 0.0, p.z).magnitude() * corona.extent;
+    if r < 1.0 || r >= corona.extent {
+        return Vec4::new(0.0, 0.0, 0.0, 0.0);
+    }
+
+    let angle = p.z.atan2(p.x);
+    let q = Vec4::new(angle.cos() * 2.5, angle.sin() * 2.5, r * 0.3 - uniforms.time * 0.05, uniforms.time * 0.2);
+    let rays = unit(uniforms.noise.derive(75).fbm4(q, 3, 0.5, 2.2)).powi(3) * corona.streamers;
+
+    let glow = 0.5 / r.powi(4) + rays * 1.5 / (r * r);
+    let edge = ((corona.extent - r) / (corona.extent * 0.3)).clamp(0.0, 1.0);
+    let [core_color, outer_color, _, _] = uniforms.planet.palette;
+    let color = core_color.lerp(&outer_color, ((r - 1.0) / (corona.extent - 1.0)).sqrt());
+
+    Vec4::new(color.x, color.y, color.z, (glow * edge).clamp(0.0, 1.0))
+}
+
+// Protuberancia: tubo aditivo a lo largo del arco. El plasma es más denso en el eje del tubo
+// (donde la normal mira a la cámara) y fluye en filamentos de un pie al otro. También emite,
+// así el bloom le da un halo.
+pub fn shade_prominence(fragment: &Fragment, uniforms: &Uniforms) -> FragmentOutput {
+    let prominence = match uniforms.prominence {
+        Some(prominence) => prominence,
+        None => return Vec4::new(0.0, 0.0, 0.0, 0.0).into(),
+    };
+
+    let u = fragment.tex_coords.x;
+    let (sin_v, cos_v) = (fragment.tex_coords.y * 2.0 * PI).sin_cos();
+    let view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    let facing = fragment.normal.normalize().dot(&view_dir).abs();
+
+    let noise = uniforms.noise.derive(prominence.seed);
+    let flow = unit(noise.fbm(Vec3::new(u * 10.0 - uniforms.time * 2.0, cos_v * 0.8, sin_v * 0.8), 3, 0.5, 2.0));
+    let filaments = (flow * 1.6 - 0.4).clamp(0.0, 1.0);
+
+    // Más caliente en los pies que en lo alto del arco
+    let [core_color, _, flame_color, _] = uniforms.planet.palette;
+    let color = flame_color.lerp(&core_color, (1.0 - (u * PI).sin()) * 0.5) * 1.3;
+    let alpha = (facing * facing * filaments * prominence.fade()).clamp(0.0, 1.0);
+
+    FragmentOutput { color: Vec4::new(color.x, color.y, color.z, alpha), emission: color }
+}
+
+// Asteroide: roca gris o marrón según la instancia (`instance_params.x`, con el brillo en
+// `y`), con manchas de fBm y cráteres oscuros de Worley. El ruido sale del índice de la
+// instancia, así dos asteroides con la misma malla no tienen la misma superficie.
+pub fn shade_asteroid(fragment: &Fragment, uniforms: &Uniforms) -> SurfaceMaterial {
+    let params = uniforms.instance_params;
+    let noise = uniforms.noise.derive(100 + uniforms.instance);
+    let p = fragment.vertex_position;
+
+    let gray = Vec3::new(0.42, 0.4, 0.38);
+    let brown = Vec3::new(0.45, 0.33, 0.24);
+    let mottling = 0.8 + 0.2 * noise.fbm(p * 3.0, 3, 0.5, 2.0);
+    let craters = noise.derive(1).worley(p * 2.5);
+    let crater = 1.0 - (1.0 - (craters.f1 / 0.35).clamp(0.0, 1.0)) * 0.35;
+
+    let albedo = gray.lerp(&brown, params.x) * (mottling * crater * params.y);
+    SurfaceMaterial::diffuse(albedo).with_roughness(0.9)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn star_emission_stays_finite_over_the_sphere() {
+        // shade_star limita su color con max/min para que un NaN del ruido no llegue al bloom.
+        // Se evalúa sobre la esfera y apenas afuera, donde cae un punto que normalize() deja
+        // con error de redondeo
+        let noise = Noise::new(1);
+        let planet = default_planet(0, 0);
+        for i in 0..=16 {
+            for j in 0..32 {
+                let latitude = (i as f32 / 16.0 - 0.5) * PI;
+                let longitude = j as f32 / 32.0 * 2.0 * PI;
+                let direction = Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+                for point in [direction, direction * (1.0 + 1e-6)] {
+                    let emission = shade_star(point, 3.0, &noise, &planet).emission;
+                    assert!(emission.iter().all(|c| c.is_finite() && *c >= 0.0), "{:?} en {:?}", emission, point);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use nalgebra_glm::{Mat4, Vec3};
+use std::f32::consts::PI;
+use crate::vertex::Vertex;
+
+// Sombras que los cuerpos y la nave proyectan unos sobre otros con la luz del sol.
+// Cada objeto lleva un id y no se sombrea a sí mismo: su lado nocturno ya lo oscurece la luz.
+
+// Id de lo que no proyecta sombra
+pub const NO_CASTER: u32 = u32::MAX;
+// Plano cercano de cada cara: los triángulos se recortan donde la distancia a la luz a lo
+// largo del eje de la cara baja de este valor
+const NEAR: f32 = 1e-3;
+// Margen de profundidad, proporcional a la distancia a la luz
+const DEPTH_BIAS: f32 = 2e-3;
+
+// Cómo se calculan las sombras (tecla H)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowMode {
+    Off,
+    Analytic,
+    Map,
+}
+
+impl ShadowMode {
+    pub fn next(self) -> Self {
+        match self {
+            ShadowMode::Off => ShadowMode::Analytic,
+            ShadowMode::Analytic => ShadowMode::Map,
+            ShadowMode::Map => ShadowMode::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShadowMode::Off => "desactivadas",
+            ShadowMode::Analytic => "analíticas (eclipses entre esferas)",
+            ShadowMode::Map => "shadow map",
+        }
+    }
+}
+
+// Esfera que puede tapar el sol
+#[derive(Debug, Clone, Copy)]
+pub struct Occluder {
+    pub center: Vec3,
+    pub radius: f32,
+    pub id: u32,
+}
+
+pub enum Shadows {
+    // El sol es un disco de radio `light_radius` que las esferas tapan en parte: los eclipses
+    // tienen penumbra. La nave no proyecta sombra en este modo.
+    Analytic { light_radius: f32, occluders: Vec<Occluder> },
+    // Luz puntual con un shadow map omnidireccional
+    Map(ShadowMap),
+}
+
+impl Shadows {
+    // Fracción de la luz del sol que llega a `point`, que pertenece al objeto `id`
+    pub fn visibility(&self, point: Vec3, light_position: Vec3, id: u32) -> f32 {
+        match self {
+            Shadows::Analytic { light_radius, occluders } => analytic_visibility(point, light_position, *light_radius, occluders, id),
+            Shadows::Map(map) => map.visibility(point, id),
+        }
+    }
+}
+
+fn analytic_visibility(point: Vec3, light_position: Vec3, light_radius: f32, occluders: &[Occluder], id: u32) -> f32 {
+    let to_light = light_position - point;
+    let light_distance = to_light.magnitude();
+    let light_dir = to_light / light_distance;
+    // Radios angulares del sol y de cada esfera vistos desde el punto
+    let sun = (light_radius / light_distance).min(1.0).asin();
+
+    let mut visible = 1.0;
+    for occluder in occluders.iter().filter(|occluder| occluder.id != id) {
+        let to_occluder = occluder.center - point;
+        let distance = to_occluder.magnitude();
+        let along = to_occluder.dot(&light_dir);
+        if distance <= occluder.radius || along <= 0.0 || distance > light_distance {
+            continue;
+        }
+
+        let size = (occluder.radius / distance).asin();
+        let separation = (along / distance).clamp(-1.0, 1.0).acos();
+        visible -= if sun < 1e-4 {
+            // Luz puntual: sombra dura
+            if separation < size { 1.0 } else { 0.0 }
+        } else {
+            disk_overlap(sun, size, separation) / (PI * sun * sun)
+        };
+    }
+    visible.max(0.0)
+}
+
+// Área común de dos discos de radios r1 y r2 con centros a distancia d
+fn disk_overlap(r1: f32, r2: f32, d: f32) -> f32 {
+    if d >= r1 + r2 {
+        return 0.0;
+    }
+    if d <= (r1 - r2).abs() {
+        return PI * r1.min(r2).powi(2);
+    }
+
+    let a1 = ((d * d + r1 * r1 - r2 * r2) / (2.0 * d * r1)).clamp(-1.0, 1.0).acos();
+    let a2 = ((d * d + r2 * r2 - r1 * r1) / (2.0 * d * r2)).clamp(-1.0, 1.0).acos();
+    let kite = ((-d + r1 + r2) * (d + r1 - r2) * (d - r1 + r2) * (d + r1 + r2)).max(0.0).sqrt();
+    r1 * r1 * a1 + r2 * r2 * a2 - 0.5 * kite
+}
+
+// Coordenadas (s, t) en [-1, 1] de la dirección `d` en la cara `face` y su componente a lo
+// largo del eje de la cara. Inversa de bake::cubemap_direction.
+fn face_coords(face: usize, d: Vec3) -> (f32, f32, f32) {
+    let (s, t, axis) = match face {
+        0 => (-d.z, -d.y, d.x),
+        1 => (d.z, -d.y, -d.x),
+        2 => (d.x, d.z, d.y),
+        3 => (d.x, -d.z, -d.y),
+        4 => (d.x, -d.y, d.z),
+        _ => (-d.x, -d.y, -d.z),
+    };
+    (s / axis, t / axis, axis)
+}
+
+// Componente de `d` a lo largo del eje de la cara `face`, sin proyectar
+fn face_axis(face: usize, d: Vec3) -> f32 {
+    match face {
+        0 => d.x,
+        1 => -d.x,
+        2 => d.y,
+        3 => -d.y,
+        4 => d.z,
+        _ => -d.z,
+    }
+}
+
+// Cara del cubo hacia la que apunta `d`: la del eje de mayor componente
+fn major_face(d: Vec3) -> usize {
+    let a = d.abs();
+    if a.x >= a.y && a.x >= a.z {
+        if d.x > 0.0 { 0 } else { 1 }
+    } else if a.y >= a.z {
+        if d.y > 0.0 { 2 } else { 3 }
+    } else if d.z > 0.0 {
+        4
+    } else {
+        5
+    }
+}
+
+// Shadow map omnidireccional centrado en la luz: seis caras de size x size, en el orden de
+// bake::CUBE_FACES, con la distancia a la luz de lo más cercano y el id del objeto
+pub struct ShadowMap {
+    pub size: usize,
+    pub light_position: Vec3,
+    depth: Vec<f32>,
+    ids: Vec<u32>,
+}
+
+impl ShadowMap {
+    pub fn new(size: usize, light_position: Vec3) -> Self {
+        let size = size.max(1);
+        ShadowMap {
+            size,
+            light_position,
+            depth: vec![f32::INFINITY; 6 * size * size],
+            ids: vec![NO_CASTER; 6 * size * size],
+        }
+    }
+
+    // Vacía el mapa para volver a usarlo con la luz en `light_position`, sin reservar memoria
+    pub fn clear(&mut self, light_position: Vec3) {
+        self.light_position = light_position;
+        self.depth.fill(f32::INFINITY);
+        self.ids.fill(NO_CASTER);
+    }
+
+    // Pasada de solo profundidad: rasteriza la malla en las seis caras, sin descartar caras
+    // traseras para que las mallas abiertas también proyecten sombra
+    pub fn render(&mut self, model_matrix: &Mat4, vertices: &[Vertex], indices: &[u32], id: u32) {
+        let relative: Vec<Vec3> = vertices
+            .iter()
+            .map(|vertex| (model_matrix * vertex.position.push(1.0)).xyz() - self.light_position)
+            .collect();
+
+        for triangle in indices.chunks_exact(3) {
+            let points = [relative[triangle[0] as usize], relative[triangle[1] as usize], relative[triangle[2] as usize]];
+            for face in 0..6 {
+                self.rasterize(face, &points, id);
+            }
+        }
+    }
+
+    // Recorta el triángulo contra el plano cercano de la cara (Sutherland-Hodgman) y rasteriza
+    // el polígono que queda, de tres o cuatro vértices, como un abanico de triángulos
+    fn rasterize(&mut self, face: usize, points: &[Vec3; 3], id: u32) {
+        let axes = points.map(|point| face_axis(face, point));
+        if axes.iter().all(|&axis| axis >= NEAR) {
+            self.rasterize_clipped(face, points, id);
+            return;
+        }
+        if axes.iter().all(|&axis| axis < NEAR) {
+            return;
+        }
+
+        let mut polygon = [Vec3::zeros(); 4];
+        let mut count = 0;
+        for i in 0..3 {
+            let j = (i + 1) % 3;
+            if axes[i] >= NEAR {
+                polygon[count] = points[i];
+                count += 1;
+            }
+            if (axes[i] >= NEAR) != (axes[j] >= NEAR) {
+                let t = (NEAR - axes[i]) / (axes[j] - axes[i]);
+                polygon[count] = points[i] + (points[j] - points[i]) * t;
+                count += 1;
+            }
+        }
+
+        for k in 1..count - 1 {
+            self.rasterize_clipped(face, &[polygon[0], polygon[k], polygon[k + 1]], id);
+        }
+    }
+
+    // Rasteriza un triángulo que está entero delante del plano cercano de la cara
+    fn rasterize_clipped(&mut self, face: usize, points: &[Vec3; 3], id: u32) {
+        let size = self.size as f32;
+        let mut screen = [(0.0, 0.0, 0.0); 3];
+        for (projected, point) in screen.iter_mut().zip(points) {
+            let (s, t, axis) = face_coords(face, *point);
+            let axis = axis.max(NEAR);
+            *projected = ((s * 0.5 + 0.5) * size, (t * 0.5 + 0.5) * size, 1.0 / axis);
+        }
+
+        let [(x0, y0, w0), (x1, y1, w1), (x2, y2, w2)] = screen;
+        let min_x = x0.min(x1).min(x2).floor().max(0.0) as usize;
+        let min_y = y0.min(y1).min(y2).floor().max(0.0) as usize;
+        let max_x = x0.max(x1).max(x2).ceil().min(size - 1.0);
+        let max_y = y0.max(y1).max(y2).ceil().min(size - 1.0);
+        if max_x < 0.0 || max_y < 0.0 || min_x as f32 > max_x || min_y as f32 > max_y {
+            return;
+        }
+
+        let area = (x1 - x0) * (y2 - y0) - (y1 - y0) * (x2 - x0);
+        if area.abs() < 1e-8 {
+            return;
+        }
+
+        let offset = face * self.size * self.size;
+        for y in min_y..=max_y as usize {
+            let py = y as f32 + 0.5;
+            for x in min_x..=max_x as usize {
+                let px = x as f32 + 0.5;
+                let b0 = ((x1 - px) * (y2 - py) - (y1 - py) * (x2 - px)) / area;
+                let b1 = ((x2 - px) * (y0 - py) - (y2 - py) * (x0 - px)) / area;
+                let b2 = 1.0 - b0 - b1;
+                if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                    continue;
+                }
+
+                // Posición con corrección de perspectiva, y de ella la distancia a la luz
+                let inv = b0 * w0 + b1 * w1 + b2 * w2;
+                let position = (points[0] * (b0 * w0) + points[1] * (b1 * w1) + points[2] * (b2 * w2)) / inv;
+                let distance = position.magnitude();
+
+                let index = offset + y * self.size + x;
+                if distance < self.depth[index] {
+                    self.depth[index] = distance;
+                    self.ids[index] = id;
+                }
+            }
+        }
+    }
+
+    // PCF: la comparación de profundidad se promedia en una ventana de 3x3 texels que se
+    // desliza con la posición dentro del texel, así el borde de la sombra no se ve escalonado
+    pub fn visibility(&self, point: Vec3, id: u32) -> f32 {
+        let d = point - self.light_position;
+        let distance = d.magnitude();
+        let face = major_face(d);
+        let (s, t, _) = face_coords(face, d);
+        let size = self.size as f32;
+        let x = (s * 0.5 + 0.5) * size - 0.5;
+        let y = (t * 0.5 + 0.5) * size - 0.5;
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let (x, y) = (x.floor() as i32, y.floor() as i32);
+
+        let offset = face * self.size * self.size;
+        let threshold = distance * (1.0 - DEPTH_BIAS);
+        let last = self.size as i32 - 1;
+        let weights = |f: f32| [1.0 - f, 1.0, 1.0, f];
+
+        let mut lit = 0.0;
+        for (j, wy) in weights(fy).iter().enumerate() {
+            let ty = (y + j as i32 - 1).clamp(0, last) as usize;
+            for (i, wx) in weights(fx).iter().enumerate() {
+                let tx = (x + i as i32 - 1).clamp(0, last) as usize;
+                let index = offset + ty * self.size + tx;
+                if self.ids[index] == id || self.depth[index] >= threshold {
+                    lit += wx * wy;
+                }
+            }
+        }
+        lit / 9.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_overlap_without_contact_is_zero() {
+        assert_eq!(disk_overlap(1.0, 0.5, 1.5), 0.0);
+        assert_eq!(disk_overlap(1.0, 0.5, 3.0), 0.0);
+    }
+
+    #[test]
+    fn disk_overlap_of_a_contained_disk_is_its_area() {
+        assert!((disk_overlap(1.0, 2.0, 0.0) - PI).abs() < 1e-5);
+        assert!((disk_overlap(2.0, 0.5, 1.0) - PI * 0.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn disk_overlap_of_two_unit_disks_one_radius_apart() {
+        let expected = 2.0 * PI / 3.0 - 3.0_f32.sqrt() / 2.0;
+        assert!((disk_overlap(1.0, 1.0, 1.0) - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn triangle_crossing_the_near_plane_is_clipped_not_dropped() {
+        // Un vértice queda detrás de la luz para la cara +Z; el triángulo cruza el eje +Z en z = 0.5
+        let vertices: Vec<Vertex> = [Vec3::new(-2.0, -2.0, 2.0), Vec3::new(2.0, -2.0, 2.0), Vec3::new(0.0, 2.0, -1.0)]
+            .iter()
+            .map(|&position| Vertex::new(position, Vec3::z(), Default::default()))
+            .collect();
+        let mut map = ShadowMap::new(16, Vec3::zeros());
+        map.render(&Mat4::identity(), &vertices, &[0, 1, 2], 0);
+
+        assert_eq!(map.visibility(Vec3::new(0.0, 0.0, 1.0), 1), 0.0);
+        assert_eq!(map.visibility(Vec3::new(0.0, 0.0, 0.25), 1), 1.0);
+    }
+
+    #[test]
+    fn clear_empties_the_map() {
+        let vertices: Vec<Vertex> = [Vec3::new(-1.0, -1.0, 1.0), Vec3::new(1.0, -1.0, 1.0), Vec3::new(0.0, 1.0, 1.0)]
+            .iter()
+            .map(|&position| Vertex::new(position, Vec3::z(), Default::default()))
+            .collect();
+        let mut map = ShadowMap::new(16, Vec3::zeros());
+        map.render(&Mat4::identity(), &vertices, &[0, 1, 2], 0);
+        assert!(map.visibility(Vec3::new(0.0, 0.0, 2.0), 1) < 1.0);
+
+        map.clear(Vec3::zeros());
+        assert_eq!(map.visibility(Vec3::new(0.0, 0.0, 2.0), 1), 1.0);
+    }
+}
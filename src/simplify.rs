@@ -0,0 +1,477 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::obj::Mesh;
+
+// Simplificación por colapso de aristas con métrica de error cuadrático (Garland-Heckbert).
+//
+// Se colapsa media arista: un vértice se mueve encima de su vecino, así los vértices
+// que quedan conservan su normal y UV originales. Las posiciones se sueldan antes de
+// empezar, de modo que una costura de UV (o una arista dura) es una posición con varios
+// vértices de atributos; un colapso solo se acepta si cada uno de esos vértices tiene
+// una pareja clara del otro lado, lo que mantiene las costuras intactas.
+
+// Peso de los planos que protegen bordes abiertos y costuras
+const SEAM_WEIGHT: f64 = 1000.0;
+// Coseno mínimo entre la normal de una cara antes y después del colapso
+const MIN_FACE_ALIGNMENT: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimplifyOptions {
+    // Número de triángulos al que se quiere llegar
+    pub target_triangles: usize,
+    // Error cuadrático máximo aceptado por colapso (distancia al cuadrado a los planos originales)
+    pub max_error: f32,
+}
+
+impl SimplifyOptions {
+    pub fn new(target_triangles: usize) -> Self {
+        SimplifyOptions { target_triangles, max_error: f32::INFINITY }
+    }
+
+    pub fn with_max_error(mut self, max_error: f32) -> Self {
+        self.max_error = max_error;
+        self
+    }
+}
+
+// Cuádrica simétrica 4x4 guardada como triángulo superior
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: Vec3, d: f32, weight: f64) -> Self {
+        let (a, b, c, d) = (normal.x as f64, normal.y as f64, normal.z as f64, d as f64);
+        Quadric([
+            a * a, a * b, a * c, a * d,
+            b * b, b * c, b * d,
+            c * c, c * d,
+            d * d,
+        ].map(|x| x * weight))
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+    }
+
+    fn evaluate(&self, p: &Vec3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// Colapso candidato: mover `from` sobre `to`
+#[derive(Debug)]
+struct Candidate {
+    cost: f64,
+    from: usize,
+    to: usize,
+    stamps: (u32, u32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // BinaryHeap es de máximos: se invierte para sacar primero el menor costo
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier<'a> {
+    mesh: &'a Mesh,
+    // Posición soldada de cada vértice de atributos
+    weld: Vec<usize>,
+    positions: Vec<Vec3>,
+    faces: Vec<[u32; 3]>,
+    face_alive: Vec<bool>,
+    position_faces: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    alive: Vec<bool>,
+    stamps: Vec<u32>,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(mesh: &'a Mesh) -> Self {
+        let mut lookup: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let weld: Vec<usize> = mesh.vertices.iter().map(|p| {
+            *lookup.entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).or_insert_with(|| {
+                positions.push(*p);
+                positions.len() - 1
+            })
+        }).collect();
+
+        let faces: Vec<[u32; 3]> = mesh.indices
+            .chunks_exact(3)
+            .map(|f| [f[0], f[1], f[2]])
+            .filter(|f| {
+                let (a, b, c) = (weld[f[0] as usize], weld[f[1] as usize], weld[f[2] as usize]);
+                a != b && b != c && a != c
+            })
+            .collect();
+
+        let mut position_faces = vec![Vec::new(); positions.len()];
+        for (i, face) in faces.iter().enumerate() {
+            for &corner in face {
+                position_faces[weld[corner as usize]].push(i);
+            }
+        }
+
+        let count = positions.len();
+        let mut simplifier = Simplifier {
+            mesh,
+            weld,
+            positions,
+            face_alive: vec![true; faces.len()],
+            faces,
+            position_faces,
+            quadrics: vec![Quadric::default(); count],
+            alive: vec![true; count],
+            stamps: vec![0; count],
+            heap: BinaryHeap::new(),
+        };
+        simplifier.build_quadrics();
+        simplifier
+    }
+
+    fn corner_positions(&self, face: usize) -> [usize; 3] {
+        self.faces[face].map(|corner| self.weld[corner as usize])
+    }
+
+    fn face_normal(&self, p: [Vec3; 3]) -> Vec3 {
+        (p[1] - p[0]).cross(&(p[2] - p[0]))
+    }
+
+    fn build_quadrics(&mut self) {
+        // Plano de cada cara, ponderado por su área
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for face in 0..self.faces.len() {
+            let corners = self.corner_positions(face);
+            let p = corners.map(|i| self.positions[i]);
+            let normal = self.face_normal(p);
+            let area = normal.magnitude();
+            if area <= f32::EPSILON {
+                continue;
+            }
+            let n = normal / area;
+            let plane = Quadric::from_plane(n, -n.dot(&p[0]), area as f64 * 0.5);
+            for &c in &corners {
+                self.quadrics[c].add(&plane);
+            }
+
+            for k in 0..3 {
+                let (a, b) = (corners[k], corners[(k + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_default().push(face);
+            }
+        }
+
+        // Bordes abiertos y costuras: plano perpendicular a la cara que pasa por la arista
+        for (&(a, b), faces) in &edges {
+            let is_boundary = faces.len() == 1;
+            let is_seam = !is_boundary && {
+                let attributes: HashSet<[u32; 2]> = faces.iter().map(|&f| self.edge_attributes(f, a, b)).collect();
+                attributes.len() > 1
+            };
+            if !is_boundary && !is_seam {
+                continue;
+            }
+
+            let (pa, pb) = (self.positions[a], self.positions[b]);
+            for &face in faces {
+                let p = self.corner_positions(face).map(|i| self.positions[i]);
+                let face_normal = self.face_normal(p).normalize();
+                let edge = pb - pa;
+                let perpendicular = edge.cross(&face_normal);
+                if perpendicular.magnitude() <= f32::EPSILON {
+                    continue;
+                }
+                let n = perpendicular.normalize();
+                let plane = Quadric::from_plane(n, -n.dot(&pa), SEAM_WEIGHT * edge.magnitude_squared() as f64);
+                self.quadrics[a].add(&plane);
+                self.quadrics[b].add(&plane);
+            }
+        }
+
+        for (&(a, b), _) in edges.iter() {
+            self.push_candidates(a, b);
+        }
+    }
+
+    // Vértices de atributos de la arista (a, b) dentro de una cara, en orden de posición
+    fn edge_attributes(&self, face: usize, a: usize, b: usize) -> [u32; 2] {
+        let mut out = [0; 2];
+        for &corner in &self.faces[face] {
+            let position = self.weld[corner as usize];
+            if position == a {
+                out[0] = corner;
+            } else if position == b {
+                out[1] = corner;
+            }
+        }
+        out
+    }
+
+    fn push_candidates(&mut self, a: usize, b: usize) {
+        for (from, to) in [(a, b), (b, a)] {
+            let mut q = self.quadrics[from];
+            q.add(&self.quadrics[to]);
+            self.heap.push(Candidate {
+                cost: q.evaluate(&self.positions[to]).max(0.0),
+                from,
+                to,
+                stamps: (self.stamps[from], self.stamps[to]),
+            });
+        }
+    }
+
+    fn live_faces(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        self.position_faces[position].iter().copied().filter(|&f| self.face_alive[f])
+    }
+
+    fn neighbors(&self, position: usize) -> HashSet<usize> {
+        self.live_faces(position)
+            .flat_map(|f| self.corner_positions(f))
+            .filter(|&p| p != position)
+            .collect()
+    }
+
+    // Para cada vértice de atributos de `from`, el de `to` que lo reemplaza;
+    // None si el colapso rompería una costura, voltearía caras o dejaría la malla no-manifold
+    fn collapse_map(&self, from: usize, to: usize) -> Option<HashMap<u32, u32>> {
+        let mut map: HashMap<u32, u32> = HashMap::new();
+        let mut shared_faces = 0;
+
+        for face in self.live_faces(from) {
+            let corners = self.corner_positions(face);
+            if !corners.contains(&to) {
+                continue;
+            }
+            shared_faces += 1;
+            let [a_from, a_to] = self.edge_attributes(face, from, to);
+            if *map.entry(a_from).or_insert(a_to) != a_to {
+                return None;
+            }
+        }
+        if shared_faces == 0 {
+            return None;
+        }
+
+        // Condición de enlace: los vecinos comunes deben ser solo los de las caras compartidas
+        let common = self.neighbors(from).intersection(&self.neighbors(to)).count();
+        if common != shared_faces {
+            return None;
+        }
+
+        for face in self.live_faces(from) {
+            let corners = self.corner_positions(face);
+            if corners.contains(&to) {
+                continue;
+            }
+
+            let corner = self.faces[face].iter().find(|&&c| self.weld[c as usize] == from)?;
+            if !map.contains_key(corner) {
+                return None;
+            }
+
+            let before = corners.map(|i| self.positions[i]);
+            let after = corners.map(|i| if i == from { self.positions[to] } else { self.positions[i] });
+            let (n0, n1) = (self.face_normal(before), self.face_normal(after));
+            if n1.magnitude() <= f32::EPSILON || n0.normalize().dot(&n1.normalize()) < MIN_FACE_ALIGNMENT {
+                return None;
+            }
+        }
+
+        Some(map)
+    }
+
+    fn collapse(&mut self, from: usize, to: usize, map: &HashMap<u32, u32>) -> usize {
+        let mut removed = 0;
+        let faces: Vec<usize> = self.live_faces(from).collect();
+
+        for face in faces {
+            if self.corner_positions(face).contains(&to) {
+                self.face_alive[face] = false;
+                removed += 1;
+                continue;
+            }
+            for corner in self.faces[face].iter_mut() {
+                if let Some(&replacement) = map.get(corner) {
+                    *corner = replacement;
+                }
+            }
+            self.position_faces[to].push(face);
+        }
+
+        let q = self.quadrics[from];
+        self.quadrics[to].add(&q);
+        self.alive[from] = false;
+        self.stamps[to] += 1;
+
+        for neighbor in self.neighbors(to) {
+            self.push_candidates(to, neighbor);
+        }
+
+        removed
+    }
+
+    fn run(&mut self, target_triangles: usize, max_error: f32) {
+        let mut triangles = self.faces.len();
+
+        while triangles > target_triangles {
+            let candidate = match self.heap.pop() {
+                Some(candidate) => candidate,
+                None => break,
+            };
+            let (from, to) = (candidate.from, candidate.to);
+            if !self.alive[from] || !self.alive[to] || candidate.stamps != (self.stamps[from], self.stamps[to]) {
+                continue;
+            }
+            if candidate.cost > max_error as f64 {
+                break;
+            }
+
+            if let Some(map) = self.collapse_map(from, to) {
+                triangles -= self.collapse(from, to, &map);
+            }
+        }
+    }
+
+    // Copia compacta con los vértices que siguen en uso. Cada vértice de atributos (una
+    // esquina con su posición, normal y UV) se copia entero desde el mismo índice, así las
+    // normales y UV quedan alineadas con las posiciones también en las costuras. Si el mesh
+    // original no tiene un atributo para cada vértice, ese atributo se descarta.
+    fn into_mesh(self) -> Mesh {
+        let source = self.mesh;
+        let has_normals = source.normals.len() == source.vertices.len();
+        let has_texcoords = source.texcoords.len() == source.vertices.len();
+
+        let mut remap: HashMap<u32, u32> = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut texcoords: Vec<Vec2> = Vec::new();
+        let mut indices = Vec::new();
+
+        for (face, corners) in self.faces.iter().enumerate() {
+            if !self.face_alive[face] {
+                continue;
+            }
+            for &corner in corners {
+                let index = *remap.entry(corner).or_insert_with(|| {
+                    let i = corner as usize;
+                    vertices.push(source.vertices[i]);
+                    if has_normals {
+                        normals.push(source.normals[i]);
+                    }
+                    if has_texcoords {
+                        texcoords.push(source.texcoords[i]);
+                    }
+                    vertices.len() as u32 - 1
+                });
+                indices.push(index);
+            }
+        }
+
+        Mesh {
+            name: source.name.clone(),
+            material: source.material,
+            vertices,
+            normals,
+            texcoords,
+            indices,
+        }
+    }
+}
+
+pub fn simplify_mesh(mesh: &Mesh, options: &SimplifyOptions) -> Mesh {
+    let mut simplifier = Simplifier::new(mesh);
+    simplifier.run(options.target_triangles, options.max_error);
+    simplifier.into_mesh()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    fn sphere() -> Mesh {
+        let (vertices, indices) = primitives::uv_sphere(1.0, 32, 16);
+        Mesh::from_vertex_arrays("esfera", &vertices, &indices)
+    }
+
+    fn key(p: Vec3, uv: Vec2) -> [u32; 5] {
+        [p.x.to_bits(), p.y.to_bits(), p.z.to_bits(), uv.x.to_bits(), uv.y.to_bits()]
+    }
+
+    #[test]
+    fn reaches_the_target_triangle_count() {
+        let mesh = sphere();
+        let before = mesh.indices.len() / 3;
+        for target in [before / 2, before / 4] {
+            let simplified = simplify_mesh(&mesh, &SimplifyOptions::new(target));
+            let triangles = simplified.indices.len() / 3;
+            assert!(triangles <= target, "{} triángulos para un objetivo de {}", triangles, target);
+            assert!(triangles + 4 >= target, "{} triángulos para un objetivo de {}", triangles, target);
+        }
+    }
+
+    #[test]
+    fn max_error_stops_early() {
+        let mesh = sphere();
+        let simplified = simplify_mesh(&mesh, &SimplifyOptions::new(0).with_max_error(0.0));
+        assert_eq!(simplified.indices.len(), mesh.indices.len());
+    }
+
+    #[test]
+    fn keeps_the_uv_seam() {
+        let mesh = sphere();
+        let simplified = simplify_mesh(&mesh, &SimplifyOptions::new(mesh.indices.len() / 3 / 4));
+
+        // Cada vértice que queda es uno de los originales con sus propios atributos
+        let original: HashSet<[u32; 5]> = mesh.vertices.iter().zip(&mesh.texcoords).map(|(p, uv)| key(*p, *uv)).collect();
+        assert_eq!(simplified.normals.len(), simplified.vertices.len());
+        assert_eq!(simplified.texcoords.len(), simplified.vertices.len());
+        for (p, uv) in simplified.vertices.iter().zip(&simplified.texcoords) {
+            assert!(original.contains(&key(*p, *uv)));
+        }
+
+        // Ningún triángulo cruza la costura mezclando u = 0 con u = 1
+        for face in simplified.indices.chunks_exact(3) {
+            let u = face.iter().map(|&i| simplified.texcoords[i as usize].x);
+            let (min, max) = u.fold((f32::MAX, f32::MIN), |(min, max), u| (min.min(u), max.max(u)));
+            assert!(max - min < 0.5, "triángulo con u entre {} y {}", min, max);
+        }
+
+        // La costura sigue teniendo vértices a ambos lados
+        assert!(simplified.texcoords.iter().any(|uv| uv.x == 0.0));
+        assert!(simplified.texcoords.iter().any(|uv| uv.x == 1.0));
+    }
+
+    #[test]
+    fn drops_attributes_missing_for_some_vertices() {
+        let mut mesh = sphere();
+        mesh.normals.truncate(10);
+        let simplified = simplify_mesh(&mesh, &SimplifyOptions::new(mesh.indices.len() / 3 / 2));
+        assert!(simplified.normals.is_empty());
+        assert_eq!(simplified.texcoords.len(), simplified.vertices.len());
+    }
+}
@@ -0,0 +1,100 @@
+use nalgebra_glm::Vec3;
+use crate::noise::Rng;
+use crate::planet::PlanetParams;
+
+// Estrellas: la clase espectral decide el color de shade_star, el tamaño, la masa (que fija
+// las velocidades orbitales de system.rs) y la luminosidad, que escala la luz del sol.
+
+// Clase espectral de la estrella, de la más fría a la más caliente
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarClass {
+    M,
+    K,
+    G,
+    F,
+    A,
+    B,
+}
+
+impl StarClass {
+    pub const ALL: [StarClass; 6] = [StarClass::M, StarClass::K, StarClass::G, StarClass::F, StarClass::A, StarClass::B];
+
+    pub fn letter(&self) -> char {
+        match self {
+            StarClass::M => 'M',
+            StarClass::K => 'K',
+            StarClass::G => 'G',
+            StarClass::F => 'F',
+            StarClass::A => 'A',
+            StarClass::B => 'B',
+        }
+    }
+
+    // Colores de shade_star: núcleo, bordes de la granulación (y de la corona) y llamaradas
+    pub fn palette(&self) -> [Vec3; 4] {
+        let rgb = Vec3::new;
+        let [core, edge, flame] = match self {
+            StarClass::M => [rgb(1.0, 0.45, 0.2), rgb(0.9, 0.25, 0.05), rgb(1.0, 0.3, 0.05)],
+            StarClass::K => [rgb(1.0, 0.7, 0.3), rgb(1.0, 0.45, 0.1), rgb(1.0, 0.4, 0.0)],
+            StarClass::G => [rgb(1.0, 0.9, 0.3), rgb(1.0, 0.6, 0.1), rgb(1.0, 0.5, 0.0)],
+            StarClass::F => [rgb(1.0, 0.97, 0.75), rgb(1.0, 0.8, 0.4), rgb(1.0, 0.7, 0.3)],
+            StarClass::A => [rgb(0.9, 0.93, 1.0), rgb(0.75, 0.8, 1.0), rgb(0.85, 0.9, 1.0)],
+            StarClass::B => [rgb(0.65, 0.75, 1.0), rgb(0.45, 0.55, 1.0), rgb(0.6, 0.7, 1.0)],
+        };
+        [core, edge, flame, core]
+    }
+
+    // Parámetros de superficie de shade_star, de la corona y de las protuberancias: de ellos
+    // solo leen la paleta
+    pub fn surface(&self) -> PlanetParams {
+        PlanetParams::from_palette(self.palette())
+    }
+
+    // Radio en unidades de la escena; el sol de la práctica mide 5
+    pub fn radius(&self) -> f32 {
+        match self {
+            StarClass::M => 2.5,
+            StarClass::K => 3.8,
+            StarClass::G => 5.0,
+            StarClass::F => 5.5,
+            StarClass::A => 6.2,
+            StarClass::B => 7.0,
+        }
+    }
+
+    // Luminosidad y masa relativas al sol (la luminosidad muy comprimida)
+    pub fn luminosity(&self) -> f32 {
+        match self {
+            StarClass::M => 0.5,
+            StarClass::K => 0.75,
+            StarClass::G => 1.0,
+            StarClass::F => 1.3,
+            StarClass::A => 1.8,
+            StarClass::B => 2.5,
+        }
+    }
+
+    pub fn mass(&self) -> f32 {
+        match self {
+            StarClass::M => 0.4,
+            StarClass::K => 0.75,
+            StarClass::G => 1.0,
+            StarClass::F => 1.3,
+            StarClass::A => 2.0,
+            StarClass::B => 5.0,
+        }
+    }
+
+    // Las estrellas frías son mucho más comunes que las calientes
+    pub fn random(rng: &mut Rng) -> StarClass {
+        let weights = [0.35, 0.25, 0.2, 0.1, 0.07, 0.03];
+        let mut pick = rng.next();
+        for (class, weight) in StarClass::ALL.into_iter().zip(weights) {
+            if pick < weight {
+                return class;
+            }
+            pick -= weight;
+        }
+        StarClass::M
+    }
+}
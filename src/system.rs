@@ -0,0 +1,244 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::noise::{Noise, Rng};
+use crate::planet::{PlanetClass, PlanetParams};
+use crate::scene::{CelestialBody, Scene};
+use crate::shaders::{Atmosphere, Clouds, Corona, Rings};
+use crate::star::StarClass;
+
+// Generador de sistemas estelares: a partir de una semilla elige la estrella y una serie de
+// planetas con órbitas ordenadas, tamaños, lunas, anillos y clases según lo lejos que están
+// de la estrella. Las distancias están comprimidas para que todo quepa en la escena.
+
+// Radio máximo de las órbitas, para que el sistema quepa en el plano lejano de la cámara
+const MAX_ORBIT: f32 = 40.0;
+const MOON_NAMES: [&str; 3] = ["I", "II", "III"];
+
+// Sistema completo de la semilla: la estrella en el origen, sus planetas de dentro hacia
+// fuera y las lunas de cada planeta detrás de él
+pub fn generate_system(seed: u32) -> Scene {
+    let mut rng = Rng::new(Noise::new(seed).derive(0x5157));
+    let star = StarClass::random(&mut rng);
+    let star_name = format!("{}-{}", star.letter(), seed);
+    let star_radius = star.radius() * rng.range(0.9, 1.1);
+
+    // La corona sale de su propia secuencia para no cambiar el resto del sistema
+    let mut corona_rng = Rng::new(Noise::new(seed).derive(0xC080));
+    let corona = Corona::new(corona_rng.range(1.8, 2.6), corona_rng.range(0.4, 1.0), corona_rng.range_int(1, 4));
+
+    let mut bodies = vec![
+        CelestialBody::new(&star_name, 0, star_radius)
+            .with_seed(rng.next_u32())
+            .with_star(star)
+            .with_corona(corona),
+    ];
+
+    // Distancia a la que un planeta recibe tanta luz como la Tierra
+    let temperate = (star_radius + 4.0) * star.luminosity().sqrt();
+    let planet_count = rng.range_int(2, 8);
+    let (mut last_orbit, mut last_edge) = (star_radius, star_radius + 0.5);
+
+    for i in 0..planet_count {
+        // Las órbitas se separan cada vez más, como en la ley de Titius-Bode
+        let tentative = (last_edge + rng.range(1.0, 2.5)).max(last_orbit * rng.range(1.15, 1.35));
+        let heat = (temperate / tentative).powi(2);
+        let class = planet_class(heat, &mut rng);
+        let seed = rng.next_u32();
+
+        let scale = match class {
+            PlanetClass::GasGiant => rng.range(1.0, 1.8),
+            PlanetClass::Ice => rng.range(0.5, 1.1),
+            PlanetClass::Rocky => rng.range(0.5, 1.0),
+            PlanetClass::Desert => rng.range(0.4, 0.8),
+            _ => rng.range(0.4, 0.9),
+        };
+        let rings = match class {
+            PlanetClass::GasGiant => rng.chance(0.5),
+            PlanetClass::Ice => rng.chance(0.15),
+            _ => false,
+        }
+        .then(|| {
+            let tilt = Vec3::new(rng.range(-0.5, 0.5), 0.0, rng.range(-0.3, 0.3));
+            Rings::new(rng.range(1.2, 1.5), rng.range(1.9, 2.6), tilt)
+        });
+        let moon_count = match class {
+            PlanetClass::GasGiant => rng.range_int(0, 3),
+            PlanetClass::Ice => rng.range_int(0, 2),
+            PlanetClass::Rocky => rng.chance(0.5) as u32,
+            _ => rng.chance(0.3) as u32,
+        };
+
+        // Las lunas van por fuera de los anillos, cada una más lejos que la anterior
+        let mut moon_orbits = Vec::new();
+        let mut moon_edge = scale * rings.map_or(1.0, |rings| rings.outer_radius);
+        for _ in 0..moon_count {
+            let moon_scale = (scale * rng.range(0.15, 0.3)).max(0.1);
+            let orbit = moon_edge + moon_scale + rng.range(0.3, 0.8);
+            moon_edge = orbit + moon_scale;
+            moon_orbits.push((orbit, moon_scale));
+        }
+        let reach = moon_edge;
+
+        let orbit = tentative.max(last_edge + reach + 0.5);
+        if orbit + reach > MAX_ORBIT {
+            break;
+        }
+        last_orbit = orbit;
+        last_edge = orbit + reach;
+
+        // Tercera ley de Kepler: la velocidad angular cae con r^1.5
+        let orbit_speed = 0.3 * (8.0 / orbit).powf(1.5) * star.mass().sqrt();
+        let name = format!("{} {}", star_name, (b'b' + i as u8) as char);
+        let params = PlanetParams::generate(class, seed);
+        let spin = rng.range(0.2, 0.8);
+        let mut planet = CelestialBody::new(&name, 0, scale)
+            .with_orbit(orbit, orbit_speed, rng.range(0.0, 2.0 * PI), rng.range(-0.4, 0.4))
+            .with_spin(spin)
+            .with_seed(seed)
+            .with_planet(params);
+        if let Some(atmosphere) = atmosphere(class, &mut rng) {
+            planet = planet.with_atmosphere(atmosphere);
+        }
+        // Los rocosos llevan sus nubes en una capa aparte, que gira algo más rápido que el suelo
+        if class == PlanetClass::Rocky && params.cloud_cover > 0.05 {
+            planet = planet.with_clouds(Clouds::new(0.08, spin * rng.range(1.1, 1.5)));
+        }
+        if let Some(rings) = rings {
+            planet = planet.with_rings(rings);
+        }
+
+        let moons: Vec<CelestialBody> = moon_orbits.iter().enumerate().map(|(m, &(moon_orbit, moon_scale))| {
+            // Lunas heladas lejos de la estrella; alguna volcánica por las mareas, como Ío
+            let moon_class = if rng.chance(0.15) {
+                PlanetClass::Volcanic
+            } else if heat < 0.5 {
+                PlanetClass::Ice
+            } else {
+                PlanetClass::Desert
+            };
+            let moon_seed = rng.next_u32();
+            CelestialBody::new(&format!("{} {}", name, MOON_NAMES[m]), 0, moon_scale)
+                .with_orbit(moon_orbit, rng.range(0.8, 1.6), rng.range(0.0, 2.0 * PI), 0.0)
+                .with_spin(rng.range(0.1, 0.5))
+                .with_seed(moon_seed)
+                .with_planet(PlanetParams::generate(moon_class, moon_seed))
+                .with_primary(&planet)
+        }).collect();
+
+        bodies.push(planet);
+        bodies.extend(moons);
+    }
+
+    Scene {
+        name: format!("Sistema {} (estrella de clase {})", star_name, star.letter()),
+        bodies,
+        belt: None,
+        camera_position: Vec3::new(0.0, last_edge * 0.3, last_edge * 1.1),
+    }
+}
+
+// Clase de un planeta según la luz que recibe (1 = como la Tierra)
+fn planet_class(heat: f32, rng: &mut Rng) -> PlanetClass {
+    let pick = rng.next();
+    let choose = |options: &[(PlanetClass, f32)]| {
+        let mut total = 0.0;
+        for &(class, probability) in options {
+            total += probability;
+            if pick < total {
+                return class;
+            }
+        }
+        options[options.len() - 1].0
+    };
+
+    if heat > 2.0 {
+        choose(&[(PlanetClass::Volcanic, 0.6), (PlanetClass::Desert, 0.4)])
+    } else if heat > 1.0 {
+        choose(&[(PlanetClass::Desert, 0.4), (PlanetClass::Rocky, 0.4), (PlanetClass::Volcanic, 0.2)])
+    } else if heat > 0.5 {
+        choose(&[(PlanetClass::Rocky, 0.6), (PlanetClass::Desert, 0.25), (PlanetClass::Ice, 0.15)])
+    } else if heat > 0.2 {
+        choose(&[(PlanetClass::GasGiant, 0.45), (PlanetClass::Ice, 0.35), (PlanetClass::Rocky, 0.2)])
+    } else {
+        choose(&[(PlanetClass::GasGiant, 0.55), (PlanetClass::Ice, 0.45)])
+    }
+}
+
+fn atmosphere(class: PlanetClass, rng: &mut Rng) -> Option<Atmosphere> {
+    let (probability, color) = match class {
+        PlanetClass::Rocky => (0.85, Vec3::new(0.35, 0.6, 1.0)),
+        PlanetClass::Desert => (0.4, Vec3::new(1.0, 0.6, 0.25)),
+        PlanetClass::Ice => (0.3, Vec3::new(0.7, 0.85, 1.0)),
+        PlanetClass::Volcanic => (0.3, Vec3::new(1.0, 0.45, 0.2)),
+        _ => return None,
+    };
+    if !rng.chance(probability) {
+        return None;
+    }
+    let tint = Vec3::new(rng.range(0.9, 1.1), rng.range(0.9, 1.1), rng.range(0.9, 1.1));
+    Some(Atmosphere::new(color.component_mul(&tint).map(|c| c.min(1.0)), rng.range(0.06, 0.14), rng.range(0.6, 1.4)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lo que define a cada cuerpo, para comparar dos sistemas
+    fn describe(scene: &Scene) -> Vec<String> {
+        scene.bodies.iter().map(|body| {
+            format!("{} {:?} {:?} {} {} {:?} {:?}", body.name, body.orbit, body.primary, body.scale, body.seed, body.planet, body.rings)
+        }).collect()
+    }
+
+    // Cada planeta con sus lunas y lo lejos que llega lo suyo (superficie, anillos o lunas)
+    fn planets(scene: &Scene) -> Vec<(&CelestialBody, Vec<&CelestialBody>, f32)> {
+        let mut planets: Vec<(&CelestialBody, Vec<&CelestialBody>, f32)> = Vec::new();
+        for body in scene.bodies.iter().filter(|body| body.star.is_none()) {
+            match body.primary {
+                Some(_) => planets.last_mut().expect("una luna va después de su planeta").1.push(body),
+                None => planets.push((body, Vec::new(), 0.0)),
+            }
+        }
+        for (planet, moons, reach) in &mut planets {
+            let rings = planet.rings.map_or(1.0, |rings| rings.outer_radius);
+            *reach = moons.iter().map(|moon| moon.orbit.radius + moon.scale).fold(planet.scale * rings, f32::max);
+        }
+        planets
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_system() {
+        for seed in [0, 1, 42, 1234, u32::MAX] {
+            assert_eq!(describe(&generate_system(seed)), describe(&generate_system(seed)));
+        }
+        assert_ne!(describe(&generate_system(1)), describe(&generate_system(2)));
+    }
+
+    #[test]
+    fn orbits_are_ordered_and_do_not_overlap() {
+        for seed in 0..300 {
+            let scene = generate_system(seed);
+            let star = &scene.bodies[0];
+            assert!(star.star.is_some());
+
+            let planets = planets(&scene);
+            assert!(!planets.is_empty(), "semilla {}", seed);
+            let mut last_edge = star.scale;
+            for (planet, moons, reach) in &planets {
+                let orbit = planet.orbit.radius;
+                assert!(orbit - reach > last_edge, "semilla {}: {} se cruza con el anterior", seed, planet.name);
+                assert!(orbit + reach <= MAX_ORBIT, "semilla {}: {} pasa de MAX_ORBIT", seed, planet.name);
+                last_edge = orbit + reach;
+
+                // Las lunas, por fuera de los anillos y cada una más lejos que la anterior
+                let rings = planet.scale * planet.rings.map_or(1.0, |rings| rings.outer_radius);
+                let mut moon_edge = rings;
+                for moon in moons {
+                    assert_eq!(moon.primary.map(|orbit| orbit.radius), Some(orbit));
+                    assert!(moon.orbit.radius - moon.scale > moon_edge, "semilla {}: {}", seed, moon.name);
+                    moon_edge = moon.orbit.radius + moon.scale;
+                }
+            }
+        }
+    }
+}
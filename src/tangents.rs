@@ -0,0 +1,162 @@
+use nalgebra_glm::{Vec3, Vec4};
+use std::collections::HashMap;
+use crate::vertex::Vertex;
+
+// Tangentes por vértice para mapas de normales, con MikkTSpace (crate bevy_mikktspace): el
+// mismo algoritmo con el que Blender, Substance y glTF hornean los mapas, así que un mapa
+// horneado afuera se ve igual acá. En w queda el signo de la bitangente: B = w * (N x T).
+//
+// MikkTSpace calcula una tangente por esquina de cada cara. Las esquinas de un mismo vértice
+// pueden quedar con tangentes distintas (UV espejados, aristas con quiebre en UV); en ese
+// caso el vértice se separa en una copia por tangente y los índices pasan a la copia.
+
+// Vista de la malla para MikkTSpace; `corners` guarda la tangente de cada esquina
+struct Geometry<'a> {
+    vertices: &'a [Vertex],
+    indices: &'a [u32],
+    corners: Vec<Option<[f32; 4]>>,
+}
+
+impl Geometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl bevy_mikktspace::Geometry for Geometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).tex_coords.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corners[face * 3 + vert] = Some(tangent);
+    }
+}
+
+pub fn generate_tangents(vertices: &mut Vec<Vertex>, indices: &mut [u32]) {
+    let mut geometry = Geometry { vertices, indices, corners: vec![None; indices.len() / 3 * 3] };
+    bevy_mikktspace::generate_tangents(&mut geometry);
+    let corners = geometry.corners;
+
+    // Copias de cada vértice ya separado, por tangente (en bits, para poder compararlas)
+    let mut copies: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+    let mut assigned = vec![false; vertices.len()];
+
+    for (index, corner) in indices.iter_mut().zip(corners) {
+        let vertex = *index as usize;
+        // Sin tangente (cara degenerada): cualquier dirección perpendicular a la normal
+        let tangent = corner.map(Vec4::from).unwrap_or_else(|| any_perpendicular(&vertices[vertex].normal).push(1.0));
+        let key = (*index, tangent.map(f32::to_bits).into());
+
+        if !assigned[vertex] {
+            assigned[vertex] = true;
+            vertices[vertex].tangent = tangent;
+            copies.insert(key, *index);
+        } else if let Some(&copy) = copies.get(&key) {
+            *index = copy;
+        } else {
+            let mut copy = vertices[vertex].clone();
+            copy.tangent = tangent;
+            vertices.push(copy);
+            let copy = vertices.len() as u32 - 1;
+            copies.insert(key, copy);
+            *index = copy;
+        }
+    }
+
+    // Vértices que no usa ninguna cara
+    for (vertex, assigned) in vertices.iter_mut().zip(assigned) {
+        if !assigned {
+            vertex.tangent = any_perpendicular(&vertex.normal).push(1.0);
+        }
+    }
+}
+
+// Vértices sin UV útiles: cualquier dirección perpendicular a la normal
+fn any_perpendicular(normal: &Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    let t = axis - normal * normal.dot(&axis);
+    match t.try_normalize(1e-8) {
+        Some(t) => t,
+        None => Vec3::x(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+
+    #[test]
+    fn mirrored_uvs_split_the_shared_vertices() {
+        // Dos quads en el plano XY que comparten la arista x = 0. En el izquierdo u crece hacia
+        // +X y en el derecho hacia -X (UV espejados, como una nave simétrica); v crece hacia +Y
+        // en los dos. MikkTSpace da T = +X, w = 1 a la izquierda y T = -X, w = -1 a la derecha
+        let vertex = |x: f32, y: f32| Vertex::new(Vec3::new(x, y, 0.0), Vec3::z(), Vec2::new(1.0 - x.abs(), y));
+        let mut vertices = vec![
+            vertex(-1.0, 0.0),
+            vertex(0.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(-1.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+        let mut indices = [0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2];
+        generate_tangents(&mut vertices, &mut indices);
+
+        // Los dos vértices de la costura se separan en una copia por lado
+        assert_eq!(vertices.len(), 8);
+        for (face, &index) in indices.iter().enumerate().map(|(i, index)| (i / 3, index)) {
+            let tangent = vertices[index as usize].tangent;
+            let expected = if face < 2 { Vec4::new(1.0, 0.0, 0.0, 1.0) } else { Vec4::new(-1.0, 0.0, 0.0, -1.0) };
+            assert!((tangent - expected).magnitude() < 1e-5, "cara {}: {:?}", face, tangent);
+        }
+        assert_ne!(indices[1], indices[6]);
+        assert_eq!(vertices[indices[1] as usize].position, vertices[indices[6] as usize].position);
+    }
+
+    #[test]
+    fn smooth_uvs_share_one_tangent_per_vertex() {
+        // Un quad sin costura: nada se separa y la bitangente sigue a +v
+        let vertex = |x: f32, y: f32| Vertex::new(Vec3::new(x, y, 0.0), Vec3::z(), Vec2::new(x, 1.0 - y));
+        let mut vertices = vec![vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(0.0, 1.0)];
+        let mut indices = [0, 1, 2, 0, 2, 3];
+        generate_tangents(&mut vertices, &mut indices);
+
+        assert_eq!(vertices.len(), 4);
+        for vertex in &vertices {
+            // v crece hacia -Y, así que B = -Y = -(N x T)
+            assert!((vertex.tangent - Vec4::new(1.0, 0.0, 0.0, -1.0)).magnitude() < 1e-5, "{:?}", vertex.tangent);
+        }
+    }
+
+    #[test]
+    fn degenerate_faces_get_a_perpendicular_tangent() {
+        let vertex = |x: f32| Vertex::new(Vec3::new(x, 0.0, 0.0), Vec3::z(), Vec2::new(0.5, 0.5));
+        let mut vertices = vec![vertex(0.0), vertex(1.0), vertex(2.0), vertex(3.0)];
+        let mut indices = [0, 1, 2];
+        generate_tangents(&mut vertices, &mut indices);
+
+        for vertex in &vertices {
+            assert!(vertex.tangent.iter().all(|c| c.is_finite()));
+            assert!(vertex.tangent.xyz().dot(&vertex.normal).abs() < 1e-5);
+            assert!((vertex.tangent.xyz().magnitude() - 1.0).abs() < 1e-5);
+        }
+    }
+}
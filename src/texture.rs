@@ -0,0 +1,98 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+// Imagen RGB en punto flotante, fila 0 arriba (la misma convención que las UV del renderer)
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize) -> Self {
+        Texture { width, height, pixels: vec![Vec3::zeros(); width * height] }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Vec3) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    // Muestreo bilineal; u se repite (costura de la esfera) y v se recorta en los polos
+    pub fn sample(&self, uv: Vec2) -> Vec3 {
+        let x = uv.x.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = (uv.y.clamp(0.0, 1.0) * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let column = |i: f32| (i as isize).rem_euclid(self.width as isize) as usize;
+        let (c0, c1) = (column(x0), column(x0 + 1.0));
+        let (r0, r1) = (y0 as usize, (y0 as usize + 1).min(self.height - 1));
+
+        let top = self.get(c0, r0).lerp(&self.get(c1, r0), tx);
+        let bottom = self.get(c0, r1).lerp(&self.get(c1, r1), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    // Lee un TGA sin comprimir de 24 o 32 bits, como los que escribe save_tga. Así se cargan
+    // los mapas de los materiales (ver Material::load_maps), como los de `export --bake`
+    pub fn load_tga(filename: &str) -> io::Result<Texture> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let data = fs::read(filename)?;
+        if data.len() < 18 || data[2] != 2 || data[1] != 0 {
+            return Err(invalid("solo se leen TGA de color verdadero sin comprimir"));
+        }
+
+        let width = u16::from_le_bytes([data[12], data[13]]) as usize;
+        let height = u16::from_le_bytes([data[14], data[15]]) as usize;
+        let bytes_per_pixel = match data[16] {
+            24 => 3,
+            32 => 4,
+            _ => return Err(invalid("solo se leen TGA de 24 o 32 bits")),
+        };
+        let top_down = data[17] & 0x20 != 0;
+        let start = 18 + data[0] as usize;
+        let pixels = data
+            .get(start..start + width * height * bytes_per_pixel)
+            .ok_or_else(|| invalid("el TGA está truncado"))?;
+        if width == 0 || height == 0 {
+            return Err(invalid("el TGA no tiene píxeles"));
+        }
+
+        let mut texture = Texture::new(width, height);
+        for (i, pixel) in pixels.chunks_exact(bytes_per_pixel).enumerate() {
+            let (x, y) = (i % width, i / width);
+            let y = if top_down { y } else { height - 1 - y };
+            let color = Vec3::new(pixel[2] as f32, pixel[1] as f32, pixel[0] as f32) / 255.0;
+            texture.set(x, y, color);
+        }
+        Ok(texture)
+    }
+
+    // TGA de 24 bits sin comprimir: no necesita dependencias y lo abren Blender y casi
+    // cualquier editor. Los valores fuera de [0, 1] se recortan.
+    pub fn save_tga(&self, filename: &str) -> io::Result<()> {
+        if self.width > u16::MAX as usize || self.height > u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "TGA admite como máximo 65535 píxeles por lado"));
+        }
+
+        let mut out = BufWriter::new(File::create(filename)?);
+        let mut header = [0u8; 18];
+        header[2] = 2; // Color verdadero sin comprimir
+        header[12..14].copy_from_slice(&(self.width as u16).to_le_bytes());
+        header[14..16].copy_from_slice(&(self.height as u16).to_le_bytes());
+        header[16] = 24;
+        header[17] = 0x20; // Origen arriba a la izquierda
+        out.write_all(&header)?;
+
+        let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        for pixel in &self.pixels {
+            out.write_all(&[to_byte(pixel.z), to_byte(pixel.y), to_byte(pixel.x)])?;
+        }
+        out.flush()
+    }
+}
@@ -1,10 +1,9 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::Vec3;
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
 use crate::Uniforms;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, uniforms: &Uniforms) -> Vec<Fragment> {
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, _uniforms: &Uniforms) -> Vec<Fragment> {
   let mut fragments = Vec::new();
 
   // Perform perspective division to get screen-space coordinates
@@ -78,16 +77,22 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, uniforms: &Uniforms) -> V
         let w = 1.0/inv_w;
 
         let vertex_position = (v1.position * (w1 / a_w) + v2.position * (w2 / b_w) + v3.position * (w3 / c_w)) * w;
+        let world_position = (v1.world_position * (w1 / a_w) + v2.world_position * (w2 / b_w) + v3.world_position * (w3 / c_w)) * w;
+        let normal = (v1.transformed_normal * (w1 / a_w) + v2.transformed_normal * (w2 / b_w) + v3.transformed_normal * (w3 / c_w)) * w;
+        let tex_coords = (v1.tex_coords * (w1 / a_w) + v2.tex_coords * (w2 / b_w) + v3.tex_coords * (w3 / c_w)) * w;
+        let tangent = (v1.transformed_tangent * (w1 / a_w) + v2.transformed_tangent * (w2 / b_w) + v3.transformed_tangent * (w3 / c_w)) * w;
         
         let depth = a_screen.z * w1 + b_screen.z * w2 + c_screen.z * w3;
 
-        fragments.push(Fragment::new_with_vertex_position(
+        fragments.push(Fragment::new_with_attributes(
             x as f32, 
             y as f32, 
-            Color::new(255, 255, 255),
             depth,
-            vertex_position
-        ));
+            vertex_position,
+            world_position,
+            normal.normalize(),
+            tex_coords,
+        ).with_tangent(tangent));
       }
     }
   }
@@ -7,8 +7,12 @@ pub struct Vertex {
   pub normal: Vec3,
   pub tex_coords: Vec2,
   pub color: Color,
+  // xyz = dirección de +u sobre la superficie, w = signo de la bitangente; cero si no se generó
+  pub tangent: Vec4,
   pub transformed_position: Vec4,
   pub transformed_normal: Vec3,
+  pub transformed_tangent: Vec4,
+  pub world_position: Vec3,
 }
 
 impl Vertex {
@@ -18,8 +22,11 @@ impl Vertex {
       normal,
       tex_coords,
       color: Color::black(),
+      tangent: Vec4::zeros(),
       transformed_position: Vec4::new(position.x, position.y, position.z, 1.0),
       transformed_normal: normal,
+      transformed_tangent: Vec4::zeros(),
+      world_position: position,
     }
   }
 
@@ -29,8 +36,11 @@ impl Vertex {
       normal: Vec3::new(0.0, 0.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color,
+      tangent: Vec4::zeros(),
       transformed_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      transformed_tangent: Vec4::zeros(),
+      world_position: position,
     }
   }
 
@@ -47,8 +57,11 @@ impl Default for Vertex {
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color: Color::black(),
+      tangent: Vec4::new(1.0, 0.0, 0.0, 1.0),
       transformed_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      transformed_tangent: Vec4::new(1.0, 0.0, 0.0, 1.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
     }
   }
 }
\ No newline at end of file